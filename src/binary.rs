@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+use crate::error::{Error, Error::CatalogFormatError, Result};
+
+const MAGIC: &[u8; 8] = b"CTL10N\x00\x01";
+const HEADER_LEN: usize = MAGIC.len() + 4;
+const ENTRY_LEN: usize = 16;
+
+/// Encode key/value pairs into a binary catalog.
+///
+/// Layout (all integers are little-endian `u32`):
+/// magic, entry count, `count` entries of `(key offset, key length, value offset,
+/// value length)` sorted by key, then the string data. Offsets are relative
+/// to the start of the string data. Identical values are stored once and
+/// share an offset.
+pub(crate) fn encode<'a>(strings: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<u8> {
+    let mut strings: Vec<(&str, &str)> = strings.into_iter().collect();
+    strings.sort_unstable_by_key(|&(key, _)| key);
+
+    let mut entries = Vec::with_capacity(strings.len() * ENTRY_LEN);
+    let mut data = Vec::new();
+    let mut value_offsets = HashMap::new();
+    let push = |s: &str, data: &mut Vec<u8>| {
+        let offset = data.len() as u32;
+        data.extend_from_slice(s.as_bytes());
+        (offset, s.len() as u32)
+    };
+    for (key, value) in &strings {
+        let (key_off, key_len) = push(key, &mut data);
+        let (val_off, val_len) = *value_offsets
+            .entry(*value)
+            .or_insert_with(|| push(value, &mut data));
+        for n in &[key_off, key_len, val_off, val_len] {
+            entries.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+
+    let mut result = Vec::with_capacity(HEADER_LEN + entries.len() + data.len());
+    result.extend_from_slice(MAGIC);
+    result.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+    result.extend_from_slice(&entries);
+    result.extend_from_slice(&data);
+    result
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+}
+
+/// Read-only view of a binary catalog produced by the binary backend.
+///
+/// Lookup is a binary search over the sorted key table, so it doesn't depend
+/// on the number of macro arms the compiler has to chew through.
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryCatalog<'a> {
+    entries: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> BinaryCatalog<'a> {
+    /// Validate and wrap catalog bytes
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(CatalogFormatError);
+        }
+        let count = read_u32(bytes, MAGIC.len());
+        let entries_len = count.checked_mul(ENTRY_LEN).ok_or(CatalogFormatError)?;
+        if bytes.len() - HEADER_LEN < entries_len {
+            return Err(CatalogFormatError);
+        }
+        let (entries, data) = bytes[HEADER_LEN..].split_at(entries_len);
+        let catalog = Self { entries, data };
+        let mut previous: Option<&str> = None;
+        for idx in 0..count {
+            let key = catalog.checked_str(idx, 0)?;
+            catalog.checked_str(idx, 8)?;
+            if let Some(prev) = previous {
+                if prev >= key {
+                    return Err(CatalogFormatError);
+                }
+            }
+            previous = Some(key);
+        }
+        Ok(catalog)
+    }
+
+    /// Read catalog from file. The file contents are leaked to give the catalog
+    /// a `'static` lifetime, so this is meant to be called once per locale.
+    pub fn load(path: impl AsRef<Path>) -> Result<BinaryCatalog<'static>> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| Error::from(err).in_file(path))?;
+        let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        BinaryCatalog::from_bytes(bytes).map_err(|err| err.in_file(path))
+    }
+
+    fn checked_str(&self, idx: usize, field: usize) -> Result<&'a str> {
+        let at = idx * ENTRY_LEN + field;
+        let (offset, len) = (read_u32(self.entries, at), read_u32(self.entries, at + 4));
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or(CatalogFormatError)?;
+        std::str::from_utf8(bytes).map_err(|_| CatalogFormatError)
+    }
+
+    fn str_at(&self, idx: usize, field: usize) -> &'a str {
+        // Validated in `from_bytes`
+        self.checked_str(idx, field).unwrap()
+    }
+
+    /// Number of strings in catalog
+    pub fn len(&self) -> usize {
+        self.entries.len() / ENTRY_LEN
+    }
+
+    /// Whether catalog contains no strings
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get string for given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.find(|stored| stored.cmp(key))
+    }
+
+    /// Get string for key `name` in `namespace`, i.e. `namespace.name`, without joining them
+    pub fn get_in(&self, namespace: &str, name: &str) -> Option<&'a str> {
+        let key = || namespace.bytes().chain(std::iter::once(b'.')).chain(name.bytes());
+        self.find(|stored| stored.bytes().cmp(key()))
+    }
+
+    /// Binary search for value of the key `compare` returns `Equal` for
+    fn find(&self, compare: impl Fn(&str) -> std::cmp::Ordering) -> Option<&'a str> {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match compare(self.str_at(mid, 0)) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(self.str_at(mid, 8)),
+            }
+        }
+        None
+    }
+
+    /// Iterate over key/value pairs in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let catalog = *self;
+        (0..self.len()).map(move |idx| (catalog.str_at(idx, 0), catalog.str_at(idx, 8)))
+    }
+}
+
+/// Catalog used by the code generated with the binary backend.
+///
+/// Holds the default catalog embedded via `include_bytes!()`, catalogs of other
+/// embedded locales (with [`Builder::runtime_locales`]) and optionally
+/// a replacement loaded at runtime (for example, with [`BinaryCatalog::load`]).
+///
+/// [`Builder::runtime_locales`]: crate::Builder::runtime_locales
+#[derive(Debug)]
+pub struct ActiveCatalog {
+    embedded: &'static [u8],
+    locales: &'static [(&'static str, &'static [u8])],
+    parsed: OnceLock<BinaryCatalog<'static>>,
+    replacement: RwLock<Option<(Option<&'static str>, BinaryCatalog<'static>)>>,
+}
+
+impl ActiveCatalog {
+    /// Create catalog from embedded bytes. Bytes are validated on first use.
+    pub const fn new(embedded: &'static [u8]) -> Self {
+        Self::with_locales(embedded, &[])
+    }
+
+    /// Create catalog from embedded default catalog and named catalogs of locales
+    /// which can be selected with [`set_locale`](Self::set_locale)
+    pub const fn with_locales(
+        embedded: &'static [u8],
+        locales: &'static [(&'static str, &'static [u8])],
+    ) -> Self {
+        Self {
+            embedded,
+            locales,
+            parsed: OnceLock::new(),
+            replacement: RwLock::new(None),
+        }
+    }
+
+    /// Create catalog from embedded catalogs of locales, using the one at index `default`
+    /// until another is selected, so its bytes are only embedded once
+    /// # Panics
+    /// If `default` is out of bounds, which fails compilation when called in a `static`
+    pub const fn with_default_locale(locales: &'static [(&'static str, &'static [u8])], default: usize) -> Self {
+        Self::with_locales(locales[default].1, locales)
+    }
+
+    /// Currently used catalog
+    pub fn current(&self) -> BinaryCatalog<'static> {
+        if let Some((_, catalog)) = *self.replacement.read().unwrap() {
+            return catalog;
+        }
+        *self.parsed.get_or_init(|| {
+            BinaryCatalog::from_bytes(self.embedded).expect("embedded ctl10n catalog is corrupted")
+        })
+    }
+
+    /// Get string for given key from currently used catalog
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.current().get(key)
+    }
+
+    /// Get string for key `name` in `namespace` from currently used catalog
+    pub fn get_in(&self, namespace: &str, name: &str) -> Option<&'static str> {
+        self.current().get_in(namespace, name)
+    }
+
+    /// Iterate over key/value pairs of currently used catalog in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.current().iter()
+    }
+
+    /// Use given catalog instead of the embedded one
+    pub fn replace(&self, catalog: BinaryCatalog<'static>) {
+        *self.replacement.write().unwrap() = Some((None, catalog));
+    }
+
+    /// Switch back to the embedded catalog
+    pub fn reset(&self) {
+        *self.replacement.write().unwrap() = None;
+    }
+
+    /// Names of embedded locales
+    pub fn locales(&self) -> impl Iterator<Item = &'static str> {
+        self.locales.iter().map(|(locale, _)| *locale)
+    }
+
+    /// Embedded locale currently in use, `None` if it's the default catalog or a
+    /// catalog set via [`replace`](Self::replace)
+    pub fn locale(&self) -> Option<&'static str> {
+        self.replacement.read().unwrap().and_then(|(locale, _)| locale)
+    }
+
+    /// Catalog of embedded locale with given name, for looking strings up without
+    /// switching the current locale (e.g. per request in a web service)
+    pub fn catalog(&self, locale: &str) -> Option<BinaryCatalog<'static>> {
+        let (_, bytes) = self.locales.iter().find(|(name, _)| *name == locale)?;
+        Some(BinaryCatalog::from_bytes(bytes).expect("embedded ctl10n catalog is corrupted"))
+    }
+
+    /// Best embedded locale for `requested` tags in order of preference,
+    /// see [`negotiate_locale`](crate::negotiate_locale)
+    pub fn negotiate(&self, requested: &[&str]) -> Option<&'static str> {
+        let available: Vec<&'static str> = self.locales().collect();
+        crate::negotiate::negotiate(requested.iter().copied(), &available)
+    }
+
+    /// Switch to embedded locale with given name. Returns `false` if there is no such locale.
+    pub fn set_locale(&self, locale: &str) -> bool {
+        match self.locales.iter().find(|(name, _)| *name == locale) {
+            Some((name, _)) => {
+                let catalog = self.catalog(name).unwrap();
+                *self.replacement.write().unwrap() = Some((Some(name), catalog));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Switch to embedded locale best matching OS settings (see [`system_locales`]),
+    /// keeping the default catalog if none matches. Returns selected locale.
+    ///
+    /// [`system_locales`]: crate::system_locales
+    #[cfg(feature = "detect")]
+    pub fn detect_locale(&self) -> Option<&'static str> {
+        let available: Vec<&'static str> = self.locales().collect();
+        let system = crate::detect::system_locales();
+        let locale = crate::negotiate::negotiate(system.iter().map(String::as_str), &available)?;
+        self.set_locale(locale);
+        Some(locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog_bytes() -> Vec<u8> {
+        encode(vec![("ok", "OK"), ("cancel", "Cancel"), ("yes", "OK"), ("empty", "")])
+    }
+
+    /// Overwrite `u32` at `at` of `bytes`
+    fn patch(bytes: &mut [u8], at: usize, value: u32) {
+        bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = catalog_bytes();
+        let catalog = BinaryCatalog::from_bytes(&bytes).unwrap();
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(catalog.get("ok"), Some("OK"));
+        assert_eq!(catalog.get("yes"), Some("OK"));
+        assert_eq!(catalog.get("empty"), Some(""));
+        assert_eq!(catalog.get("missing"), None);
+        let keys: Vec<&str> = catalog.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["cancel", "empty", "ok", "yes"]);
+    }
+
+    #[test]
+    fn namespaced_keys() {
+        let bytes = encode(vec![("errors.not-found", "Not found"), ("errors", "Errors"), ("errors-x.y", "Y")]);
+        let catalog = BinaryCatalog::from_bytes(&bytes).unwrap();
+        assert_eq!(catalog.get_in("errors", "not-found"), Some("Not found"));
+        assert_eq!(catalog.get_in("errors-x", "y"), Some("Y"));
+        assert_eq!(catalog.get_in("errors", "y"), None);
+        assert_eq!(catalog.get_in("", "errors"), None);
+    }
+
+    #[test]
+    fn shared_values_are_stored_once() {
+        let shared = encode(vec![("a", "value"), ("b", "value")]);
+        let distinct = encode(vec![("a", "value"), ("b", "other")]);
+        assert_eq!(distinct.len() - shared.len(), "other".len());
+    }
+
+    #[test]
+    fn default_locale() {
+        let en: &'static [u8] = Box::leak(catalog_bytes().into_boxed_slice());
+        let de: &'static [u8] = Box::leak(encode(vec![("ok", "Gut")]).into_boxed_slice());
+        let locales: &'static [(&str, &[u8])] = Box::leak(Box::new([("de", de), ("en", en)]));
+        let catalog = ActiveCatalog::with_default_locale(locales, 1);
+        assert_eq!(catalog.get("ok"), Some("OK"));
+        assert!(catalog.set_locale("de"));
+        assert_eq!(catalog.get("ok"), Some("Gut"));
+    }
+
+    #[test]
+    fn empty_catalog() {
+        let bytes = encode(Vec::new());
+        let catalog = BinaryCatalog::from_bytes(&bytes).unwrap();
+        assert!(catalog.is_empty());
+        assert_eq!(catalog.get("ok"), None);
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let mut bytes = catalog_bytes();
+        bytes[0] = b'X';
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes[..HEADER_LEN - 1]), Err(CatalogFormatError)));
+    }
+
+    #[test]
+    fn truncated_entries() {
+        let bytes = catalog_bytes();
+        let truncated = &bytes[..HEADER_LEN + ENTRY_LEN];
+        assert!(matches!(BinaryCatalog::from_bytes(truncated), Err(CatalogFormatError)));
+        let mut bytes = bytes;
+        patch(&mut bytes, MAGIC.len(), u32::MAX);
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+    }
+
+    #[test]
+    fn corrupted_offsets() {
+        let mut bytes = catalog_bytes();
+        // Value offset of the first entry
+        patch(&mut bytes, HEADER_LEN + 8, 1 << 20);
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+        let mut bytes = catalog_bytes();
+        // Offset plus length of the first key overflows
+        patch(&mut bytes, HEADER_LEN, u32::MAX);
+        patch(&mut bytes, HEADER_LEN + 4, u32::MAX);
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+    }
+
+    #[test]
+    fn unsorted_keys() {
+        let mut bytes = catalog_bytes();
+        // Point the second key at the first one, so keys aren't strictly increasing
+        let first_key = read_u32(&bytes, HEADER_LEN) as u32;
+        let first_len = read_u32(&bytes, HEADER_LEN + 4) as u32;
+        patch(&mut bytes, HEADER_LEN + ENTRY_LEN, first_key);
+        patch(&mut bytes, HEADER_LEN + ENTRY_LEN + 4, first_len);
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut bytes = encode(vec![("key", "é")]);
+        let last = bytes.len() - 1;
+        bytes[last] = 0xff;
+        assert!(matches!(BinaryCatalog::from_bytes(&bytes), Err(CatalogFormatError)));
+    }
+}
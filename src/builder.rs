@@ -0,0 +1,2368 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::binary;
+use crate::cache::{self, Cache};
+use crate::catalog::{Catalog, CatalogEntry};
+use crate::cfg;
+use crate::codegen::{self, Dedup};
+use crate::error::{Error, Result};
+use crate::escapes::Escapes;
+use crate::fingerprint::catalog_fingerprint;
+use crate::json;
+use crate::key_style::KeyStyle;
+use crate::lockfile;
+use crate::lint::{self, Lint, Lints, Severity};
+use crate::locales::{self, LocaleOutput};
+use crate::manifest;
+use crate::markdown;
+use crate::metadata::{self, Input};
+use crate::list::ListFormat;
+use crate::number::NumberFormat;
+use crate::placeholder::{parse_placeholders, with_specs, Placeholder};
+use crate::plural::PluralKind;
+use crate::pot;
+use crate::report::GenerationReport;
+use crate::schema;
+use crate::snapshot;
+use crate::stats::{self, LocaleStats};
+use crate::template::Template;
+use crate::toml_parser::{decode, parse_toml, Document, Limits, ParseOptions};
+use crate::whitespace::Whitespace;
+
+/// Kind of code generated from strings file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// `tr!()` expands to string literals. Unknown keys and invalid arguments
+    /// are compile-time errors.
+    #[default]
+    Macro,
+    /// Strings are stored in a binary catalog next to the generated file and
+    /// embedded via `include_bytes!()`. `tr!()` looks them up at runtime, so
+    /// compile times don't grow with the number of keys and the catalog can
+    /// be replaced at runtime with a file loaded by [`BinaryCatalog::load`].
+    ///
+    /// Generated code defines `static CTL10N_CATALOG: ctl10n::ActiveCatalog`
+    /// and requires `ctl10n` in `dependencies`. Unknown keys evaluate to the
+    /// key itself and `tr!()` with arguments uses [`format_named`]. Array values
+    /// aren't stored in the catalog, `tr_list!()` is generated as with [`Backend::Macro`].
+    ///
+    /// [`BinaryCatalog::load`]: crate::BinaryCatalog::load
+    /// [`format_named`]: crate::format_named
+    Binary,
+}
+
+/// Configurable strings file converter
+///
+/// ```no_run
+/// use ctl10n::{Backend, Builder};
+///
+/// Builder::new()
+///     .backend(Backend::Binary)
+///     .convert_strings_file("strings.toml", "target/strings.rs")
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+    backend: Backend,
+    pretty: bool,
+    parse_options: ParseOptions,
+    locale_selection: LocaleSelection,
+    normalize_locale_names: bool,
+    codegen: codegen::Options,
+    manifest: bool,
+    fingerprints: bool,
+    env_overrides: bool,
+    length_limits: Severity,
+    lints: Lints,
+    allow_untranslated: BTreeSet<String>,
+    key_style: Option<KeyStyle>,
+    include_duplicates: Severity,
+    print_warnings: bool,
+    fail_on_warnings: bool,
+    format_tests: bool,
+    number_format: bool,
+    list_format: bool,
+    truncation: bool,
+    parallel: bool,
+    hot_reload: bool,
+    json_bundle: bool,
+    string_catalog: bool,
+    split_prefixes: Vec<String>,
+    macro_budget: Option<usize>,
+    auto_binary: bool,
+    incremental: bool,
+    lockfile: Option<PathBuf>,
+    show_keys: bool,
+    locked_keys: Severity,
+    #[cfg(feature = "normalize")]
+    normalization: Option<crate::NormalizationForm>,
+    source_locale: Option<String>,
+    /// Limits from other locales, set by `convert_locales`
+    shared_limits: Limits,
+    fill_missing: bool,
+    /// Template set with `template` or, during `convert_locales`, the source locale
+    template: Option<Template>,
+    out_dir: Option<PathBuf>,
+    locale_file_name: String,
+}
+
+/// How [`Builder::convert_locales`] chooses between generated locales
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LocaleSelection {
+    /// User includes locale file manually
+    Manual,
+    /// Via cargo features
+    Features,
+    /// At runtime, starting with given default locale
+    Runtime(String),
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            pretty: true,
+            parse_options: ParseOptions::default(),
+            locale_selection: LocaleSelection::Manual,
+            normalize_locale_names: false,
+            codegen: codegen::Options::default(),
+            manifest: false,
+            fingerprints: false,
+            env_overrides: false,
+            length_limits: Severity::Deny,
+            lints: lint::default_lints(),
+            allow_untranslated: BTreeSet::new(),
+            key_style: None,
+            include_duplicates: Severity::Deny,
+            print_warnings: true,
+            fail_on_warnings: false,
+            format_tests: false,
+            number_format: false,
+            list_format: false,
+            truncation: false,
+            parallel: false,
+            hot_reload: false,
+            json_bundle: false,
+            string_catalog: false,
+            split_prefixes: Vec::new(),
+            macro_budget: None,
+            auto_binary: false,
+            incremental: false,
+            lockfile: None,
+            show_keys: false,
+            locked_keys: Severity::Deny,
+            #[cfg(feature = "normalize")]
+            normalization: None,
+            source_locale: None,
+            shared_limits: Limits::new(),
+            fill_missing: false,
+            template: None,
+            out_dir: None,
+            locale_file_name: "strings_{locale}.rs".to_string(),
+        }
+    }
+}
+
+impl Builder {
+    /// Create builder with default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set kind of generated code. Default is [`Backend::Macro`].
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Format generated code with `prettyplease`, so it's readable when inspecting
+    /// `$OUT_DIR`. Default is `true`. Has no effect if the `pretty` feature is disabled.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Accept integer, float and boolean values, converting them to strings
+    /// (`answer = 42` is the same as `answer = "42"`). Default is `false`.
+    ///
+    /// ```
+    /// let code = ctl10n::Builder::new()
+    ///     .lenient_values(true)
+    ///     .gen_strings_macro("answer = 42\npi = 3.0")
+    ///     .unwrap();
+    /// assert!(code.contains(r#""42""#) && code.contains(r#""3.0""#));
+    /// ```
+    pub fn lenient_values(mut self, lenient: bool) -> Self {
+        self.parse_options.lenient_values = lenient;
+        self
+    }
+
+    /// Treat array values as lines of a single message, joining them with
+    /// `separator` (usually `"\n"`), instead of generating `tr_list!()` for them.
+    ///
+    /// ```
+    /// let code = ctl10n::Builder::new()
+    ///     .join_arrays("\n")
+    ///     .gen_strings_macro(r#"help = ["First paragraph.", "", "Second paragraph."]"#)
+    ///     .unwrap();
+    /// assert!(code.contains(r#""First paragraph.\n\nSecond paragraph.""#));
+    /// ```
+    pub fn join_arrays(mut self, separator: impl Into<String>) -> Self {
+        self.parse_options.join_arrays = Some(separator.into());
+        self
+    }
+
+    /// Clean up whitespace of values, e.g. indentation of multi-line TOML strings, according
+    /// to `whitespace`. Keys can use another mode in the reserved `[whitespace]` table of
+    /// the strings file defining them. Default is [`Whitespace::Keep`].
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Whitespace};
+    ///
+    /// let strings = "about = \"\"\"\n    Long\n    message.\n    \"\"\"\n[whitespace]\nabout = \"collapse\"";
+    /// assert!(Builder::new().gen_strings_macro(strings).unwrap().contains(r#""Long message.""#));
+    /// let code = Builder::new()
+    ///     .whitespace(Whitespace::Dedent)
+    ///     .gen_strings_macro("about = \"\"\"\n    Long\n    message.\"\"\"")
+    ///     .unwrap();
+    /// assert!(code.contains(r#""Long\nmessage.""#));
+    /// ```
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.parse_options.whitespace = whitespace;
+        self
+    }
+
+    /// What to do with backslash sequences like `\n` left in values and control characters,
+    /// e.g. [`Escapes::Forbid`] for single-line GUI labels. [`Lint::SuspiciousEscape`] can
+    /// report sequences without changing them. Default is [`Escapes::Keep`].
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Escapes};
+    ///
+    /// let code = Builder::new().escapes(Escapes::Unescape).gen_strings_macro(r"tip = 'a\tb'").unwrap();
+    /// assert!(code.contains(r#""a\tb""#));
+    /// assert!(Builder::new().escapes(Escapes::Forbid).gen_strings_macro(r#"tip = "a\tb""#).is_err());
+    /// ```
+    pub fn escapes(mut self, escapes: Escapes) -> Self {
+        self.parse_options.escapes = escapes;
+        self
+    }
+
+    /// Make [`convert_locales`](Self::convert_locales) also generate `$OUT_DIR/strings.rs`,
+    /// which includes the locale selected via cargo feature `locale-<locale>`
+    /// (lowercase, with `_` replaced by `-`), so a binary can be built per-locale with
+    /// `cargo build --no-default-features --features locale-de`. Features must be
+    /// declared in `Cargo.toml` and exactly one of them must be enabled. It also defines
+    /// `enum Locale` with a variant per locale (`PtBr` for `pt-BR`), `Locale::ALL`,
+    /// `as_tag()`, `from_tag()` and `const fn current()` returning the enabled one.
+    /// Default is `false`. Overrides [`runtime_locales`](Self::runtime_locales).
+    pub fn locale_features(mut self, locale_features: bool) -> Self {
+        self.locale_selection = if locale_features {
+            LocaleSelection::Features
+        } else {
+            LocaleSelection::Manual
+        };
+        self
+    }
+
+    /// Make [`convert_locales`](Self::convert_locales) also generate `$OUT_DIR/strings.rs`
+    /// embedding binary catalogs of all locales, with `default_locale` used at startup.
+    /// It defines `tr!()` as with [`Backend::Binary`] and
+    /// `static CTL10N_CATALOG: ctl10n::ActiveCatalog`, which can switch between locales
+    /// with [`ActiveCatalog::set_locale`], and `fn negotiate_locale(&[&str]) -> &'static str`
+    /// picking the best locale for given tags. `enum Locale` is defined as with
+    /// [`locale_features`](Self::locale_features), with `Locale::current()` returning the
+    /// selected locale and `set_current()` switching to it. Overrides
+    /// [`locale_features`](Self::locale_features).
+    ///
+    /// ```ignore
+    /// ctl10n::include_strings!("strings.rs");
+    ///
+    /// Locale::PtBr.set_current();
+    /// assert_eq!(Locale::current().as_tag(), "pt-BR");
+    /// ```
+    ///
+    /// [`ActiveCatalog::set_locale`]: crate::ActiveCatalog::set_locale
+    pub fn runtime_locales(mut self, default_locale: impl Into<String>) -> Self {
+        self.locale_selection = LocaleSelection::Runtime(default_locale.into());
+        self
+    }
+
+    /// Replace underscores with dashes in locale file names found by
+    /// [`convert_locales`](Self::convert_locales), so `en_US.toml` is locale `en-US`
+    /// instead of an error. Default is `false`.
+    pub fn normalize_locale_names(mut self, normalize: bool) -> Self {
+        self.normalize_locale_names = normalize;
+        self
+    }
+
+    /// Mark generated macros with `#[macro_export]` and use `$crate::` paths inside them,
+    /// so a crate including strings can expose `tr!()` to other crates. Macros exported from
+    /// an included file can't be called by `$crate::` paths in the crate itself, so it gets
+    /// copies of the macros which aren't exported and it calls `tr!()` as usual, but its own
+    /// exported macros can't call `$crate::tr!()`. Generated file must be included in the
+    /// crate root; with [`Backend::Binary`] it also re-exports `ctl10n` as hidden `__ctl10n`,
+    /// so users of the macro don't need to depend on it. Standard macros are always called by
+    /// full path, so macros named `format` or `concat` at call site don't break `tr!()`.
+    /// Default is `false`.
+    pub fn macro_export(mut self, macro_export: bool) -> Self {
+        self.codegen.macro_export = macro_export;
+        self
+    }
+
+    /// Generate macros 2.0 (`pub macro tr { ... }`) instead of `macro_rules!`, so they're
+    /// scoped like other items and can be imported by path (`use my_strings::tr;`).
+    /// Requires nightly and `#![feature(decl_macro)]` in the including crate. With
+    /// [`Backend::Binary`] generated file re-exports `ctl10n` as hidden `__ctl10n`.
+    /// Makes [`macro_export`](Self::macro_export) unnecessary. Default is `false`.
+    pub fn decl_macro(mut self, decl_macro: bool) -> Self {
+        self.codegen.decl_macro = decl_macro;
+        self
+    }
+
+    /// Make [`convert_strings_file`](Self::convert_strings_file) also write
+    /// `<name>.manifest.json` next to `<name>.rs`, listing every key with its kind
+    /// (`string` or `list`), placeholders (e.g. `{name}`, `{0}`, `{}`) and file and
+    /// line/column defining it, which differs from the source file for keys of included and
+    /// overrides files. Errors about keys are reported at the same location, like
+    /// `locales/de.toml:42: ...`. Default is `false`.
+    ///
+    /// ```json
+    /// {
+    ///   "version": 1,
+    ///   "source": "strings.toml",
+    ///   "keys": [
+    ///     { "key": "greeting", "kind": "string", "placeholders": ["{name}"], "file": "strings.toml", "line": 1, "column": 1 }
+    ///   ]
+    /// }
+    /// ```
+    pub fn manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Also write strings as a flat JSON object of keys and values (arrays for array values),
+    /// so a JavaScript or WASM frontend can use the same strings files. It's written to
+    /// `strings.<locale>.json` next to the generated code of every locale or, for a single
+    /// strings file, to `<name>.json` next to `<name>.rs`. Values are written after
+    /// [filling missing keys](Self::fill_missing) and applying overrides, with placeholders
+    /// as in strings files. Default is `false`.
+    ///
+    /// ```json
+    /// {
+    ///   "greeting": "Hello, {name}!",
+    ///   "tips": ["first tip", "second tip"]
+    /// }
+    /// ```
+    pub fn json_bundle(mut self, json_bundle: bool) -> Self {
+        self.json_bundle = json_bundle;
+        self
+    }
+
+    /// Also generate `fn string_catalog() -> ctl10n::StringCatalog` returning all strings
+    /// available via `tr!()`, e.g. for sending them to clients. Generated code requires
+    /// `ctl10n` in `dependencies`. Default is `false`.
+    ///
+    /// ```
+    /// let code = ctl10n::Builder::new().string_catalog(true).gen_strings_macro(r#"ok = "OK""#).unwrap();
+    /// assert!(code.contains("fn string_catalog"));
+    /// ```
+    pub fn string_catalog(mut self, string_catalog: bool) -> Self {
+        self.string_catalog = string_catalog;
+        self
+    }
+
+    /// Also generate a file for each of namespaces `prefixes` with only the keys in it, so
+    /// crates or modules of a workspace can include the subset they need and compile smaller
+    /// macros. Keys are used by their full names, e.g. `tr!("errors.not-found")` from
+    /// `strings_errors.rs` generated for `errors` next to `strings.rs`. Locale files get
+    /// parts as well, and with [`locale_features`](Self::locale_features) so does the file
+    /// switching between them. With [`runtime_locales`](Self::runtime_locales) strings are
+    /// looked up at runtime, so its `strings.rs` isn't split. Default is empty.
+    ///
+    /// ```no_run
+    /// ctl10n::Builder::new()
+    ///     .split_prefixes(vec!["errors", "ui"])
+    ///     .convert_default_strings_file()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// ```ignore
+    /// mod errors {
+    ///     ctl10n::include_strings!("strings_errors.rs");
+    /// }
+    /// ```
+    pub fn split_prefixes<I>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.split_prefixes.extend(prefixes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skip writing code of locales converted by [`convert_locales`](Self::convert_locales)
+    /// whose inputs didn't change since the last build, which makes incremental builds
+    /// with many locales almost free. Hashes of contents of every file read (includes,
+    /// overrides, the source locale and the lockfile too), of every locale after filling
+    /// missing keys and of builder options are stored in `ctl10n-cache.json` in the output
+    /// directory, along with files written for each locale. When none of the files changed
+    /// and every output exists, strings files aren't even parsed, so their warnings are only
+    /// reported by the build which generated them. [`env_overrides`](Self::env_overrides)
+    /// disables the shortcut. Default is `false`.
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-incremental");
+    /// std::fs::create_dir_all(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), "ok = \"OK\"").unwrap();
+    /// let builder = ctl10n::Builder::new().incremental(true).manifest(true).out_dir(&dir);
+    /// builder.convert_locales(dir.join("locales")).unwrap();
+    /// // Files written with the code are written again if they're missing
+    /// std::fs::remove_file(dir.join("strings_en.manifest.json")).unwrap();
+    /// builder.convert_locales(dir.join("locales")).unwrap();
+    /// assert!(dir.join("strings_en.manifest.json").exists());
+    /// std::fs::write(dir.join("locales/en.toml"), "ok = \"Okay\"").unwrap();
+    /// builder.convert_locales(dir.join("locales")).unwrap();
+    /// assert!(std::fs::read_to_string(dir.join("strings_en.rs")).unwrap().contains("Okay"));
+    /// ```
+    pub fn incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Warn when `tr!()` generated with [`Backend::Macro`] would have more than `max_arms`
+    /// arms, suggesting [`Backend::Binary`] or [splitting](Self::split_prefixes), since huge
+    /// macros slow down compilation of every crate using them. Number of arms and bytes of
+    /// literals of the largest generated macro are in [`GenerationReport`] either way.
+    /// Default is no budget.
+    ///
+    /// ```
+    /// let builder = ctl10n::Builder::new().print_warnings(false).macro_budget(10);
+    /// let strings: String = (0..20).map(|idx| format!("key-{} = \"Value {}\"\n", idx, idx)).collect();
+    /// let dir = std::env::temp_dir().join("ctl10n-macro-budget");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("strings.toml"), strings).unwrap();
+    /// let report = builder.convert_strings_file(dir.join("strings.toml"), dir.join("strings.rs")).unwrap();
+    /// assert!(report.macro_arms > 20);
+    /// assert!(report.warnings[0].contains("consider Backend::Binary"));
+    /// ```
+    pub fn macro_budget(mut self, max_arms: usize) -> Self {
+        self.macro_budget = Some(max_arms);
+        self
+    }
+
+    /// Generate files exceeding [`macro_budget`](Self::macro_budget) with [`Backend::Binary`]
+    /// instead of warning. Strings of such files are looked up at runtime, so they can't be
+    /// used in `concat!()` or attributes and keys must be literals. With
+    /// [`convert_locales`](Self::convert_locales) all locales are generated with
+    /// [`Backend::Binary`] if one of them exceeds the budget. Files with defaults of
+    /// arguments aren't switched, since binary catalogs don't support them. Default is `false`.
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-auto-binary");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let strings: String = (0..20).map(|idx| format!("key-{} = \"Value {}\"\n", idx, idx)).collect();
+    /// std::fs::write(dir.join("en.toml"), strings).unwrap();
+    /// std::fs::write(dir.join("de.toml"), "key-0 = \"Wert 0\"").unwrap();
+    /// ctl10n::Builder::new()
+    ///     .print_warnings(false)
+    ///     .macro_budget(10)
+    ///     .auto_binary(true)
+    ///     .convert_all(&[("en", &dir.join("en.toml")), ("de", &dir.join("de.toml"))], &dir)
+    ///     .unwrap();
+    /// assert!(dir.join("strings_en.bin").exists());
+    /// assert!(dir.join("strings_de.bin").exists());
+    /// ```
+    pub fn auto_binary(mut self, auto_binary: bool) -> Self {
+        self.auto_binary = auto_binary;
+        self
+    }
+
+    /// Add `fingerprint` of every value, as computed by [`fingerprint`](crate::fingerprint),
+    /// to the [`manifest`](Self::manifest). With [`convert_locales`](Self::convert_locales)
+    /// and a [source locale](Self::source_locale) keys also have `source_fingerprint` of
+    /// the source value, so translation tools can mark translations of changed source
+    /// strings as outdated. Default is `false`.
+    pub fn fingerprints(mut self, fingerprints: bool) -> Self {
+        self.fingerprints = fingerprints;
+        self
+    }
+
+    /// Override strings with environment variables `CTL10N_OVERRIDE_<KEY>` at build time,
+    /// where `<KEY>` is the key in uppercase with characters other than ASCII letters and
+    /// digits replaced by `_` (`CTL10N_OVERRIDE_PRODUCT_NAME` for `product-name`).
+    /// Every applied override is reported with `cargo:warning` and
+    /// `cargo:rerun-if-env-changed` is printed for every key. Doesn't affect
+    /// [`gen_strings_macro`](Self::gen_strings_macro). Keys with the same variable, like
+    /// `product-name` and `product_name`, fail generation. Default is `false`.
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-env-overrides");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("strings.toml"), "product-name = \"ctl10n\"\nproduct_name = \"ctl10n\"").unwrap();
+    /// let error = ctl10n::Builder::new()
+    ///     .env_overrides(true)
+    ///     .convert_strings_file(dir.join("strings.toml"), dir.join("strings.rs"))
+    ///     .unwrap_err();
+    /// assert!(error.to_string().ends_with(
+    ///     "strings.toml:2: Keys `product-name` and `product_name` would both be overridden by \
+    ///      CTL10N_OVERRIDE_PRODUCT_NAME, rename one of them"
+    /// ));
+    /// ```
+    pub fn env_overrides(mut self, env_overrides: bool) -> Self {
+        self.env_overrides = env_overrides;
+        self
+    }
+
+    /// What to do when a value is longer than its limit in characters, set in the reserved
+    /// `[limits]` table of strings file. With [`convert_locales`](Self::convert_locales)
+    /// limits set in any locale apply to all of them, unless a locale sets its own.
+    /// For arrays the limit applies to every item. Default is [`Severity::Deny`].
+    ///
+    /// ```
+    /// let strings = "title = \"Einstellungen\"\n[limits]\ntitle = 8";
+    /// assert!(ctl10n::Builder::new().gen_strings_macro(strings).is_err());
+    /// let code = ctl10n::Builder::new()
+    ///     .length_limits(ctl10n::Severity::Allow)
+    ///     .gen_strings_macro(strings)
+    ///     .unwrap();
+    /// assert!(code.contains("Einstellungen"));
+    /// ```
+    pub fn length_limits(mut self, severity: Severity) -> Self {
+        self.length_limits = severity;
+        self
+    }
+
+    /// Check values with `lint` during generation, reporting problems according to
+    /// `severity`. Every lint except [`Lint::InvisibleCharacter`] and
+    /// [`Lint::DuplicateValue`], which warn, is [`Severity::Allow`] by default.
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Lint, Severity};
+    ///
+    /// let builder = Builder::new().lint(Lint::SurroundingWhitespace, Severity::Deny);
+    /// assert!(builder.gen_strings_macro(r#"ok = "OK ""#).is_err());
+    /// assert!(builder.gen_strings_macro(r#"ok = "OK""#).is_ok());
+    /// ```
+    pub fn lint(mut self, lint: Lint, severity: Severity) -> Self {
+        self.lints.insert(lint, severity);
+        self
+    }
+
+    /// Values which may be the same in translations and the source locale, like `OK`,
+    /// so [`Lint::Untranslated`] doesn't report them. Default is empty.
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Lint, Severity};
+    ///
+    /// let dir = std::env::temp_dir().join("ctl10n-allow-untranslated");
+    /// std::fs::create_dir_all(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), "ok = \"OK\"\ncancel = \"Cancel\"").unwrap();
+    /// std::fs::write(dir.join("locales/de.toml"), "ok = \"OK\"\ncancel = \"Abbrechen\"").unwrap();
+    /// let builder = Builder::new()
+    ///     .source_locale("en")
+    ///     .lint(Lint::Untranslated, Severity::Deny)
+    ///     .out_dir(&dir);
+    /// let error = builder.clone().convert_locales(dir.join("locales")).unwrap_err();
+    /// assert!(error.to_string().contains("`ok`"));
+    /// builder.allow_untranslated(["OK", "Email"]).convert_locales(dir.join("locales")).unwrap();
+    /// ```
+    pub fn allow_untranslated<I>(mut self, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.allow_untranslated.extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    /// Naming convention keys of strings and arrays must follow, checked by [`Lint::KeyStyle`].
+    /// Unless the lint's severity is set with [`lint`](Self::lint), keys violating it fail
+    /// generation. Suggested names are listed in [`GenerationReport::key_renames`].
+    /// Default is no convention.
+    pub fn key_style(mut self, style: KeyStyle) -> Self {
+        self.key_style = Some(style);
+        self.lints.entry(Lint::KeyStyle).or_insert(Severity::Deny);
+        self
+    }
+
+    /// Normalize all values to given Unicode normalization form during generation, so
+    /// strings compare equal regardless of how translator's editor composed them.
+    /// Requires the `normalize` feature. Default is no normalization.
+    ///
+    /// ```
+    /// let code = ctl10n::Builder::new()
+    ///     .normalize_unicode(ctl10n::NormalizationForm::Nfc)
+    ///     .gen_strings_macro(r#"cafe = "cafe\u0301""#)
+    ///     .unwrap();
+    /// assert!(code.contains("caf\u{e9}"));
+    /// ```
+    #[cfg(feature = "normalize")]
+    pub fn normalize_unicode(mut self, form: crate::NormalizationForm) -> Self {
+        self.normalization = Some(form);
+        self
+    }
+
+    /// What to do when a key from a file listed in `include = ["../common.toml"]` array
+    /// of strings file is also defined in the file itself or in another included file.
+    /// Unless denied, keys of the including file win over included ones and later
+    /// includes win over earlier ones. Include paths are relative to the including file
+    /// and `cargo:rerun-if-changed` is printed for every included file.
+    /// Default is [`Severity::Deny`].
+    pub fn include_duplicates(mut self, severity: Severity) -> Self {
+        self.include_duplicates = severity;
+        self
+    }
+
+    /// Locale the others are translated from, which is the [`Template`] of
+    /// [`convert_locales`](Self::convert_locales) and is used for checks comparing translations
+    /// to it, like [`Lint::TerminalPunctuation`]. Values of translations must use arguments
+    /// declared with `args` in the source locale, which translations can't redeclare differently.
+    /// Defaults to the default locale of [`runtime_locales`](Self::runtime_locales), if any.
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-source-locale");
+    /// std::fs::create_dir_all(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), r#"items = { value = "{count} items", args = ["count"] }"#).unwrap();
+    /// std::fs::write(dir.join("locales/de.toml"), r#"items = { value = "{n} Dinge", args = ["n"] }"#).unwrap();
+    /// let error = ctl10n::Builder::new().source_locale("en").out_dir(&dir).convert_locales(dir.join("locales")).unwrap_err();
+    /// assert!(error.to_string().ends_with("de.toml:1: Invalid metadata for key `items`: args {n} differ from args {count} declared in the template"));
+    /// ```
+    pub fn source_locale(mut self, locale: impl Into<String>) -> Self {
+        self.source_locale = Some(locale.into());
+        self
+    }
+
+    /// Check translations against `template` instead of the [source locale](Self::source_locale).
+    /// Unlike the source locale, it's also used by [`convert_strings_file`](Self::convert_strings_file),
+    /// so a single translation can be filled from and checked against it. Default is no template.
+    pub fn template(mut self, template: Template) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Read [`Template`] from strings file `path`, with includes and overrides applied.
+    /// Its locale is the file stem, e.g. `en` for `locales/en.toml`.
+    pub fn load_template(&self, path: impl AsRef<Path>) -> Result<Template> {
+        let path = path.as_ref();
+        let mut report = GenerationReport::default();
+        let document = self.read_document(path, &mut report);
+        let document = self.finish(&report, document)?;
+        let locale = path.file_stem().unwrap_or_default().to_string_lossy();
+        Ok(Template::new(locale, path, document))
+    }
+
+    /// Make [`convert_locales`](Self::convert_locales) fill keys missing from a locale with
+    /// values from the [`Template`], printing `cargo:warning` for every filled key, so
+    /// translations can be added gradually. Requires a [template](Self::template) or
+    /// [source locale](Self::source_locale) to be set. Default is `false`.
+    pub fn fill_missing(mut self, fill_missing: bool) -> Self {
+        self.fill_missing = fill_missing;
+        self
+    }
+
+    /// Print warnings collected in [`GenerationReport`] with `cargo:warning`, so they're
+    /// shown by cargo when building. Default is `true`.
+    pub fn print_warnings(mut self, print_warnings: bool) -> Self {
+        self.print_warnings = print_warnings;
+        self
+    }
+
+    /// Fail generation if there are any warnings, like lint findings with [`Severity::Warn`]
+    /// or keys filled by [`fill_missing`](Self::fill_missing), e.g. for release builds in CI.
+    /// Informational messages like deduplication statistics don't count. Default is `false`.
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Lint, Severity};
+    ///
+    /// let builder = Builder::new().lint(Lint::DoubleSpace, Severity::Warn).print_warnings(false);
+    /// assert!(builder.gen_strings_macro(r#"ok = "O  K""#).is_ok());
+    /// assert!(builder.fail_on_warnings(true).gen_strings_macro(r#"ok = "O  K""#).is_err());
+    /// ```
+    pub fn fail_on_warnings(mut self, fail_on_warnings: bool) -> Self {
+        self.fail_on_warnings = fail_on_warnings;
+        self
+    }
+
+    /// Also generate `#[cfg(test)] mod ctl10n_format_tests` with a test calling `format!()`
+    /// with dummy arguments for every string with placeholders, so `cargo test` checks
+    /// format strings of keys which aren't used with arguments anywhere yet. With
+    /// [`locale_features`](Self::locale_features) or [`runtime_locales`](Self::runtime_locales)
+    /// tests for all locales are put into `strings.rs`. For a single strings file the module
+    /// also uses `tr_static!()` of every string without placeholders in `#[doc]` attributes,
+    /// so it doesn't compile if one isn't a literal. That's skipped with
+    /// [`macro_export`](Self::macro_export) and [`decl_macro`](Self::decl_macro), since macros
+    /// can't be used by name there, and with [`Backend::Binary`], whose strings aren't
+    /// literals. Default is `false`.
+    pub fn format_tests(mut self, format_tests: bool) -> Self {
+        self.format_tests = format_tests;
+        self
+    }
+
+    /// Also generate `trn_num!()`, which works like `tr!()` but formats arguments as numbers
+    /// with decimal and group separators of the locale, so `trn_num!("total", sum = 1234.5)`
+    /// shows `1.234,5` in German. Separators are chosen by [`NumberFormat::for_locale`] from
+    /// the name of strings file and can be set in its reserved `[number]` table. Generated code
+    /// defines `const CTL10N_NUMBER_FORMAT: ctl10n::NumberFormat`, or `fn number_format()`
+    /// returning format of the current locale with [`runtime_locales`](Self::runtime_locales),
+    /// and requires `ctl10n` in `dependencies`. Default is `false`.
+    ///
+    /// ```toml
+    /// [number]
+    /// decimal = ","
+    /// group = " "
+    /// ```
+    ///
+    /// [`NumberFormat::for_locale`]: crate::NumberFormat::for_locale
+    pub fn number_format(mut self, number_format: bool) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Also generate `tr_join!(items)`, joining items into a sentence with separators of the
+    /// locale (`a, b, and c` in English, `a, b et c` in French). Separators are chosen by
+    /// [`ListFormat::for_locale`] from the name of strings file and can be set in its reserved
+    /// `[join]` table. Generated code defines `const CTL10N_LIST_FORMAT: ctl10n::ListFormat`,
+    /// or `fn list_format()` with [`runtime_locales`](Self::runtime_locales), and requires
+    /// `ctl10n` in `dependencies`. Default is `false`.
+    ///
+    /// ```toml
+    /// [join]
+    /// separator = ", "
+    /// last = " et "
+    /// pair = " et "
+    /// ```
+    ///
+    /// [`ListFormat::for_locale`]: crate::ListFormat::for_locale
+    pub fn list_format(mut self, list_format: bool) -> Self {
+        self.list_format = list_format;
+        self
+    }
+
+    /// Also generate `tr_trunc!("key", max, args...)`, which formats the message like `tr!()`
+    /// and shortens it to `max` grapheme clusters with ellipsis of the locale, see
+    /// [`truncate`](crate::truncate). Ellipsis is chosen by [`ellipsis_for_locale`] from the
+    /// name of strings file and can be set in its reserved `[truncate]` table. Generated code
+    /// defines `const CTL10N_ELLIPSIS: &str`, or `fn ellipsis()` with
+    /// [`runtime_locales`](Self::runtime_locales), and requires `ctl10n` in `dependencies`.
+    /// Default is `false`.
+    ///
+    /// ```toml
+    /// [truncate]
+    /// ellipsis = "..."
+    /// ```
+    ///
+    /// [`ellipsis_for_locale`]: crate::ellipsis_for_locale
+    pub fn truncation(mut self, truncation: bool) -> Self {
+        self.truncation = truncation;
+        self
+    }
+
+    /// Read and convert locales on all available cores in [`convert_locales`](Self::convert_locales),
+    /// [`convert_all`](Self::convert_all) and [`load_catalog`](Self::load_catalog), which
+    /// speeds up builds with many large locales. Warnings are still printed and reported
+    /// in order of locales. Default is `false`.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Make `tr!()` read strings again from the strings file whenever it's modified in
+    /// debug builds, falling back to compiled strings, so copy can be tweaked without
+    /// recompiling. Only arguments passed by name are substituted into changed strings,
+    /// other macros like `tr_list!()` and `tr_static!()` use compiled strings. Reloaded values
+    /// are parsed and transformed with options of this builder, such as
+    /// [`whitespace`](Self::whitespace), [`escapes`](Self::escapes) and
+    /// [`show_keys`](Self::show_keys). Release builds aren't affected. Only used with
+    /// [`Backend::Macro`]. Default is `false`.
+    ///
+    /// See [`reload_string`](crate::reload_string) for details.
+    pub fn hot_reload(mut self, hot_reload: bool) -> Self {
+        self.hot_reload = hot_reload;
+        self
+    }
+
+    /// Check keys against lockfile at `path`, listing every key with arguments of its string,
+    /// so keys removed or changed by accident are reported according to
+    /// [`locked_keys`](Self::locked_keys) before translators and downstream locales break.
+    /// The lockfile is written when it doesn't exist, updated with new keys when nothing
+    /// changed and rewritten when environment variable `CTL10N_UPDATE_LOCKFILE` is set.
+    /// [`convert_locales`](Self::convert_locales) and, with a [template](Self::template),
+    /// [`convert_strings_file`](Self::convert_strings_file) check keys of the [`Template`].
+    /// Default is no lockfile.
+    ///
+    /// ```no_run
+    /// ctl10n::Builder::new()
+    ///     .lockfile("ctl10n.lock")
+    ///     .convert_strings_file("strings.toml", "target/strings.rs")
+    ///     .unwrap();
+    /// ```
+    pub fn lockfile(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lockfile = Some(path.into());
+        self
+    }
+
+    /// What to do when a key of the [lockfile](Self::lockfile) was removed or its arguments
+    /// changed. Default is [`Severity::Deny`]. Warnings of [`convert_locales`](Self::convert_locales)
+    /// are in the report of the source locale.
+    ///
+    /// ```
+    /// use ctl10n::{Builder, Severity};
+    ///
+    /// let dir = std::env::temp_dir().join("ctl10n-locked-keys");
+    /// let _ = std::fs::remove_dir_all(&dir);
+    /// std::fs::create_dir_all(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/de.toml"), "ok = \"OK\"\nbye = \"Tschüss\"").unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), "ok = \"OK\"\nbye = \"Bye\"").unwrap();
+    /// let builder = Builder::new().source_locale("en").lockfile(dir.join("ctl10n.lock")).out_dir(&dir);
+    /// builder.convert_locales(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/de.toml"), "ok = \"OK\"").unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), "ok = \"OK\"").unwrap();
+    /// let outputs = builder.locked_keys(Severity::Warn).convert_locales(dir.join("locales")).unwrap();
+    /// assert!(outputs[0].report.warnings.is_empty());
+    /// assert!(outputs[1].report.warnings[0].contains("`bye`"));
+    /// ```
+    pub fn locked_keys(mut self, severity: Severity) -> Self {
+        self.locked_keys = severity;
+        self
+    }
+
+    /// Prefix every value with its key, as in `[settings.title] Settings`, so testers can
+    /// tell which key produced a string on screen when reporting copy bugs. Checks like
+    /// [lints](Self::lint) still see the original values. Environment variable
+    /// `CTL10N_SHOW_KEYS` set to a non-empty value enables it when converting files,
+    /// e.g. `CTL10N_SHOW_KEYS=1 cargo build` for a testing build. Default is `false`.
+    ///
+    /// ```
+    /// let code = ctl10n::Builder::new().show_keys(true).gen_strings_macro(r#"ok = "OK""#).unwrap();
+    /// assert!(code.contains("[ok] OK"));
+    /// ```
+    pub fn show_keys(mut self, show_keys: bool) -> Self {
+        self.show_keys = show_keys;
+        self
+    }
+
+    /// Directory [`convert_locales`](Self::convert_locales) and
+    /// [`convert_default_strings_file`](Self::convert_default_strings_file) write generated
+    /// files to, created if it doesn't exist. Environment variable `CTL10N_OUT_DIR` overrides
+    /// it, e.g. for workspaces generating into a shared location. Files outside of `$OUT_DIR`
+    /// must be included with `include!()` instead of [`include_strings!`](crate::include_strings).
+    /// Default is `$OUT_DIR`.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Path of file generated for every locale by [`convert_locales`](Self::convert_locales)
+    /// and [`convert_all`](Self::convert_all), relative to the output directory, where
+    /// `{locale}` is replaced by locale name. Use `{locale}/strings.rs` for a directory per
+    /// locale. Conversion fails with [`Error::InvalidFileNamePattern`] if the pattern doesn't
+    /// contain `{locale}`. Default is `strings_{locale}.rs`.
+    pub fn locale_file_name(mut self, pattern: impl Into<String>) -> Self {
+        self.locale_file_name = pattern.into();
+        self
+    }
+
+    /// Convert TOML string to Rust source code with `tr!()` macro and `CTL10N_KEYS`
+    /// constant listing all keys. Always uses [`Backend::Macro`], since the binary backend needs a file to write catalog to.
+    pub fn gen_strings_macro(&self, input: &str) -> Result<String> {
+        let mut report = GenerationReport::default();
+        let result = self.gen_strings_macro_inner(input, &mut report);
+        self.finish(&report, result)
+    }
+
+    fn gen_strings_macro_inner(&self, input: &str, report: &mut GenerationReport) -> Result<String> {
+        let mut document = parse_toml(input, &self.parse_options)?;
+        if !document.includes.is_empty() {
+            return Err(Error::InvalidMetadata {
+                key: "include".to_string(),
+                detail: "includes are only supported when converting files".to_string(),
+            });
+        }
+        self.normalize(&mut document);
+        for error in lint::check_limits(&document, &document.limits) {
+            report.report(error, self.length_limits)?;
+        }
+        if let Some(error) = lint::check_args(&document, &document.args).into_iter().next() {
+            return Err(error);
+        }
+        for (severity, error) in lint::check_lints(&document, None, &self.lints, &self.allow_untranslated) {
+            report.report(error, severity)?;
+        }
+        self.check_key_style(&document, Path::new(""), report)?;
+        if self.show_keys {
+            show_keys(&mut document);
+        }
+        let mut tokens = codegen::gen_macro(&document, &self.codegen);
+        tokens.extend(codegen::gen_static(&document, &self.codegen));
+        tokens.extend(codegen::gen_cow(&self.codegen));
+        tokens.extend(codegen::gen_keys(document.in_order(&document.strings).into_iter().map(|(key, _)| key)));
+        tokens.extend(codegen::gen_catalog_version(&catalog_fingerprint(&[("", &document)]), None, &self.codegen));
+        if self.string_catalog {
+            tokens.extend(codegen::gen_string_catalog(&document, false, &self.codegen));
+        }
+        tokens.extend(codegen::gen_lists(&document, &self.codegen));
+        tokens.extend(codegen::gen_formats(&document.formats, &self.codegen));
+        if !document.html.is_empty() || has_plurals(&document) {
+            tokens.extend(codegen::gen_reexport(&self.codegen));
+        }
+        if !document.html.is_empty() {
+            tokens.extend(codegen::gen_html(html_keys(&document), false, &self.codegen));
+        }
+        for kind in PLURAL_KINDS {
+            if !document.plurals(kind).is_empty() {
+                tokens.extend(codegen::gen_plurals(&[("", &document)], kind, None, &self.codegen));
+            }
+        }
+        Ok(codegen::render(tokens, self.pretty))
+    }
+
+    /// Convert given TOML file to Rust source code in given location, providing
+    /// macro `tr!()`. The file may be UTF-8, with or without BOM, or UTF-16.
+    ///
+    /// If there's `<name>.overrides.toml` next to `<name>.toml`, strings from it replace
+    /// the ones from `<name>.toml`, so downstream forks can customize wording without
+    /// touching the upstream file. Overrides file can't define new keys.
+    ///
+    /// Files with `.json` extension are read as nested JSON exported by translation
+    /// management systems like Crowdin or Lokalise. Nested objects are flattened with `.`,
+    /// so plural forms `{ "items": { "one": "...", "other": "..." } }` become keys
+    /// `items.one` and `items.other`. Objects with a `message` string, as in structured
+    /// JSON exports, are single messages whose `maxLength` becomes a [limit](Self::length_limits).
+    pub fn convert_strings_file(
+        &self,
+        toml_file: impl AsRef<Path>,
+        rs_file: impl AsRef<Path>,
+    ) -> Result<GenerationReport> {
+        let mut report = GenerationReport::default();
+        let rs_file = rs_file.as_ref();
+        let result = self.convert_file(toml_file.as_ref(), rs_file, &mut report).and_then(|document| {
+            let template = self.template.as_ref().map_or(&document, Template::document);
+            self.check_lockfile(template, &mut report)?;
+            if self.json_bundle {
+                write_file(&rs_file.with_extension("json"), json::bundle(&document))?;
+            }
+            Ok(document)
+        });
+        self.finish(&report, result)?;
+        Ok(report)
+    }
+
+    /// Convert strings file, returning the document code was generated from
+    fn convert_file(&self, toml_file: &Path, rs_file: &Path, report: &mut GenerationReport) -> Result<Document> {
+        let document = self.read_document(toml_file, report)?;
+        self.convert_document(document, toml_file, rs_file, report)
+    }
+
+    /// Generate code from already read strings file `toml_file`
+    fn convert_document(
+        &self,
+        document: Document,
+        toml_file: &Path,
+        rs_file: &Path,
+        report: &mut GenerationReport,
+    ) -> Result<Document> {
+        let document = self.prepare(document, toml_file, report)?;
+        report_dedup(&document, report);
+        self.write_outputs(&document, toml_file, rs_file, report)?;
+        Ok(document)
+    }
+
+    /// Write code and manifest generated from prepared `document`
+    fn write_outputs(&self, document: &Document, toml_file: &Path, rs_file: &Path, report: &mut GenerationReport) -> Result<()> {
+        if self.manifest {
+            let source = self.template.as_ref().map(Template::document);
+            let manifest = manifest::manifest(document, toml_file, self.fingerprints, source);
+            let manifest = serde_json::to_string_pretty(&manifest).unwrap();
+            write_file(&rs_file.with_extension("manifest.json"), manifest)?;
+        }
+        self.generate(document, toml_file, rs_file, report)?;
+        for prefix in &self.split_prefixes {
+            self.generate(&document.with_prefix(prefix), toml_file, &part_file(rs_file, prefix), report)?;
+        }
+        Ok(())
+    }
+
+    /// Reloading of strings of `document` from `path` with transformations done during generation
+    fn reload(&self, path: String, document: &Document) -> codegen::Reload {
+        #[cfg(feature = "normalize")]
+        let normalization = self.normalization.map(|form| format!("{:?}", form));
+        #[cfg(not(feature = "normalize"))]
+        let normalization = None;
+        let overridden = document
+            .strings
+            .keys()
+            .filter(|key| self.env_overrides && env::var_os(override_var(key)).is_some())
+            .cloned()
+            .collect();
+        codegen::Reload {
+            path,
+            parse_options: self.parse_options.clone(),
+            normalization,
+            show_keys: self.shows_keys(),
+            overridden,
+        }
+    }
+
+    /// Write code providing strings of `document`, read from `toml_file`, to `rs_file`
+    fn generate(&self, document: &Document, toml_file: &Path, rs_file: &Path, report: &mut GenerationReport) -> Result<()> {
+        let backend = self.check_budget(document, rs_file, report);
+        let mut codegen = self.codegen.clone();
+        if self.hot_reload && backend == Backend::Macro {
+            let path = fs::canonicalize(toml_file).map_err(|err| Error::from(err).in_file(toml_file))?;
+            codegen.reload = Some(self.reload(path.display().to_string(), document));
+        }
+        let mut tokens = match backend {
+            Backend::Macro => codegen::gen_macro(document, &codegen),
+            Backend::Binary => {
+                let catalog_file = rs_file.with_extension("bin");
+                write_catalog(&catalog_file, document)?;
+                let catalog_file = fs::canonicalize(&catalog_file)
+                    .map_err(|err| Error::from(err).in_file(&catalog_file))?;
+                codegen::gen_binary_loader(&catalog_file, &[], &codegen)
+            }
+        };
+        tokens.extend(codegen::gen_static(document, &codegen));
+        tokens.extend(codegen::gen_cow(&codegen));
+        tokens.extend(codegen::gen_keys(document.in_order(&document.strings).into_iter().map(|(key, _)| key)));
+        let hash = catalog_fingerprint(&[("", document)]);
+        tokens.extend(codegen::gen_catalog_version(&hash, modified([toml_file]), &codegen));
+        tokens.extend(codegen::gen_lists(document, &codegen));
+        let binary = backend == Backend::Binary;
+        if self.string_catalog {
+            tokens.extend(codegen::gen_string_catalog(document, binary, &codegen));
+        }
+        let reexport = binary || codegen.reload.is_some() || self.truncation;
+        if reexport || !document.html.is_empty() || has_plurals(document) {
+            tokens.extend(codegen::gen_reexport(&codegen));
+        }
+        if !document.html.is_empty() {
+            tokens.extend(codegen::gen_html(html_keys(document), binary, &codegen));
+        }
+        let locale = toml_file.file_stem().unwrap_or_default().to_string_lossy();
+        for kind in PLURAL_KINDS {
+            if !document.plurals(kind).is_empty() {
+                tokens.extend(codegen::gen_plurals(&[(&locale, document)], kind, None, &codegen));
+            }
+        }
+        tokens.extend(codegen::gen_formats(&document.formats, &codegen));
+        if self.number_format {
+            let separators = number_separators(toml_file, document);
+            let separators = (separators.0.as_str(), separators.1.as_str());
+            tokens.extend(codegen::gen_number_format(separators, !binary, &codegen));
+        }
+        if let Some(currency) = &document.currency {
+            let separators = number_separators(toml_file, document);
+            let separators = (separators.0.as_str(), separators.1.as_str());
+            tokens.extend(codegen::gen_currency_format(currency, separators, &codegen));
+        }
+        if self.list_format {
+            let separators = list_separators(toml_file, document);
+            tokens.extend(codegen::gen_list_format(separators.each_ref().map(String::as_str), &codegen));
+        }
+        if self.truncation {
+            tokens.extend(codegen::gen_truncate(&ellipsis(toml_file, document), &codegen));
+        }
+        if self.format_tests {
+            tokens.extend(codegen::gen_format_tests([(None, document)], !binary, &codegen));
+        }
+        write_file(rs_file, codegen::render(tokens, self.pretty))
+    }
+
+    /// Record size of `tr!()` generated for `document` to `rs_file` and check it against
+    /// [`macro_budget`](Self::macro_budget). Returns backend the file is generated with.
+    fn check_budget(&self, document: &Document, rs_file: &Path, report: &mut GenerationReport) -> Backend {
+        if self.backend != Backend::Macro {
+            return self.backend;
+        }
+        let size = codegen::macro_size(document);
+        report.macro_arms = report.macro_arms.max(size.arms);
+        report.literal_bytes = report.literal_bytes.max(size.literal_bytes);
+        match self.macro_budget {
+            Some(budget) if size.arms > budget => {
+                let size = format!(
+                    "tr!() in {} has {} arms with {} bytes of literals, more than the budget of {} arms",
+                    rs_file.display(),
+                    size.arms,
+                    size.literal_bytes,
+                    budget,
+                );
+                if !document.defaults.is_empty() {
+                    report.warn(format_args!(
+                        "{}, consider split_prefixes() (Backend::Binary doesn't support defaults)",
+                        size
+                    ));
+                    Backend::Macro
+                } else if self.auto_binary {
+                    report.note(format_args!("{}, using Backend::Binary", size));
+                    Backend::Binary
+                } else {
+                    report.warn(format_args!("{}, consider Backend::Binary or split_prefixes()", size));
+                    Backend::Macro
+                }
+            }
+            _ => Backend::Macro,
+        }
+    }
+
+    /// Convert given TOML file to binary catalog which can be loaded at runtime
+    /// with [`BinaryCatalog::load`]
+    ///
+    /// [`BinaryCatalog::load`]: crate::BinaryCatalog::load
+    pub fn compile_catalog(
+        &self,
+        toml_file: impl AsRef<Path>,
+        catalog_file: impl AsRef<Path>,
+    ) -> Result<GenerationReport> {
+        let mut report = GenerationReport::default();
+        let result = self.prepare_document(toml_file.as_ref(), &mut report).and_then(|document| {
+            report_dedup(&document, &mut report);
+            write_catalog(catalog_file.as_ref(), &document)
+        });
+        self.finish(&report, result)?;
+        Ok(report)
+    }
+
+    /// Convert every `*.toml` and `*.json` file in `locales_dir` to `strings_<locale>.rs` in `$OUT_DIR`
+    /// (see [`out_dir`](Self::out_dir) and [`locale_file_name`](Self::locale_file_name)),
+    /// where `<locale>` is the file stem, which must be a BCP-47 tag, and print `cargo:rerun-if-changed` for the directory
+    /// and every file in it. Returns generated locales sorted by name, which can be used
+    /// to generate further code (e.g. a locale enum). `<locale>.overrides.toml` files are
+    /// applied to their locales as with [`convert_strings_file`](Self::convert_strings_file).
+    /// # Panics
+    /// If environment variable `OUT_DIR` is not set and no other output directory is
+    /// configured. You should call this function only from `build.rs` script
+    pub fn convert_locales(&self, locales_dir: impl AsRef<Path>) -> Result<Vec<LocaleOutput>> {
+        let out_dir = self.resolve_out_dir()?;
+        if let Some(outputs) = self.unchanged_locales(locales_dir.as_ref(), &out_dir)? {
+            return Ok(outputs);
+        }
+        let catalog = self.load_catalog(locales_dir)?;
+        self.convert_catalog_to(&catalog, &out_dir)
+    }
+
+    /// Outputs of [`convert_locales`](Self::convert_locales), if it's [incremental](Self::incremental)
+    /// and no file read to generate them changed since the last build, so strings files
+    /// don't need to be parsed at all. Prints `cargo:rerun-if-changed` for the files then.
+    fn unchanged_locales(&self, locales_dir: &Path, out_dir: &Path) -> Result<Option<Vec<LocaleOutput>>> {
+        // Overrides are read from environment, which isn't hashed
+        if !self.incremental || self.env_overrides {
+            return Ok(None);
+        }
+        let cache = Cache::load(out_dir.join(cache::FILE_NAME));
+        let config = cache::config_hash(&format!("{:?}", self));
+        let mut inputs = BTreeSet::new();
+        let mut outputs = Vec::new();
+        for (locale, source) in locales::discover(locales_dir, self.normalize_locale_names)? {
+            let output = out_dir.join(self.locale_file_name.replace("{locale}", &locale));
+            match cache.unchanged_inputs(&output, &config) {
+                Some(files) => inputs.extend(files),
+                None => return Ok(None),
+            }
+            let feature = locales::feature_name(&locale);
+            outputs.push(LocaleOutput { feature, locale, source, output, report: GenerationReport::default() });
+        }
+        if self.locale_selection != LocaleSelection::Manual {
+            match cache.unchanged_inputs(&out_dir.join("strings.rs"), &config) {
+                Some(files) => inputs.extend(files),
+                None => return Ok(None),
+            }
+        }
+        println!("cargo:rerun-if-changed={}", locales_dir.display());
+        for input in inputs {
+            println!("cargo:rerun-if-changed={}", input.display());
+        }
+        Ok(Some(outputs))
+    }
+
+    /// Read and parse every `*.toml` and `*.json` file in `locales_dir` once, so the [`Catalog`] can be
+    /// converted and checked several times without parsing the files again, and print
+    /// `cargo:rerun-if-changed` for the directory and every file in it. Locales are named
+    /// as with [`convert_locales`](Self::convert_locales).
+    pub fn load_catalog(&self, locales_dir: impl AsRef<Path>) -> Result<Catalog> {
+        let locales_dir = locales_dir.as_ref();
+        println!("cargo:rerun-if-changed={}", locales_dir.display());
+        let discovered = locales::discover(locales_dir, self.normalize_locale_names)?;
+        self.read_catalog(discovered)
+    }
+
+    /// Convert every locale of `catalog` loaded with [`load_catalog`](Self::load_catalog)
+    /// like [`convert_locales`](Self::convert_locales) does, without reading strings files again
+    /// # Panics
+    /// If environment variable `OUT_DIR` is not set and no other output directory is
+    /// configured. You should call this function only from `build.rs` script
+    pub fn convert_catalog(&self, catalog: &Catalog) -> Result<Vec<LocaleOutput>> {
+        let out_dir = self.resolve_out_dir()?;
+        self.convert_catalog_to(catalog, &out_dir)
+    }
+
+    /// Convert strings files of given locales to `strings_<locale>.rs` in `out_dir` in one
+    /// call, returning warnings and statistics of all locales combined. Works like
+    /// [`convert_locales`](Self::convert_locales) with explicitly listed files, so limits
+    /// are shared and checks comparing translations to the [source locale](Self::source_locale)
+    /// are done for every locale. Locales must be BCP-47 tags and can only be listed once,
+    /// as with [`convert_locales`](Self::convert_locales).
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    ///
+    /// let report = ctl10n::Builder::new()
+    ///     .source_locale("en")
+    ///     .convert_all(
+    ///         &[("en", Path::new("strings/en.toml")), ("de", Path::new("strings/de.toml"))],
+    ///         std::env::var("OUT_DIR").unwrap(),
+    ///     )
+    ///     .expect("ctl10n failed");
+    /// for (locale, keys) in &report.filled_keys_by_locale {
+    ///     eprintln!("{} is missing {}", locale, keys.join(", "));
+    /// }
+    /// ```
+    ///
+    /// ```
+    /// let locales = [("en_US", std::path::Path::new("strings/en_US.toml"))];
+    /// let error = ctl10n::Builder::new().convert_all(&locales, std::env::temp_dir()).unwrap_err();
+    /// assert!(error.to_string().ends_with("Locale name `en_US` is not a valid BCP-47 tag, did you mean `en-US`?"));
+    /// ```
+    pub fn convert_all(&self, locales: &[(&str, &Path)], out_dir: impl AsRef<Path>) -> Result<GenerationReport> {
+        let locales = locales
+            .iter()
+            .map(|(locale, path)| Ok((locales::locale_name(locale, path, self.normalize_locale_names)?, path.to_path_buf())))
+            .collect::<Result<Vec<_>>>()?;
+        locales::check_duplicates(&locales)?;
+        let catalog = self.read_catalog(locales)?;
+        let mut report = GenerationReport::default();
+        for output in self.convert_catalog_to(&catalog, out_dir.as_ref())? {
+            if !output.report.filled_keys.is_empty() {
+                report.filled_keys_by_locale.insert(output.locale.clone(), output.report.filled_keys.clone());
+            }
+            report.extend(output.report);
+        }
+        Ok(report)
+    }
+
+    /// Read strings files of `locales`, printing `cargo:rerun-if-changed` for them
+    fn read_catalog(&self, locales: Vec<(String, PathBuf)>) -> Result<Catalog> {
+        let read = map_locales(self.parallel, &locales, |(_, path)| {
+            let mut report = GenerationReport::default();
+            let mut inputs = Vec::new();
+            let document = self.read_document_inputs(path, &mut report, &mut inputs);
+            (document, inputs, report)
+        });
+        let mut report = GenerationReport::default();
+        let entries = locales
+            .into_iter()
+            .zip(read)
+            .map(|((locale, path), (document, inputs, entry_report))| {
+                println!("cargo:rerun-if-changed={}", path.display());
+                report.extend(entry_report.clone());
+                Ok(CatalogEntry { locale, path, document: document?, inputs, report: entry_report })
+            })
+            .collect::<Result<Vec<_>>>();
+        let entries = self.finish(&report, entries)?;
+        Ok(Catalog { entries })
+    }
+
+    /// Convert strings files of `catalog` to `out_dir`
+    fn convert_catalog_to(&self, catalog: &Catalog, out_dir: &Path) -> Result<Vec<LocaleOutput>> {
+        if !self.locale_file_name.contains("{locale}") {
+            return Err(Error::InvalidFileNamePattern(self.locale_file_name.clone()));
+        }
+        let mut builder = self.clone();
+        // Tests of all locales are put into `strings.rs`, if there is one
+        builder.format_tests &= self.locale_selection == LocaleSelection::Manual;
+        for entry in &catalog.entries {
+            for (key, &limit) in &entry.document.limits {
+                let shared = builder.shared_limits.entry(key.clone()).or_insert(limit);
+                *shared = limit.min(*shared);
+            }
+        }
+        // All locales are generated with the same backend, so `tr!()` works alike with every one
+        let mut switched = None;
+        if self.auto_binary && self.backend == Backend::Macro {
+            builder.auto_binary = false;
+            let budget = self.macro_budget.unwrap_or(usize::MAX);
+            let oversized = catalog.entries.iter().any(|entry| codegen::macro_size(&entry.document).arms > budget);
+            let defaults = catalog.entries.iter().any(|entry| !entry.document.defaults.is_empty());
+            if oversized && !defaults {
+                builder.backend = Backend::Binary;
+                switched = Some(budget);
+            }
+        }
+        builder.template = self.resolve_template(catalog)?;
+        if builder.template.is_none() && (self.fill_missing || self.lockfile.is_some()) {
+            return Err(Error::NoSourceLocale);
+        }
+        let mut lockfile_report = GenerationReport::default();
+        if let Some(template) = &builder.template {
+            let result = self.check_lockfile(template.document(), &mut lockfile_report);
+            self.finish(&lockfile_report, result)?;
+        }
+        let mut cache = self.incremental.then(|| Cache::load(out_dir.join(cache::FILE_NAME)));
+        let config = format!("{:?}", builder);
+        let config_hash = cache::config_hash(&format!("{:?}", self));
+        // Outputs of other locales depend on the source locale, e.g. when filling missing keys
+        let mut shared_inputs: Vec<PathBuf> = self.lockfile.iter().filter(|path| path.is_file()).cloned().collect();
+        if let (None, Some(template)) = (&self.template, &builder.template) {
+            shared_inputs.extend(catalog.get(template.locale()).into_iter().flat_map(|entry| entry.inputs.clone()));
+        }
+        let converted = map_locales(self.parallel, &catalog.entries, |entry| {
+            let output = out_dir.join(self.locale_file_name.replace("{locale}", &entry.locale));
+            let mut report = GenerationReport::default();
+            if let Some(budget) = switched {
+                let size = codegen::macro_size(&entry.document);
+                report.macro_arms = size.arms;
+                report.literal_bytes = size.literal_bytes;
+                report.note(format_args!(
+                    "tr!() of some locale has more than the budget of {} arms, using Backend::Binary for {}",
+                    budget,
+                    output.display(),
+                ));
+            }
+            let result = output
+                .parent()
+                .map_or(Ok(()), |parent| fs::create_dir_all(parent).map_err(|err| Error::from(err).in_file(parent)))
+                .and_then(|()| builder.prepare(entry.document.clone(), &entry.path, &mut report))
+                .and_then(|document| {
+                    report_dedup(&document, &mut report);
+                    let hash = cache.as_ref().map(|_| cache::input_hash(&config, &document));
+                    if let (Some(cache), Some(hash)) = (&cache, &hash) {
+                        if cache.is_fresh(&output, hash) {
+                            return Ok((document, hash.clone()));
+                        }
+                    }
+                    builder.write_outputs(&document, &entry.path, &output, &mut report)?;
+                    if let (LocaleSelection::Runtime(_), Backend::Macro) = (&self.locale_selection, builder.backend) {
+                        write_catalog(&output.with_extension("bin"), &document)?;
+                    }
+                    if self.json_bundle {
+                        let bundle = output.with_file_name(format!("strings.{}.json", entry.locale));
+                        write_file(&bundle, json::bundle(&document))?;
+                    }
+                    Ok((document, hash.unwrap_or_default()))
+                });
+            (output, report, result)
+        });
+        // Reports are printed in order of locales, however they were generated
+        let (mut outputs, documents): (Vec<LocaleOutput>, Vec<_>) = catalog
+            .entries
+            .iter()
+            .zip(converted)
+            .map(|(entry, (output, report, result))| {
+                let locale = entry.locale.clone();
+                let (document, hash) = self.finish(&report, result)?;
+                if let Some(cache) = &mut cache {
+                    let inputs: Vec<PathBuf> = entry.inputs.iter().chain(&shared_inputs).cloned().collect();
+                    let written = builder.written_with(&locale, &output);
+                    cache.update(&output, hash, &config_hash, &inputs, &written);
+                }
+                // Warnings of reading the file were printed when it was loaded
+                let mut full_report = entry.report.clone();
+                full_report.extend(report);
+                let output = LocaleOutput {
+                    feature: locales::feature_name(&locale),
+                    locale,
+                    source: entry.path.clone(),
+                    output,
+                    report: full_report,
+                };
+                Ok((output, document))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        // Lockfile findings are reported once, with the source locale they were checked against
+        let source = builder.template.as_ref().map(|template| template.locale());
+        let source = outputs.iter().position(|output| Some(output.locale.as_str()) == source).unwrap_or(0);
+        if let Some(output) = outputs.get_mut(source) {
+            output.report.extend(lockfile_report);
+        }
+        if let (Some(cache), LocaleSelection::Manual) = (&cache, &self.locale_selection) {
+            cache.save()?;
+        }
+        let mut tokens = match &self.locale_selection {
+            LocaleSelection::Manual => return Ok(outputs),
+            LocaleSelection::Features => {
+                // Included files are resolved relative to `strings.rs`, so paths must be absolute
+                let canonical = outputs
+                    .iter()
+                    .map(|output| {
+                        let path = fs::canonicalize(&output.output).map_err(|err| Error::from(err).in_file(&output.output))?;
+                        Ok(LocaleOutput { output: path, ..output.clone() })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let mut tokens = codegen::gen_locale_switch(&canonical);
+                let names: Vec<&str> = outputs.iter().map(|output| output.locale.as_str()).collect();
+                tokens.extend(codegen::gen_locale_enum(&names, None, &self.codegen));
+                for prefix in &self.split_prefixes {
+                    let parts: Vec<LocaleOutput> = canonical
+                        .iter()
+                        .map(|output| LocaleOutput { output: part_file(&output.output, prefix), ..output.clone() })
+                        .collect();
+                    let switch = codegen::render(codegen::gen_locale_switch(&parts), self.pretty);
+                    write_file(&part_file(&out_dir.join("strings.rs"), prefix), switch)?;
+                }
+                tokens
+            }
+            LocaleSelection::Runtime(default) => {
+                let catalogs = outputs
+                    .iter()
+                    .map(|output| {
+                        let catalog = output.output.with_extension("bin");
+                        fs::canonicalize(&catalog)
+                            .map(|catalog| (output.locale.as_str(), catalog))
+                            .map_err(|err| Error::from(err).in_file(&catalog))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let default = catalogs
+                    .iter()
+                    .find(|(locale, _)| locale == default)
+                    .ok_or_else(|| Error::UnknownLocale(default.clone()))?;
+                let locales: Vec<(&str, &Path)> =
+                    catalogs.iter().map(|(locale, path)| (*locale, path.as_path())).collect();
+                let names: Vec<&str> = locales.iter().map(|(locale, _)| *locale).collect();
+                let default_document = &catalog.get(default.0).unwrap().document;
+                let mut tokens = codegen::gen_reexport(&self.codegen);
+                tokens.extend(codegen::gen_binary_loader(&default.1, &locales, &self.codegen));
+                tokens.extend(codegen::gen_static(default_document, &self.codegen));
+                tokens.extend(codegen::gen_cow(&self.codegen));
+                let keys = default_document.in_order(&default_document.strings);
+                tokens.extend(codegen::gen_keys(keys.iter().map(|(key, _)| *key)));
+                let versions: Vec<_> = outputs
+                    .iter()
+                    .zip(&documents)
+                    .map(|(output, document)| (output.locale.as_str(), document))
+                    .collect();
+                let timestamp = modified(outputs.iter().map(|output| output.source.as_path()));
+                tokens.extend(codegen::gen_catalog_version(&catalog_fingerprint(&versions), timestamp, &self.codegen));
+                if self.string_catalog {
+                    tokens.extend(codegen::gen_string_catalog(default_document, true, &self.codegen));
+                }
+                if !default_document.html.is_empty() {
+                    tokens.extend(codegen::gen_html(html_keys(default_document), true, &self.codegen));
+                }
+                tokens.extend(codegen::gen_negotiate(default.0, &names, &self.codegen));
+                tokens.extend(codegen::gen_locale_enum(&names, Some(default.0), &self.codegen));
+                let plurals: Vec<_> = outputs
+                    .iter()
+                    .zip(&documents)
+                    .map(|(output, document)| (output.locale.as_str(), document))
+                    .collect();
+                for kind in PLURAL_KINDS {
+                    if !default_document.plurals(kind).is_empty() {
+                        tokens.extend(codegen::gen_plurals(&plurals, kind, Some(default.0), &self.codegen));
+                    }
+                }
+                let formats: Vec<_> = outputs
+                    .iter()
+                    .zip(&documents)
+                    .map(|(output, document)| (output.locale.as_str(), &document.formats))
+                    .collect();
+                tokens.extend(codegen::gen_runtime_formats(&formats, default.0, &self.codegen));
+                if self.number_format {
+                    let separators: Vec<_> = outputs
+                        .iter()
+                        .zip(&documents)
+                        .map(|(output, document)| number_separators(&output.source, document))
+                        .collect();
+                    let formats: Vec<_> = outputs
+                        .iter()
+                        .zip(&separators)
+                        .map(|(output, (decimal, group))| (output.locale.as_str(), (decimal.as_str(), group.as_str())))
+                        .collect();
+                    tokens.extend(codegen::gen_number_formats(&formats, default.0, &self.codegen));
+                }
+                // Locales without `[currency]` use the one of the default locale, if any
+                let fallback = default_document.currency.as_ref();
+                let fallback = fallback.or_else(|| documents.iter().find_map(|document| document.currency.as_ref()));
+                if let Some(fallback) = fallback {
+                    let separators: Vec<_> = outputs
+                        .iter()
+                        .zip(&documents)
+                        .map(|(output, document)| number_separators(&output.source, document))
+                        .collect();
+                    let formats: Vec<_> = outputs
+                        .iter()
+                        .zip(&documents)
+                        .zip(&separators)
+                        .map(|((output, document), (decimal, group))| {
+                            let currency = document.currency.as_ref().unwrap_or(fallback);
+                            (output.locale.as_str(), currency, (decimal.as_str(), group.as_str()))
+                        })
+                        .collect();
+                    tokens.extend(codegen::gen_currency_formats(&formats, default.0, &self.codegen));
+                }
+                if self.list_format {
+                    let separators: Vec<_> = outputs
+                        .iter()
+                        .zip(&documents)
+                        .map(|(output, document)| list_separators(&output.source, document))
+                        .collect();
+                    let formats: Vec<_> = outputs
+                        .iter()
+                        .zip(&separators)
+                        .map(|(output, separators)| (output.locale.as_str(), separators.each_ref().map(String::as_str)))
+                        .collect();
+                    tokens.extend(codegen::gen_list_formats(&formats, default.0, &self.codegen));
+                }
+                if self.truncation {
+                    let ellipses: Vec<_> = outputs
+                        .iter()
+                        .zip(&documents)
+                        .map(|(output, document)| ellipsis(&output.source, document))
+                        .collect();
+                    let ellipses: Vec<_> = outputs
+                        .iter()
+                        .zip(&ellipses)
+                        .map(|(output, ellipsis)| (output.locale.as_str(), ellipsis.as_str()))
+                        .collect();
+                    tokens.extend(codegen::gen_truncates(&ellipses, default.0, &self.codegen));
+                }
+                tokens
+            }
+        };
+        if self.format_tests {
+            let locales = outputs.iter().map(|output| Some(output.locale.as_str()));
+            tokens.extend(codegen::gen_format_tests(locales.zip(&documents), false, &self.codegen));
+        }
+        let strings = out_dir.join("strings.rs");
+        write_file(&strings, codegen::render(tokens, self.pretty))?;
+        if let Some(cache) = &mut cache {
+            let inputs: Vec<PathBuf> =
+                catalog.entries.iter().flat_map(|entry| entry.inputs.clone()).chain(shared_inputs).collect();
+            let parts: Vec<PathBuf> = match self.locale_selection {
+                LocaleSelection::Features => self.split_prefixes.iter().map(|prefix| part_file(&strings, prefix)).collect(),
+                _ => Vec::new(),
+            };
+            cache.update(&strings, String::new(), &config_hash, &inputs, &parts);
+            cache.save()?;
+        }
+        Ok(outputs)
+    }
+
+    /// Files written by [`convert_catalog_to`](Self::convert_catalog_to) for `locale` besides `output`
+    fn written_with(&self, locale: &str, output: &Path) -> Vec<PathBuf> {
+        let parts: Vec<PathBuf> = self.split_prefixes.iter().map(|prefix| part_file(output, prefix)).collect();
+        let mut written = parts.clone();
+        if self.manifest {
+            written.push(output.with_extension("manifest.json"));
+        }
+        if self.backend == Backend::Binary {
+            written.push(output.with_extension("bin"));
+            written.extend(parts.iter().map(|part| part.with_extension("bin")));
+        } else if let LocaleSelection::Runtime(_) = self.locale_selection {
+            written.push(output.with_extension("bin"));
+        }
+        if self.json_bundle {
+            written.push(output.with_file_name(format!("strings.{}.json", locale)));
+        }
+        written
+    }
+
+    /// Write JSON Schema to `out_path`, which requires locale files to have exactly
+    /// the keys of `template` strings file with the same kinds of values. Editors
+    /// supporting schemas for TOML (e.g. VS Code with Even Better TOML) can use it to
+    /// highlight typos and missing keys in translations.
+    pub fn emit_schema(&self, template: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+        let template = self.load_template(template)?;
+        let title = format!("ctl10n strings for {}", template.path().display());
+        let joined_arrays = self.parse_options.join_arrays.is_some();
+        let schema = serde_json::to_string_pretty(&schema::schema(template.document(), &title, joined_arrays)).unwrap();
+        write_file(out_path.as_ref(), schema)
+    }
+
+    /// Write Markdown document with a table of every key of strings files in `locales_dir`,
+    /// its placeholders and text in every locale to `out_path`, so copy can be reviewed
+    /// without reading TOML. The [source locale](Self::source_locale) is the first column,
+    /// keys missing from a locale are marked and counted.
+    ///
+    /// ```text
+    /// | Key | Placeholders | en | de |
+    /// |---|---|---|---|
+    /// | `greeting` | `{name}` | Hello, {name}! | Hallo, {name}! |
+    /// ```
+    pub fn markdown_report(&self, locales_dir: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+        let locales_dir = locales_dir.as_ref();
+        let catalog = self.load_catalog(locales_dir)?;
+        let template = self.resolve_template(&catalog)?;
+        let mut documents: Vec<_> = catalog
+            .entries
+            .into_iter()
+            .map(|entry| (entry.locale, entry.document))
+            .collect();
+        if let Some(template) = template {
+            let idx = documents
+                .iter()
+                .position(|(locale, _)| locale == template.locale())
+                .ok_or_else(|| Error::UnknownLocale(template.locale().to_string()))?;
+            let source = documents.remove(idx);
+            documents.insert(0, source);
+        }
+        let title = format!("Strings in {}", locales_dir.display());
+        write_file(out_path.as_ref(), markdown::catalog_report(&title, &documents))
+    }
+
+    /// Write `<locale>.txt` with every string of strings files in `locales_dir` rendered
+    /// with sample `args` to `out_dir`, so changes of messages show up in code review as
+    /// text diffs when snapshots are committed or compared with `insta`. Arguments are
+    /// substituted with [`format_named`](crate::format_named), so placeholders without
+    /// a sample argument are kept as is. Items of arrays are written as `key[idx]`.
+    /// Strings are snapshotted as they're generated, e.g. with [filled](Self::fill_missing)
+    /// keys and [keys shown](Self::show_keys).
+    ///
+    /// ```no_run
+    /// let name = "Alice";
+    /// ctl10n::Builder::new().write_snapshots("locales", "snapshots", &[("name", &name), ("count", &3)]).unwrap();
+    /// ```
+    ///
+    /// ```text
+    /// # Strings of de
+    /// greeting = Hallo, Alice!
+    /// tips[0] = Erster Tipp
+    /// ```
+    pub fn write_snapshots(
+        &self,
+        locales_dir: impl AsRef<Path>,
+        out_dir: impl AsRef<Path>,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        self.catalog_snapshots(&self.load_catalog(locales_dir)?, out_dir, args)
+    }
+
+    /// Write snapshots of every locale of `catalog` loaded with
+    /// [`load_catalog`](Self::load_catalog), as with [`write_snapshots`](Self::write_snapshots)
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-catalog-snapshots");
+    /// std::fs::create_dir_all(dir.join("locales")).unwrap();
+    /// std::fs::write(dir.join("locales/en.toml"), "greeting = \"Hello, {name}!\"\nbye = \"Bye\"").unwrap();
+    /// std::fs::write(dir.join("locales/de.toml"), "greeting = \"Hallo, {name}!\"").unwrap();
+    /// let builder = ctl10n::Builder::new().print_warnings(false).source_locale("en").fill_missing(true);
+    /// let catalog = builder.load_catalog(dir.join("locales")).unwrap();
+    /// builder.catalog_snapshots(&catalog, dir.join("snapshots"), &[("name", &"Alice")]).unwrap();
+    /// let snapshot = std::fs::read_to_string(dir.join("snapshots/de.txt")).unwrap();
+    /// assert_eq!(snapshot, "# Strings of de\ngreeting = Hallo, Alice!\nbye = Bye\n");
+    /// ```
+    pub fn catalog_snapshots(
+        &self,
+        catalog: &Catalog,
+        out_dir: impl AsRef<Path>,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir).map_err(|err| Error::from(err).in_file(out_dir))?;
+        let mut builder = self.clone();
+        builder.template = self.resolve_template(catalog)?;
+        for entry in &catalog.entries {
+            let mut report = GenerationReport::default();
+            let document = builder.prepare(entry.document.clone(), &entry.path, &mut report);
+            let document = self.finish(&report, document)?;
+            let path = out_dir.join(format!("{}.txt", entry.locale));
+            write_file(&path, snapshot::snapshot(&entry.locale, &document, args))?;
+        }
+        Ok(())
+    }
+
+    /// Translation completeness of every locale in `locales_dir` compared to the
+    /// [`Template`], e.g. for gating releases on thresholds.
+    /// Translations are stale if the reserved `[fingerprints]` table of the locale has
+    /// a [fingerprint](crate::fingerprint) for the key which isn't the fingerprint of the
+    /// current source text, which translation tools can record when translating.
+    ///
+    /// ```no_run
+    /// let stats = ctl10n::Builder::new().source_locale("en").stats("locales").unwrap();
+    /// for locale in stats {
+    ///     assert!(locale.completeness > 90.0, "{} is incomplete", locale.locale);
+    /// }
+    /// ```
+    pub fn stats(&self, locales_dir: impl AsRef<Path>) -> Result<Vec<LocaleStats>> {
+        self.catalog_stats(&self.load_catalog(locales_dir)?)
+    }
+
+    /// Translation completeness of every locale of `catalog` loaded with
+    /// [`load_catalog`](Self::load_catalog), as with [`stats`](Self::stats)
+    pub fn catalog_stats(&self, catalog: &Catalog) -> Result<Vec<LocaleStats>> {
+        let template = self.resolve_template(catalog)?.ok_or(Error::NoSourceLocale)?;
+        Ok(catalog
+            .entries
+            .iter()
+            .map(|entry| stats::locale_stats(&entry.locale, &entry.document, template.document(), self.fill_missing))
+            .collect())
+    }
+
+    /// Write gettext template (`.pot`) with an entry for every string and list item of
+    /// `template` strings file to `pot_path`, so it can be translated with standard gettext
+    /// tools, with translations imported back with [`import_po`](Self::import_po).
+    /// Keys are used as `msgctxt` (`key[0]` for list items) and values as `msgid`.
+    /// With [`fingerprints`](Self::fingerprints) entries have fingerprints of their text
+    /// in extracted comments.
+    ///
+    /// ```text
+    /// #: strings.toml:2
+    /// msgctxt "greeting"
+    /// msgid "Hello, {name}!"
+    /// msgstr ""
+    /// ```
+    pub fn export_pot(&self, template: impl AsRef<Path>, pot_path: impl AsRef<Path>) -> Result<()> {
+        let template = self.load_template(template)?;
+        write_file(pot_path.as_ref(), pot::pot(template.document(), template.path(), self.fingerprints))
+    }
+
+    /// Write strings file `out_path` with translations of gettext file `po_path`, translated
+    /// from a template written by [`export_pot`](Self::export_pot) for `template` strings
+    /// file. Untranslated and fuzzy entries are left out, so they can be
+    /// [filled](Self::fill_missing) from the template, as are lists with any untranslated item.
+    /// Entries with keys which aren't in the template are errors.
+    ///
+    /// ```no_run
+    /// ctl10n::Builder::new().import_po("locales/en.toml", "po/de.po", "locales/de.toml").unwrap();
+    /// ```
+    pub fn import_po(&self, template: impl AsRef<Path>, po_path: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+        let template = self.load_template(template)?;
+        let po_path = po_path.as_ref();
+        let input = fs::read(po_path).map_err(Error::from).and_then(decode).map_err(|err| err.in_file(po_path))?;
+        let strings = pot::import_po(&input, po_path, template.document())?;
+        write_file(out_path.as_ref(), strings)
+    }
+
+    /// Write tests checking every strings file in `locales_dir` against the file of the
+    /// [`Template`] with [`check_locale`](Self::check_locale) to
+    /// `out_path`, e.g. `tests/locales.rs`, so `cargo test` fails when a translator commits
+    /// a broken file even if its locale isn't built. Tests read files with the options
+    /// of this builder and allow missing keys with [`fill_missing`](Self::fill_missing), so they're
+    /// as strict as the build. Relative paths are relative to
+    /// the crate being tested. Cargo finds test files before running build scripts, so a
+    /// file written from `build.rs` for the first time is only tested by the next `cargo test`.
+    ///
+    /// ```no_run
+    /// ctl10n::Builder::new()
+    ///     .source_locale("en")
+    ///     .emit_locale_tests("locales", "tests/locales.rs")
+    ///     .unwrap();
+    /// ```
+    pub fn emit_locale_tests(&self, locales_dir: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+        let locales_dir = locales_dir.as_ref();
+        println!("cargo:rerun-if-changed={}", locales_dir.display());
+        let locales = locales::discover(locales_dir, self.normalize_locale_names)?;
+        let template = match (&self.template, self.resolve_source_locale()) {
+            (Some(template), _) => template.path(),
+            (None, Some(source_locale)) => locales
+                .iter()
+                .find(|(locale, _)| locale == source_locale)
+                .map(|(_, path)| path.as_path())
+                .ok_or_else(|| Error::UnknownLocale(source_locale.clone()))?,
+            (None, None) => return Err(Error::NoSourceLocale),
+        };
+        let checks = codegen::LocaleChecks {
+            parse_options: &self.parse_options,
+            include_duplicates: self.include_duplicates,
+            fill_missing: self.fill_missing,
+        };
+        let tests = codegen::gen_locale_tests(template, &locales, &checks);
+        write_file(out_path.as_ref(), codegen::render(tests, self.pretty))
+    }
+
+    /// Check that strings file `file` can be read and has the same keys as `template`, with
+    /// the same arguments, as tests written by [`emit_locale_tests`](Self::emit_locale_tests) do.
+    /// Keys missing from `file` are allowed with [`fill_missing`](Self::fill_missing).
+    ///
+    /// ```
+    /// let dir = std::env::temp_dir().join("ctl10n-check-locale");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// std::fs::write(dir.join("en.toml"), "greeting = \"Hello\"\nbye = \"Bye\"").unwrap();
+    /// std::fs::write(dir.join("de.toml"), "greeting = \"Hallo\"").unwrap();
+    /// let builder = ctl10n::Builder::new();
+    /// assert!(builder.check_locale(dir.join("de.toml"), dir.join("en.toml")).is_err());
+    /// let builder = builder.fill_missing(true);
+    /// assert!(builder.check_locale(dir.join("de.toml"), dir.join("en.toml")).is_ok());
+    ///
+    /// std::fs::write(dir.join("de.toml"), "greeting = \"Hallo\"\nbye = \"Tschüss\"\ntypo = \"Tippfehler\"").unwrap();
+    /// let error = builder.check_locale(dir.join("de.toml"), dir.join("en.toml")).unwrap_err();
+    /// assert!(error.to_string().ends_with("de.toml:3: Keys don't match the template, unexpected `typo`"));
+    /// ```
+    pub fn check_locale(&self, file: impl AsRef<Path>, template: impl AsRef<Path>) -> Result<()> {
+        let file = file.as_ref();
+        let template = self.load_template(template)?;
+        let template = template.document();
+        let mut report = GenerationReport::default();
+        let document = self.read_document(file, &mut report)?;
+        let keys = |document: &Document| -> BTreeSet<String> {
+            document.strings.keys().chain(document.lists.keys()).cloned().collect()
+        };
+        let (expected, found) = (keys(template), keys(&document));
+        let missing: Vec<String> = match self.fill_missing {
+            true => Vec::new(),
+            false => expected.difference(&found).cloned().collect(),
+        };
+        let unexpected: Vec<String> = found.difference(&expected).cloned().collect();
+        if !missing.is_empty() || !unexpected.is_empty() {
+            return Err(Error::KeysMismatch { missing, unexpected }.at_key(&document, file));
+        }
+        let mut lints = Lints::new();
+        lints.insert(Lint::PlaceholderMismatch, Severity::Deny);
+        match lint::check_lints(&document, Some(&template.strings), &lints, &BTreeSet::new()).into_iter().next() {
+            Some((_, error)) => Err(error.at_key(&document, file)),
+            None => Ok(()),
+        }
+    }
+
+    /// Report keys of the [lockfile](Self::lockfile) changed in `document` and update it
+    fn check_lockfile(&self, document: &Document, report: &mut GenerationReport) -> Result<()> {
+        let path = match &self.lockfile {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        println!("cargo:rerun-if-changed={}", path.display());
+        println!("cargo:rerun-if-env-changed=CTL10N_UPDATE_LOCKFILE");
+        let update = env::var_os("CTL10N_UPDATE_LOCKFILE").is_some_and(|value| !value.is_empty());
+        let current = lockfile::signatures(document);
+        let existing = match fs::read(path) {
+            Ok(bytes) => Some(decode(bytes).map_err(|err| err.in_file(path))?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(Error::from(err).in_file(path)),
+        };
+        let mut unchanged = true;
+        if let (Some(input), false) = (&existing, update) {
+            let locked = lockfile::parse(input).map_err(|err| err.in_file(path))?;
+            for error in lockfile::changes(&locked, &current) {
+                unchanged = false;
+                report.report(error.in_file(path), self.locked_keys)?;
+            }
+        }
+        let rendered = lockfile::render(&current);
+        // Writing the same lockfile again would make cargo rerun the build script
+        if unchanged && existing.as_ref() != Some(&rendered) {
+            write_file(path, rendered)?;
+        }
+        Ok(())
+    }
+
+    /// Read strings file and apply transformations done during generation
+    fn prepare_document(&self, path: &Path, report: &mut GenerationReport) -> Result<Document> {
+        let document = self.read_document(path, report)?;
+        self.prepare(document, path, report)
+    }
+
+    /// Apply transformations done during generation to strings file read from `path`
+    fn prepare(&self, mut document: Document, path: &Path, report: &mut GenerationReport) -> Result<Document> {
+        if let (Some(template), true) = (&self.template, self.fill_missing) {
+            fill_missing(&mut document, template.document(), template.locale(), path, report);
+        }
+        if let Some(template) = &self.template {
+            inherit_defaults(&mut document, template.document());
+        }
+        if self.env_overrides {
+            apply_env_overrides(&mut document, report).map_err(|err| err.at_key(&document, path))?;
+        }
+        self.normalize(&mut document);
+        let mut limits = self.shared_limits.clone();
+        limits.extend(document.limits.clone());
+        for error in lint::check_limits(&document, &limits) {
+            report.report(error.at_key(&document, path), self.length_limits)?;
+        }
+        // Translations are checked against arguments declared in the template, which they can't redeclare
+        let mut args = self.template.as_ref().map(|template| template.document().args.clone()).unwrap_or_default();
+        for (key, declared) in &document.args {
+            match args.get(key) {
+                Some(expected) if expected != declared => {
+                    let error = Error::InvalidMetadata {
+                        key: key.clone(),
+                        detail: format!(
+                            "args {{{}}} differ from args {{{}}} declared in the template",
+                            declared.join("}, {"),
+                            expected.join("}, {"),
+                        ),
+                    };
+                    return Err(error.at_key(&document, path));
+                }
+                _ => {
+                    args.insert(key.clone(), declared.clone());
+                }
+            }
+        }
+        if let Some(error) = lint::check_args(&document, &args).into_iter().next() {
+            return Err(error.at_key(&document, path));
+        }
+        // The template isn't compared to itself
+        let source = self.template.as_ref().filter(|template| !template.is_read_from(path));
+        let source = source.map(|template| &template.document().strings);
+        for (severity, error) in lint::check_lints(&document, source, &self.lints, &self.allow_untranslated) {
+            // Filled keys are reported as missing already
+            if matches!(&error, Error::Lint { key, lint: Lint::Untranslated, .. } if report.filled_keys.contains(key)) {
+                continue;
+            }
+            report.report(error.at_key(&document, path), severity)?;
+        }
+        self.check_key_style(&document, path, report)?;
+        println!("cargo:rerun-if-env-changed=CTL10N_SHOW_KEYS");
+        if self.shows_keys() {
+            show_keys(&mut document);
+        }
+        Ok(document)
+    }
+
+    /// Whether values are prefixed with keys by [`show_keys`](Self::show_keys) or `CTL10N_SHOW_KEYS`
+    fn shows_keys(&self) -> bool {
+        self.show_keys || env::var_os("CTL10N_SHOW_KEYS").is_some_and(|value| !value.is_empty())
+    }
+
+    /// Report keys of `document`, read from `path` (if it's not empty), violating [`key_style`](Self::key_style)
+    fn check_key_style(&self, document: &Document, path: &Path, report: &mut GenerationReport) -> Result<()> {
+        let (style, severity) = match (&self.key_style, self.lints.get(&Lint::KeyStyle)) {
+            (Some(style), Some(&severity)) if severity != Severity::Allow => (style, severity),
+            _ => return Ok(()),
+        };
+        for error in lint::check_key_style(document, style) {
+            if let Error::Lint { key, .. } = &error {
+                let suggestion = style.suggest(key);
+                if suggestion != *key {
+                    report.key_renames.push((key.clone(), suggestion));
+                }
+            }
+            let error = if path.as_os_str().is_empty() { error } else { error.at_key(document, path) };
+            report.report(error, severity)?;
+        }
+        Ok(())
+    }
+
+    /// Print warnings of finished operation and fail if there are any and
+    /// [`fail_on_warnings`](Self::fail_on_warnings) is set
+    fn finish<T>(&self, report: &GenerationReport, result: Result<T>) -> Result<T> {
+        if self.print_warnings {
+            report.print();
+        }
+        let value = result?;
+        if self.fail_on_warnings && !report.warnings.is_empty() {
+            return Err(Error::Warnings(report.warnings.clone()));
+        }
+        Ok(value)
+    }
+
+    fn normalize(&self, document: &mut Document) {
+        #[cfg(feature = "normalize")]
+        if let Some(form) = self.normalization {
+            crate::normalize::normalize(document, form);
+        }
+        #[cfg(not(feature = "normalize"))]
+        let _ = document;
+    }
+
+    /// Read strings file with includes and apply its overrides file, if any
+    fn read_document(&self, path: &Path, report: &mut GenerationReport) -> Result<Document> {
+        self.read_document_inputs(path, report, &mut Vec::new())
+    }
+
+    /// Like [`read_document`](Self::read_document), adding every file read to `inputs`
+    fn read_document_inputs(&self, path: &Path, report: &mut GenerationReport, inputs: &mut Vec<PathBuf>) -> Result<Document> {
+        let mut document = self.read_included(path, &mut Vec::new(), report, inputs)?;
+        let overrides_path = locales::overrides_path(path);
+        if overrides_path.is_file() {
+            println!("cargo:rerun-if-changed={}", overrides_path.display());
+            let mut overrides = self.read_included(&overrides_path, &mut Vec::new(), report, inputs)?;
+            overrides.set_file(&overrides_path);
+            let keys = overrides.strings.keys().chain(overrides.lists.keys());
+            for key in keys {
+                if !document.strings.contains_key(key) && !document.lists.contains_key(key) {
+                    let error = Error::UnknownOverride(key.clone());
+                    return Err(error.in_file(overrides_path));
+                }
+            }
+            document.merge(overrides);
+        }
+        Ok(document)
+    }
+
+    /// Read strings file and files it includes. `stack` contains files currently being read,
+    /// every file read is added to `inputs`.
+    fn read_included(
+        &self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        report: &mut GenerationReport,
+        inputs: &mut Vec<PathBuf>,
+    ) -> Result<Document> {
+        let in_file = |err: Error| err.in_file(path);
+        inputs.push(path.to_path_buf());
+        let document = fs::read(path)
+            .map_err(Error::from)
+            .and_then(decode)
+            .and_then(|input| match path.extension() {
+                Some(extension) if extension == "json" => json::parse_json(&input, &self.parse_options),
+                _ => parse_toml(&input, &self.parse_options),
+            })
+            .map_err(in_file)?;
+        if document.includes.is_empty() {
+            return Ok(document);
+        }
+
+        let canonical = fs::canonicalize(path).map_err(|err| in_file(err.into()))?;
+        if stack.contains(&canonical) {
+            return Err(in_file(Error::IncludeCycle(path.to_path_buf())));
+        }
+        stack.push(canonical);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut merged = Document::default();
+        // Included file each key comes from
+        let mut origins = BTreeMap::new();
+        for include in &document.includes {
+            let included = dir.join(include);
+            println!("cargo:rerun-if-changed={}", included.display());
+            let mut included_document = self.read_included(&included, stack, report, inputs)?;
+            included_document.set_file(&included);
+            let keys = included_document.strings.keys().chain(included_document.lists.keys());
+            origins.extend(keys.map(|key| (key.clone(), included.clone())));
+            for key in merged.merge(included_document) {
+                let error = Error::ConflictingInclude { key, included: included.clone() };
+                report.report(in_file(error), self.include_duplicates)?;
+            }
+        }
+        stack.pop();
+        for key in merged.merge(document) {
+            let included = origins[&key].clone();
+            let error = Error::ConflictingInclude { key, included };
+            report.report(in_file(error), self.include_duplicates)?;
+        }
+        crate::toml_parser::validate_metadata(&merged).map_err(in_file)?;
+        Ok(merged)
+    }
+
+    /// Convert file `strings.toml` in current diretory to file `strings.rs` in `$OUT_DIR`
+    /// (see [`out_dir`](Self::out_dir))
+    /// # Panics
+    /// If environment variable `OUT_DIR` is not set and no other output directory is
+    /// configured. You should call this function only from `build.rs` script
+    pub fn convert_default_strings_file(&self) -> Result<GenerationReport> {
+        let out_dir = self.resolve_out_dir()?;
+        self.convert_strings_file("strings.toml", out_dir.join("strings.rs"))
+    }
+
+    /// Convert strings as configured in `[package.metadata.ctl10n]` table of `Cargo.toml`
+    /// of the crate being built, so `build.rs` only needs this call. Options are named
+    /// like builder methods in kebab case and override ones set on the builder;
+    /// `strings` (default `strings.toml`) is converted to `strings.rs` as with
+    /// [`convert_default_strings_file`](Self::convert_default_strings_file), unless
+    /// `locales` directory is set, which is converted with [`convert_locales`](Self::convert_locales).
+    /// `default-locale` is the default locale of [`runtime_locales`](Self::runtime_locales),
+    /// `template` is a strings file read with [`load_template`](Self::load_template),
+    /// `normalize-unicode` is `nfc` or `nfkc` and `lints` is a table of lint names and
+    /// severities. Paths of `out-dir`, `template` and `lockfile` are relative to the manifest.
+    /// Macros are always named `tr!()` and so on, there is no option renaming them. Returns
+    /// warnings and statistics of all converted files.
+    ///
+    /// ```toml
+    /// [package.metadata.ctl10n]
+    /// locales = "locales"
+    /// default-locale = "en"
+    /// fail-on-warnings = true
+    /// lints = { double-space = "warn", placeholder-mismatch = "deny" }
+    /// ```
+    ///
+    /// ```no_run
+    /// fn main() {
+    ///     ctl10n::Builder::new().convert_from_manifest().expect("ctl10n failed");
+    /// }
+    /// ```
+    /// # Panics
+    /// If environment variable `CARGO_MANIFEST_DIR` or `OUT_DIR` is not set. You should
+    /// call this function only from `build.rs` script
+    pub fn convert_from_manifest(&self) -> Result<GenerationReport> {
+        let manifest = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml");
+        println!("cargo:rerun-if-changed={}", manifest.display());
+        let (builder, input) = metadata::read(&manifest, self.clone())?;
+        match input {
+            Input::Strings(strings) => {
+                println!("cargo:rerun-if-changed={}", strings.display());
+                let out_dir = builder.resolve_out_dir()?;
+                builder.convert_strings_file(strings, out_dir.join("strings.rs"))
+            }
+            Input::Locales(locales_dir) => {
+                let mut report = GenerationReport::default();
+                for output in builder.convert_locales(locales_dir)? {
+                    report.extend(output.report);
+                }
+                Ok(report)
+            }
+        }
+    }
+
+    /// Source locale if it's set or default locale of runtime locale switching
+    fn resolve_source_locale(&self) -> Option<&String> {
+        match (&self.source_locale, &self.locale_selection) {
+            (Some(locale), _) | (None, LocaleSelection::Runtime(locale)) => Some(locale),
+            _ => None,
+        }
+    }
+
+    /// Template set with [`template`](Self::template) or locale of `catalog` named by
+    /// [`resolve_source_locale`](Self::resolve_source_locale), if any
+    fn resolve_template(&self, catalog: &Catalog) -> Result<Option<Template>> {
+        if let Some(template) = &self.template {
+            return Ok(Some(template.clone()));
+        }
+        let locale = match self.resolve_source_locale() {
+            Some(locale) => locale,
+            None => return Ok(None),
+        };
+        let entry = catalog.get(locale).ok_or_else(|| Error::UnknownLocale(locale.clone()))?;
+        Ok(Some(Template::new(locale.clone(), entry.path.clone(), entry.document.clone())))
+    }
+
+    /// Output directory: `CTL10N_OUT_DIR`, [`out_dir`](Self::out_dir) or `$OUT_DIR`, created
+    /// if it doesn't exist
+    fn resolve_out_dir(&self) -> Result<PathBuf> {
+        println!("cargo:rerun-if-env-changed=CTL10N_OUT_DIR");
+        let out_dir = match (env::var_os("CTL10N_OUT_DIR"), &self.out_dir) {
+            (Some(out_dir), _) => PathBuf::from(out_dir),
+            (None, Some(out_dir)) => out_dir.clone(),
+            (None, None) => PathBuf::from(env::var("OUT_DIR").unwrap()),
+        };
+        fs::create_dir_all(&out_dir).map_err(|err| Error::from(err).in_file(&out_dir))?;
+        Ok(out_dir)
+    }
+}
+
+/// Apply `f` to every item in order, on all available cores if `parallel` is set
+fn map_locales<T, R, F>(parallel: bool, items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    if !parallel || threads == 1 || items.len() < 2 {
+        return items.iter().map(f).collect();
+    }
+    let chunk_size = items.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let f = &f;
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
+/// Name of environment variable overriding value of `key`
+fn override_var(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("CTL10N_OVERRIDE_{}", key)
+}
+
+/// Prefix every value of `document` with `[key] `
+pub(crate) fn show_keys(document: &mut Document) {
+    // Braces of keys mustn't become placeholders
+    let prefix = |key: &str| format!("[{}] ", key.replace('{', "{{").replace('}', "}}"));
+    for (key, value) in document.strings.iter_mut() {
+        value.insert_str(0, &prefix(key));
+    }
+    for (key, items) in document.lists.iter_mut() {
+        // Array items aren't format strings
+        items.iter_mut().for_each(|item| item.insert_str(0, &format!("[{}] ", key)));
+    }
+    for (key, overrides) in document.targets.iter_mut() {
+        overrides.values_mut().for_each(|value| value.insert_str(0, &prefix(key)));
+    }
+    for (key, messages) in document.cardinals.iter_mut().chain(document.ordinals.iter_mut()) {
+        messages.values_mut().for_each(|message| message.insert_str(0, &prefix(key)));
+    }
+}
+
+fn apply_env_overrides(document: &mut Document, report: &mut GenerationReport) -> Result<()> {
+    let mut vars = BTreeMap::new();
+    for key in document.in_order(&document.strings).into_iter().map(|(key, _)| key) {
+        if let Some(other) = vars.insert(override_var(key), key) {
+            return Err(Error::OverrideCollision { key: key.clone(), other: other.clone(), var: override_var(key) });
+        }
+    }
+    for (key, value) in document.strings.iter_mut() {
+        let var = override_var(key);
+        println!("cargo:rerun-if-env-changed={}", var);
+        if let Ok(new_value) = env::var(&var) {
+            if let Err(detail) = parse_placeholders(&new_value) {
+                return Err(Error::InvalidPlaceholder {
+                    key: key.clone(),
+                    detail,
+                });
+            }
+            if document.html.contains(key) {
+                crate::toml_parser::validate_html(key, &new_value)?;
+            }
+            report.warn(format_args!("`{}` is overridden by {}", key, var));
+            report.overridden_keys.push(key.clone());
+            *value = new_value;
+        }
+    }
+    Ok(())
+}
+
+/// File next to `rs_file` with keys of namespace `prefix`, e.g. `strings_errors.rs`
+fn part_file(rs_file: &Path, prefix: &str) -> PathBuf {
+    let stem = rs_file.file_stem().unwrap_or_default().to_string_lossy();
+    let prefix: String = prefix.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    rs_file.with_file_name(format!("{}_{}.rs", stem, prefix))
+}
+
+/// Use defaults of keys of `source` locale for the same keys of `document` which don't set
+/// them, if the translation has the same placeholders and they have no format specs, which
+/// defaults can't be formatted with
+fn inherit_defaults(document: &mut Document, source: &Document) {
+    for (key, defaults) in &source.defaults {
+        let value = match document.strings.get(key) {
+            Some(value) if !document.defaults.contains_key(key) => value,
+            _ => continue,
+        };
+        let placeholders = parse_placeholders(value).unwrap_or_default();
+        let names: BTreeSet<&str> = placeholders
+            .iter()
+            .filter_map(|placeholder| match placeholder {
+                Placeholder::Named(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        let same = names.len() == placeholders.len() && names.iter().copied().eq(defaults.keys().map(String::as_str));
+        if same && with_specs(value).is_empty() {
+            document.defaults.insert(key.clone(), defaults.clone());
+        }
+    }
+}
+
+/// Add keys of `source` locale missing from `document`, warning about each one
+fn fill_missing(
+    document: &mut Document,
+    source: &Document,
+    source_locale: &str,
+    path: &Path,
+    report: &mut GenerationReport,
+) {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let missing = |key: &String| !document.strings.contains_key(key) && !document.lists.contains_key(key);
+    let mut strings = source.strings.clone();
+    strings.retain(|key, _| missing(key));
+    let mut lists = source.lists.clone();
+    lists.retain(|key, _| missing(key));
+    for key in strings.keys().chain(lists.keys()) {
+        report.warn(format_args!("{} missing key '{}', using {} fallback", file_name, key, source_locale));
+        report.filled_keys.push(key.clone());
+    }
+    let filled = source.order.iter().filter(|key| strings.contains_key(*key) || lists.contains_key(*key));
+    document.order.extend(filled.cloned());
+    document.strings.append(&mut strings);
+    document.lists.append(&mut lists);
+}
+
+/// Decimal and group separators for strings file: set in its `[number]` table or
+/// commonly used in locale named like the file
+fn number_separators(path: &Path, document: &Document) -> (String, String) {
+    let locale = path.file_stem().unwrap_or_default().to_string_lossy();
+    let format = NumberFormat::for_locale(&locale);
+    let separator = |name: &str, default: &str| document.number.get(name).cloned().unwrap_or_else(|| default.to_string());
+    (separator("decimal", format.decimal), separator("group", format.group))
+}
+
+/// Separators for joining lists: set in `[join]` table of strings file or commonly
+/// used in locale named like the file
+fn list_separators(path: &Path, document: &Document) -> [String; 3] {
+    let locale = path.file_stem().unwrap_or_default().to_string_lossy();
+    let format = ListFormat::for_locale(&locale);
+    let separator = |name: &str, default: &str| document.join.get(name).cloned().unwrap_or_else(|| default.to_string());
+    [
+        separator("separator", format.separator),
+        separator("last", format.last),
+        separator("pair", format.pair),
+    ]
+}
+
+/// Ellipsis set in `[truncate]` table of strings file or commonly used in locale named like the file
+fn ellipsis(path: &Path, document: &Document) -> String {
+    let locale = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ellipsis = document.truncate.get("ellipsis").map(String::as_str);
+    ellipsis.unwrap_or_else(|| crate::truncate::ellipsis_for_locale(&locale)).to_string()
+}
+
+/// Keys marked with `html = true` in order of definition
+fn html_keys(document: &Document) -> impl Iterator<Item = &String> {
+    let strings = document.in_order(&document.strings);
+    strings.into_iter().map(|(key, _)| key).filter(move |key| document.html.contains(*key))
+}
+
+/// Latest modification time of `paths` in seconds since Unix epoch, if it's known, or
+/// `$SOURCE_DATE_EPOCH` if it's set, so reproducible builds don't depend on checkout time
+fn modified<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<u64> {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Some(epoch) = env::var("SOURCE_DATE_EPOCH").ok().and_then(|epoch| epoch.trim().parse().ok()) {
+        return Some(epoch);
+    }
+    paths
+        .into_iter()
+        .filter_map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .filter_map(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_secs())
+        .max()
+}
+
+const PLURAL_KINDS: [PluralKind; 2] = [PluralKind::Cardinal, PluralKind::Ordinal];
+
+/// Whether `document` has messages of `trn!()` or `trn_ord!()`, which use `ctl10n`
+fn has_plurals(document: &Document) -> bool {
+    PLURAL_KINDS.iter().any(|&kind| !document.plurals(kind).is_empty())
+}
+
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    fs::write(path, contents).map_err(|err| Error::from(err).in_file(path))
+}
+
+/// Write binary catalog of strings, leaving out keys with `cfg` which doesn't hold
+/// for the target being built and using target overrides matching it
+fn write_catalog(path: &Path, document: &Document) -> Result<()> {
+    if let Some(key) = document.defaults.keys().next() {
+        return Err(Error::InvalidMetadata {
+            key: key.clone(),
+            detail: "defaults aren't supported by binary catalogs".to_string(),
+        });
+    }
+    let mut kv = Vec::new();
+    for (key, value) in &document.strings {
+        if !document.cfgs.get(key).is_none_or(|predicate| cfg::is_enabled(predicate)) {
+            continue;
+        }
+        let overrides = document.targets.get(key).into_iter().flatten();
+        let matching: Vec<_> = overrides.filter(|(predicate, _)| cfg::is_enabled(predicate)).collect();
+        let value = match matching.as_slice() {
+            [] => value,
+            [(_, value)] => *value,
+            [(first, _), (second, _), ..] => {
+                return Err(Error::InvalidMetadata {
+                    key: key.clone(),
+                    detail: format!("overrides for both cfg({}) and cfg({}) match the target", first, second),
+                })
+            }
+        };
+        kv.push((key.as_str(), value.as_str()));
+    }
+    write_file(path, binary::encode(kv))
+}
+
+fn report_dedup(document: &Document, report: &mut GenerationReport) {
+    let dedup = Dedup::new(codegen::shareable(&document.strings, document));
+    report.shared_values = dedup.shared_count();
+    report.saved_bytes = dedup.saved_bytes();
+    if dedup.shared_count() > 0 {
+        report.note(format_args!(
+            "deduplicated {} shared values ({} bytes saved)",
+            dedup.shared_count(),
+            dedup.saved_bytes(),
+        ));
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::fingerprint::{bytes_fingerprint, fingerprint};
+use crate::toml_parser::Document;
+
+/// Name of the file in output directory storing hashes of [`Builder::incremental`](crate::Builder::incremental)
+pub(crate) const FILE_NAME: &str = "ctl10n-cache.json";
+
+/// What a generated file was last written from, and other files written with it
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Entry {
+    /// Hash of the prepared document, see [`input_hash`]
+    hash: String,
+    /// Hash of builder options, see [`config_hash`]
+    config: String,
+    /// Hashes of raw contents of every file read to generate it, by path
+    inputs: BTreeMap<String, String>,
+    /// Other files written with it, e.g. binary catalogs and manifests
+    outputs: Vec<String>,
+}
+
+impl Entry {
+    fn to_json(&self) -> Value {
+        json!({
+            "hash": self.hash,
+            "config": self.config,
+            "inputs": self.inputs,
+            "outputs": self.outputs,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let string = |key: &str| value.get(key)?.as_str().map(str::to_string);
+        let inputs = value
+            .get("inputs")?
+            .as_object()?
+            .iter()
+            .map(|(path, hash)| Some((path.clone(), hash.as_str()?.to_string())))
+            .collect::<Option<_>>()?;
+        let outputs = value
+            .get("outputs")?
+            .as_array()?
+            .iter()
+            .map(|path| path.as_str().map(str::to_string))
+            .collect::<Option<_>>()?;
+        Some(Self { hash: string("hash")?, config: string("config")?, inputs, outputs })
+    }
+
+    /// Whether every file written with the generated one still exists
+    fn outputs_exist(&self) -> bool {
+        self.outputs.iter().all(|output| Path::new(output).exists())
+    }
+}
+
+/// What generated files were last written from, by file name
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cache {
+    path: PathBuf,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Cache {
+    /// Read cache from `path`. Missing or malformed cache is empty, so everything is regenerated.
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+            .and_then(|value| {
+                value
+                    .as_object()?
+                    .iter()
+                    .map(|(output, entry)| Some((output.clone(), Entry::from_json(entry)?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// Whether `output` and every file written with it exist and were generated from
+    /// a document with given hash
+    pub(crate) fn is_fresh(&self, output: &Path, hash: &str) -> bool {
+        self.entries
+            .get(&key(output))
+            .is_some_and(|entry| entry.hash == hash && entry.outputs_exist())
+            && output.exists()
+    }
+
+    /// Files read to generate `output`, if it and every file written with it exist, it was
+    /// generated with builder options with given hash, and none of the files changed since,
+    /// so it doesn't need to be generated again and its inputs don't even need to be parsed
+    pub(crate) fn unchanged_inputs(&self, output: &Path, config: &str) -> Option<Vec<PathBuf>> {
+        let entry = self.entries.get(&key(output))?;
+        if entry.config != config || !entry.outputs_exist() || !output.exists() {
+            return None;
+        }
+        let unchanged = entry
+            .inputs
+            .iter()
+            .all(|(path, hash)| fs::read(path).is_ok_and(|contents| bytes_fingerprint(&contents) == *hash));
+        unchanged.then(|| entry.inputs.keys().map(PathBuf::from).collect())
+    }
+
+    /// Record that `output` and `outputs` were generated from a document with given hash and
+    /// builder options with hash `config`, reading `inputs`, which are hashed as they are now
+    pub(crate) fn update(&mut self, output: &Path, hash: String, config: &str, inputs: &[PathBuf], outputs: &[PathBuf]) {
+        let inputs = inputs
+            .iter()
+            .map(|path| {
+                // Unreadable file never matches, so the output is regenerated next time
+                let hash = fs::read(path).map(|contents| bytes_fingerprint(&contents)).unwrap_or_default();
+                (key(path), hash)
+            })
+            .collect();
+        let outputs = outputs.iter().map(|path| key(path)).collect();
+        self.entries.insert(key(output), Entry { hash, config: config.to_string(), inputs, outputs });
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        let entries: serde_json::Map<_, _> =
+            self.entries.iter().map(|(output, entry)| (output.clone(), entry.to_json())).collect();
+        fs::write(&self.path, serde_json::to_string_pretty(&entries).unwrap())
+            .map_err(|err| Error::from(err).in_file(&self.path))
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Hash of builder options and version of ctl10n, `config` being debug representation of the builder
+pub(crate) fn config_hash(config: &str) -> String {
+    fingerprint(&format!("{}\0{}", env!("CARGO_PKG_VERSION"), config))
+}
+
+/// Hash of everything code generated from `document` depends on: the document after
+/// transformations done during generation, `config` of the builder and version of ctl10n
+pub(crate) fn input_hash(config: &str, document: &Document) -> String {
+    fingerprint(&format!("{}\0{}\0{:?}", env!("CARGO_PKG_VERSION"), config, document))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::{parse_toml, ParseOptions};
+
+    fn document(toml: &str) -> Document {
+        parse_toml(toml, &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn hash_depends_on_inputs() {
+        let hash = input_hash("config", &document(r#"ok = "OK""#));
+        assert_eq!(hash, input_hash("config", &document(r#"ok = "OK""#)));
+        assert_ne!(hash, input_hash("config", &document(r#"ok = "Okay""#)));
+        assert_ne!(hash, input_hash("other config", &document(r#"ok = "OK""#)));
+    }
+
+    #[test]
+    fn fresh_after_save() {
+        let dir = std::env::temp_dir().join(format!("ctl10n-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("strings.rs");
+        let mut cache = Cache::load(dir.join(FILE_NAME));
+        assert!(!cache.is_fresh(&output, "hash"));
+        cache.update(&output, "hash".to_string(), "config", &[], &[]);
+        // Output doesn't exist yet
+        assert!(!cache.is_fresh(&output, "hash"));
+        fs::write(&output, "").unwrap();
+        assert!(cache.is_fresh(&output, "hash"));
+        cache.save().unwrap();
+
+        let cache = Cache::load(dir.join(FILE_NAME));
+        assert!(cache.is_fresh(&output, "hash"));
+        assert!(!cache.is_fresh(&output, "other hash"));
+        fs::remove_file(&output).unwrap();
+        assert!(!cache.is_fresh(&output, "hash"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_output_must_exist() {
+        let dir = std::env::temp_dir().join(format!("ctl10n-cache-outputs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("strings.rs");
+        let catalog = dir.join("strings.bin");
+        fs::write(&output, "").unwrap();
+        let mut cache = Cache::load(dir.join(FILE_NAME));
+        cache.update(&output, "hash".to_string(), "config", &[], std::slice::from_ref(&catalog));
+        assert!(!cache.is_fresh(&output, "hash"));
+        assert_eq!(cache.unchanged_inputs(&output, "config"), None);
+        fs::write(&catalog, "").unwrap();
+        assert!(cache.is_fresh(&output, "hash"));
+        assert_eq!(cache.unchanged_inputs(&output, "config"), Some(Vec::new()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn inputs_are_hashed() {
+        let dir = std::env::temp_dir().join(format!("ctl10n-cache-inputs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("strings.rs");
+        let input = dir.join("en.toml");
+        fs::write(&output, "").unwrap();
+        fs::write(&input, r#"ok = "OK""#).unwrap();
+        let mut cache = Cache::load(dir.join(FILE_NAME));
+        cache.update(&output, "hash".to_string(), "config", std::slice::from_ref(&input), &[]);
+        cache.save().unwrap();
+
+        let cache = Cache::load(dir.join(FILE_NAME));
+        assert_eq!(cache.unchanged_inputs(&output, "config"), Some(vec![input.clone()]));
+        assert_eq!(cache.unchanged_inputs(&output, "other config"), None);
+        fs::write(&input, r#"ok = "Okay""#).unwrap();
+        assert_eq!(cache.unchanged_inputs(&output, "config"), None);
+        fs::remove_file(&input).unwrap();
+        assert_eq!(cache.unchanged_inputs(&output, "config"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_cache_is_empty() {
+        let dir = std::env::temp_dir().join(format!("ctl10n-malformed-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("strings.rs");
+        fs::write(&output, "").unwrap();
+        fs::write(dir.join(FILE_NAME), "not json").unwrap();
+        let cache = Cache::load(dir.join(FILE_NAME));
+        assert!(!cache.is_fresh(&output, "hash"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
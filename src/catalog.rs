@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+use crate::report::GenerationReport;
+use crate::toml_parser::Document;
+
+/// Strings files of locales read and parsed once by [`Builder::load_catalog`], so
+/// several conversions and checks can reuse them instead of parsing every file again
+///
+/// ```no_run
+/// let builder = ctl10n::Builder::new().source_locale("en");
+/// let catalog = builder.load_catalog("locales").unwrap();
+/// for locale in builder.catalog_stats(&catalog).unwrap() {
+///     assert!(locale.completeness > 90.0, "{} is incomplete", locale.locale);
+/// }
+/// builder.convert_catalog(&catalog).unwrap();
+/// ```
+///
+/// [`Builder::load_catalog`]: crate::Builder::load_catalog
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub(crate) entries: Vec<CatalogEntry>,
+}
+
+/// Parsed strings file of a locale
+#[derive(Debug, Clone)]
+pub(crate) struct CatalogEntry {
+    pub locale: String,
+    pub path: PathBuf,
+    pub document: Document,
+    /// Every file read: the strings file, files it includes and its overrides file
+    pub inputs: Vec<PathBuf>,
+    /// Warnings reported while reading the file
+    pub report: GenerationReport,
+}
+
+impl Catalog {
+    /// Names of locales in the catalog
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.locale.as_str())
+    }
+
+    /// Strings file of `locale`, if it's in the catalog
+    pub fn path(&self, locale: &str) -> Option<&Path> {
+        self.get(locale).map(|entry| entry.path.as_path())
+    }
+
+    /// Number of locales
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the catalog has no locales
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub(crate) fn get(&self, locale: &str) -> Option<&CatalogEntry> {
+        self.entries.iter().find(|entry| entry.locale == locale)
+    }
+}
@@ -0,0 +1,67 @@
+use std::env;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+/// Parse `cfg` predicate like `all(unix, feature = "debug")`, checking its syntax
+pub(crate) fn parse(predicate: &str) -> Result<TokenStream, String> {
+    let tokens: TokenStream = predicate
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid cfg predicate", predicate))?;
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    evaluate_tokens(&tokens, &|_| None)?;
+    Ok(tokens.into_iter().collect())
+}
+
+/// Evaluate `cfg` predicate in build script for the target being built, using
+/// `CARGO_CFG_*` and `CARGO_FEATURE_*` variables set by cargo. Invalid predicates are false.
+pub(crate) fn is_enabled(predicate: &str) -> bool {
+    let tokens: Vec<TokenTree> = match predicate.parse::<TokenStream>() {
+        Ok(tokens) => tokens.into_iter().collect(),
+        Err(_) => return false,
+    };
+    evaluate_tokens(&tokens, &|var| env::var(var).ok()).unwrap_or(false)
+}
+
+fn env_name(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+fn evaluate_tokens(tokens: &[TokenTree], var: &dyn Fn(&str) -> Option<String>) -> Result<bool, String> {
+    let invalid = || {
+        let predicate: TokenStream = tokens.iter().cloned().collect();
+        format!("`{}` is not a valid cfg predicate", predicate)
+    };
+    match tokens {
+        [TokenTree::Ident(name)] => Ok(var(&format!("CARGO_CFG_{}", env_name(&name.to_string()))).is_some()),
+        [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Literal(value)] if eq.as_char() == '=' => {
+            let value = value.to_string();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .ok_or_else(invalid)?;
+            let name = name.to_string();
+            if name == "feature" {
+                Ok(var(&format!("CARGO_FEATURE_{}", env_name(value))).is_some())
+            } else {
+                let values = var(&format!("CARGO_CFG_{}", env_name(&name))).unwrap_or_default();
+                Ok(values.split(',').any(|item| item == value))
+            }
+        }
+        [TokenTree::Ident(name), TokenTree::Group(group)] if group.delimiter() == Delimiter::Parenthesis => {
+            let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+            let mut results = Vec::new();
+            for predicate in inner.split(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ',')) {
+                if !predicate.is_empty() {
+                    results.push(evaluate_tokens(predicate, var)?);
+                }
+            }
+            match (name.to_string().as_str(), results.as_slice()) {
+                ("all", _) => Ok(results.iter().all(|&result| result)),
+                ("any", _) => Ok(results.iter().any(|&result| result)),
+                ("not", &[result]) => Ok(!result),
+                _ => Err(invalid()),
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
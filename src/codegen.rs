@@ -0,0 +1,1998 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+
+use crate::locales::{feature_name, variant_name, LocaleOutput};
+use crate::placeholder::{parse_counts, parse_placeholders, Placeholder};
+use crate::plural::{parse_condition, PluralCategory, PluralKind};
+use crate::currency::SymbolPosition;
+use crate::lint::Severity;
+use crate::toml_parser::{format_constant, Currency, Document, ParseOptions};
+
+/// Values occurring more than once, which are emitted only once
+pub(crate) struct Dedup<'a> {
+    shared: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+    saved_bytes: usize,
+}
+
+impl<'a> Dedup<'a> {
+    pub(crate) fn new(values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut order = Vec::new();
+        for value in values {
+            let count = counts.entry(value).or_insert(0);
+            if *count == 0 {
+                order.push(value);
+            }
+            *count += 1;
+        }
+        let shared: Vec<&str> = order.into_iter().filter(|v| counts[v] > 1).collect();
+        let index = shared.iter().enumerate().map(|(idx, v)| (*v, idx)).collect();
+        let saved_bytes = shared.iter().map(|v| (counts[v] - 1) * v.len()).sum();
+        Self {
+            shared,
+            index,
+            saved_bytes,
+        }
+    }
+
+    /// Number of distinct values shared between multiple keys
+    pub(crate) fn shared_count(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Bytes of string data not emitted thanks to deduplication
+    pub(crate) fn saved_bytes(&self) -> usize {
+        self.saved_bytes
+    }
+}
+
+/// Options affecting generated code
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options {
+    /// Export generated macros from the crate with `#[macro_export]`
+    pub macro_export: bool,
+    /// Generate `pub macro` (macros 2.0) instead of `macro_rules!`
+    pub decl_macro: bool,
+    /// How `tr!()` reloads strings in debug builds
+    pub reload: Option<Reload>,
+}
+
+/// Strings file `tr!()` reloads strings from in debug builds and transformations
+/// of its values done during generation, which are done after reloading too
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Reload {
+    pub path: String,
+    pub parse_options: ParseOptions,
+    /// Variant of `NormalizationForm` values are normalized to, e.g. `Nfc`
+    pub normalization: Option<String>,
+    pub show_keys: bool,
+    /// Keys overridden by environment variables
+    pub overridden: Vec<String>,
+}
+
+impl Options {
+    /// Define macro `name` with given arms, e.g. `(matcher) => { body }`
+    fn define(&self, name: &str, hidden: bool, arms: Vec<TokenStream>) -> TokenStream {
+        self.define_with(TokenStream::new(), name, hidden, arms)
+    }
+
+    /// [`define`](Self::define) macro with attributes `attrs`, e.g. `#[cfg(unix)]`. Macros
+    /// exported from an included file can't be called by `$crate::` paths in the crate
+    /// including it, so exported macros are followed by copies shadowing them there, which
+    /// aren't exported and call other macros by name.
+    fn define_with(&self, attrs: TokenStream, name: &str, hidden: bool, arms: Vec<TokenStream>) -> TokenStream {
+        let name = format_ident!("{}", name);
+        let hidden = if hidden {
+            quote! { #[doc(hidden)] }
+        } else {
+            TokenStream::new()
+        };
+        if self.decl_macro {
+            quote! {
+                #attrs
+                #hidden
+                #[allow(unused_macros)]
+                pub macro #name { #( #arms ),* }
+            }
+        } else if self.macro_export {
+            let local_arms = arms.iter().cloned().map(local_macro_paths);
+            quote! {
+                #attrs
+                #hidden
+                #[allow(unused_macros)]
+                #[macro_export]
+                macro_rules! #name { #( #arms );* }
+                #attrs
+                #[allow(unused_macros)]
+                macro_rules! #name { #( #local_arms );* }
+            }
+        } else {
+            quote! {
+                #attrs
+                #hidden
+                #[allow(unused_macros)]
+                macro_rules! #name { #( #arms );* }
+            }
+        }
+    }
+
+    /// Prefix for paths to generated macros and items used inside macros
+    fn local(&self) -> TokenStream {
+        if self.macro_export && !self.decl_macro {
+            quote! { $crate:: }
+        } else {
+            TokenStream::new()
+        }
+    }
+
+    /// Path to ctl10n crate used inside macros. Exported macros can't use `::ctl10n`,
+    /// since it's resolved in the calling crate.
+    fn ctl10n(&self) -> TokenStream {
+        if self.decl_macro {
+            quote! { __ctl10n }
+        } else if self.macro_export {
+            quote! { $crate::__ctl10n }
+        } else {
+            quote! { ::ctl10n }
+        }
+    }
+
+    /// Visibility of generated items used by exported macros
+    fn vis(&self) -> TokenStream {
+        if self.macro_export || self.decl_macro {
+            quote! { pub }
+        } else {
+            TokenStream::new()
+        }
+    }
+}
+
+/// `tokens` without `$crate::` before names of called macros, e.g. `$crate::tr!`
+fn local_macro_paths(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match &tokens[idx..] {
+            [TokenTree::Punct(dollar), TokenTree::Ident(krate), TokenTree::Punct(first), TokenTree::Punct(second), TokenTree::Ident(_), TokenTree::Punct(bang), ..]
+                if dollar.as_char() == '$'
+                    && krate == "crate"
+                    && first.as_char() == ':'
+                    && second.as_char() == ':'
+                    && bang.as_char() == '!' =>
+            {
+                idx += 4;
+                continue;
+            }
+            [TokenTree::Group(group), ..] => {
+                let mut local = Group::new(group.delimiter(), local_macro_paths(group.stream()));
+                local.set_span(group.span());
+                result.push(TokenTree::Group(local));
+            }
+            [token, ..] => result.push(token.clone()),
+            [] => unreachable!(),
+        }
+        idx += 1;
+    }
+    result.into_iter().collect()
+}
+
+/// Values of keys without `cfg` or target overrides, which are deduplicated
+pub(crate) fn shareable<'a>(
+    strings: impl IntoIterator<Item = (&'a String, &'a String)>,
+    document: &'a Document,
+) -> impl Iterator<Item = &'a str> {
+    strings
+        .into_iter()
+        .filter(move |(key, _)| !document.cfgs.contains_key(*key) && !document.targets.contains_key(*key))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Generate hidden macro `name` expanding to `body` if `predicate` holds
+/// and to a compilation error otherwise
+fn gen_cfg_helper(name: &str, key: &str, predicate: &str, body: TokenStream, options: &Options) -> TokenStream {
+    let predicate: TokenStream = predicate.parse().expect("cfg predicates are validated by parser");
+    let message = format!("Key `{}` is only available with cfg({})", key, predicate);
+    let mut tokens = options.define_with(quote! { #[cfg(#predicate)] }, name, true, vec![quote! { () => { #body } }]);
+    tokens.extend(options.define_with(
+        quote! { #[cfg(not(#predicate))] },
+        name,
+        true,
+        vec![quote! { () => { ::core::compile_error!(#message) } }],
+    ));
+    tokens
+}
+
+/// Generate hidden macro `name` expanding to override of the target being built,
+/// or to `base` if no predicate of `overrides` holds. Overlapping predicates are
+/// a compilation error, since it's unclear which override should win.
+fn gen_target_helper(
+    name: &str,
+    key: &str,
+    base: &str,
+    overrides: &BTreeMap<String, String>,
+    options: &Options,
+) -> TokenStream {
+    let predicates: Vec<TokenStream> = overrides
+        .keys()
+        .map(|predicate| predicate.parse().expect("cfg predicates are validated by parser"))
+        .collect();
+    let mut tokens = TokenStream::new();
+    for (predicate, value) in predicates.iter().zip(overrides.values()) {
+        tokens.extend(options.define_with(quote! { #[cfg(#predicate)] }, name, true, vec![quote! { () => { #value } }]));
+    }
+    let fallback = quote! { #[cfg(not(any( #( #predicates ),* )))] };
+    tokens.extend(options.define_with(fallback, name, true, vec![quote! { () => { #base } }]));
+    for (idx, first) in predicates.iter().enumerate() {
+        for second in &predicates[idx + 1..] {
+            let message = format!("Key `{}` has overrides for both cfg({}) and cfg({})", key, first, second);
+            tokens.extend(quote! {
+                #[cfg(all(#first, #second))]
+                ::core::compile_error!(#message);
+            });
+        }
+    }
+    tokens
+}
+
+/// Size of `tr!()` generated by [`gen_macro`], which compile times of crates using
+/// it grow with
+pub(crate) struct MacroSize {
+    /// Arms of internal `ctl10n_tr_inner!()`, which every `tr!()` call is matched against
+    pub arms: usize,
+    /// Bytes of string literals in the arms
+    pub literal_bytes: usize,
+}
+
+/// Size of `tr!()` [`gen_macro`] generates for `document`
+pub(crate) fn macro_size(document: &Document) -> MacroSize {
+    let strings = document.in_order(&document.strings);
+    let dedup = Dedup::new(shareable(strings.iter().copied(), document));
+    let arms = inner_arms(document, &mut TokenStream::new(), &Options::default()).len();
+    let unique: usize = strings
+        .iter()
+        .filter(|(_, value)| !dedup.index.contains_key(value.as_str()))
+        .map(|(_, value)| value.len())
+        .sum();
+    let shared: usize = dedup.shared.iter().map(|value| value.len()).sum();
+    let defaults: usize = strings
+        .iter()
+        .filter_map(|(key, value)| Some(with_defaults(value, document.defaults.get(*key)?).len()))
+        .sum();
+    MacroSize {
+        arms,
+        literal_bytes: unique + shared + defaults,
+    }
+}
+
+/// Generate `tr!()` macro with one arm per key. Values shared by multiple keys
+/// are emitted once in an internal `(@vN)` arm. Keys with `cfg` expand to helper
+/// macros defined only when the predicate holds, keys with target overrides to helper
+/// macros choosing value of the target. Arms are in order of definition.
+pub(crate) fn gen_macro(document: &Document, options: &Options) -> TokenStream {
+    let local = options.local();
+    let strings = document.in_order(&document.strings);
+    let mut tokens = TokenStream::new();
+    let arms = inner_arms(document, &mut tokens, options);
+    tokens.extend(options.define("ctl10n_tr_inner", true, arms));
+    let tr = vec![
+        quote! { ($key:tt) => { #local ctl10n_tr_inner!(@defaults $key) } },
+        quote! { ($key:tt, $( $args:tt )* ) => { ::std::format!(#local ctl10n_tr_inner!($key), $( $args )* ) } },
+    ];
+    match &options.reload {
+        Some(reload) => {
+            let keys = strings.iter().map(|(key, _)| *key);
+            tokens.extend(gen_hot_reload(reload, keys, &document.defaults, tr, options));
+        }
+        None => tokens.extend(options.define("tr", false, tr)),
+    }
+    tokens.extend(options.define("tr_in", false, vec![
+        quote! { ($namespace:tt, $key:tt) => { #local ctl10n_tr_inner!(@defaults $namespace, $key) } },
+        quote! {
+            ($namespace:tt, $key:tt, $( $args:tt )* ) => {
+                ::std::format!(#local ctl10n_tr_inner!($namespace, $key), $( $args )* )
+            }
+        },
+    ]));
+    tokens
+}
+
+/// Arms of `ctl10n_tr_inner!()` generated by [`gen_macro`]. Helper macros of keys with
+/// `cfg` or target overrides are added to `tokens`.
+fn inner_arms(document: &Document, tokens: &mut TokenStream, options: &Options) -> Vec<TokenStream> {
+    let local = options.local();
+    let strings = document.in_order(&document.strings);
+    let dedup = Dedup::new(shareable(strings.iter().copied(), document));
+    let mut arms: Vec<TokenStream> = strings
+        .iter()
+        .enumerate()
+        .map(|(idx, &(key, value))| match (document.cfgs.get(key), dedup.index.get(value.as_str())) {
+            (None, _) if document.targets.contains_key(key) => {
+                let helper = format!("ctl10n_tr_target_{}", idx);
+                tokens.extend(gen_target_helper(&helper, key, value, &document.targets[key], options));
+                let helper = format_ident!("{}", helper);
+                quote! { (#key) => { #local #helper!() } }
+            }
+            (Some(predicate), _) => {
+                let helper = format!("ctl10n_tr_cfg_{}", idx);
+                tokens.extend(gen_cfg_helper(&helper, key, predicate, quote! { #value }, options));
+                let helper = format_ident!("{}", helper);
+                quote! { (#key) => { #local #helper!() } }
+            }
+            (None, Some(idx)) => {
+                let shared = format_ident!("v{}", idx);
+                quote! { (#key) => { #local ctl10n_tr_inner!(@#shared) } }
+            }
+            (None, None) => quote! { (#key) => { #value } },
+        })
+        .collect();
+    arms.extend(dedup.shared.iter().enumerate().map(|(idx, value)| {
+        let shared = format_ident!("v{}", idx);
+        quote! { (@#shared) => { #value } }
+    }));
+    arms.extend(gen_default_arms(document, tokens, options));
+    arms.extend(gen_ident_arms(strings.iter().map(|(key, _)| *key), "ctl10n_tr_inner", options));
+    for (key, _) in &strings {
+        for (idx, _) in key.match_indices('.') {
+            let (namespace, name) = (&key[..idx], &key[idx + 1..]);
+            arms.push(quote! { (#namespace, #name) => { #local ctl10n_tr_inner!(#key) } });
+        }
+    }
+    arms.push(quote! {
+        ($key:tt) => {
+            ::core::compile_error!(::core::concat!("There is no string for key `", ::core::stringify!($key), "`"))
+        }
+    });
+    arms.push(quote! {
+        ($namespace:tt, $key:tt) => {
+            ::core::compile_error!(::core::concat!("There is no string for key `", $namespace, ".", $key, "`"))
+        }
+    });
+    arms
+}
+
+/// `value` with placeholders replaced by `defaults`
+fn with_defaults(value: &str, defaults: &BTreeMap<String, String>) -> String {
+    let args: Vec<(&str, &dyn std::fmt::Display)> =
+        defaults.iter().map(|(name, value)| (name.as_str(), value as &dyn std::fmt::Display)).collect();
+    crate::runtime::format_named(value, &args)
+}
+
+/// Arms `(@defaults key)` of `ctl10n_tr_inner!()`, used by `tr!()` without arguments. They
+/// expand to values with defaults substituted for keys which have them and to the value
+/// itself for other keys. Helper macros of keys with `cfg` or target overrides are added
+/// to `tokens`.
+fn gen_default_arms(document: &Document, tokens: &mut TokenStream, options: &Options) -> Vec<TokenStream> {
+    let local = options.local();
+    let strings = document.in_order(&document.strings);
+    let idents: BTreeMap<&String, String> = key_idents(strings.iter().map(|(key, _)| *key)).into_iter().collect();
+    let mut arms = Vec::new();
+    for (idx, (key, value)) in strings.iter().enumerate() {
+        let defaults = match document.defaults.get(*key) {
+            Some(defaults) => defaults,
+            None => continue,
+        };
+        let value = with_defaults(value, defaults);
+        let body = match (document.cfgs.get(*key), document.targets.get(*key)) {
+            (Some(predicate), _) => {
+                let helper = format!("ctl10n_tr_defaults_{}", idx);
+                tokens.extend(gen_cfg_helper(&helper, key, predicate, quote! { #value }, options));
+                let helper = format_ident!("{}", helper);
+                quote! { #local #helper!() }
+            }
+            (None, Some(overrides)) => {
+                let helper = format!("ctl10n_tr_defaults_{}", idx);
+                let overrides = overrides
+                    .iter()
+                    .map(|(predicate, value)| (predicate.clone(), with_defaults(value, defaults)))
+                    .collect();
+                tokens.extend(gen_target_helper(&helper, key, &value, &overrides, options));
+                let helper = format_ident!("{}", helper);
+                quote! { #local #helper!() }
+            }
+            (None, None) => quote! { #value },
+        };
+        arms.push(quote! { (@defaults #key) => { #body } });
+        if let Some(ident) = idents.get(key) {
+            let ident = format_ident!("{}", ident);
+            arms.push(quote! { (@defaults #ident) => { #local ctl10n_tr_inner!(@defaults #key) } });
+        }
+        for (idx, _) in key.match_indices('.') {
+            let (namespace, name) = (&key[..idx], &key[idx + 1..]);
+            arms.push(quote! { (@defaults #namespace, #name) => { #local ctl10n_tr_inner!(@defaults #key) } });
+        }
+    }
+    arms.push(quote! { (@defaults $key:tt) => { #local ctl10n_tr_inner!($key) } });
+    arms.push(quote! { (@defaults $namespace:tt, $key:tt) => { #local ctl10n_tr_inner!($namespace, $key) } });
+    arms
+}
+
+/// Options of `reload_string()` transforming reloaded values as `reload` describes
+fn gen_reload_options(reload: &Reload, options: &Options) -> TokenStream {
+    let (ctl10n, vis) = (options.ctl10n(), options.vis());
+    let parse_options = &reload.parse_options;
+    let mut setters = TokenStream::new();
+    if parse_options.lenient_values {
+        setters.extend(quote! { .lenient_values(true) });
+    }
+    if let Some(separator) = &parse_options.join_arrays {
+        setters.extend(quote! { .join_arrays(#separator) });
+    }
+    let whitespace = format_ident!("{}", format!("{:?}", parse_options.whitespace));
+    let escapes = format_ident!("{}", format!("{:?}", parse_options.escapes));
+    setters.extend(quote! {
+        .whitespace(#ctl10n::Whitespace::#whitespace)
+        .escapes(#ctl10n::Escapes::#escapes)
+    });
+    if let Some(form) = &reload.normalization {
+        let form = format_ident!("{}", form);
+        setters.extend(quote! { .normalize_unicode(#ctl10n::NormalizationForm::#form) });
+    }
+    if reload.show_keys {
+        setters.extend(quote! { .show_keys(true) });
+    }
+    if !reload.overridden.is_empty() {
+        let keys = &reload.overridden;
+        setters.extend(quote! { .overridden(&[ #( #keys ),* ]) });
+    }
+    quote! {
+        #[cfg(debug_assertions)]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #vis static CTL10N_RELOAD_OPTIONS: #ctl10n::ReloadOptions = #ctl10n::ReloadOptions::new() #setters;
+    }
+}
+
+/// Generate `tr!()` reloading strings from file of `reload` in debug builds, with `release`
+/// arms of `tr!()` used otherwise. Only arguments passed by name are substituted
+/// into reloaded strings, with positional ones strings are the compiled ones. Keys with
+/// defaults aren't reloaded when used without arguments.
+fn gen_hot_reload<'a>(
+    reload: &Reload,
+    keys: impl Iterator<Item = &'a String> + Clone,
+    defaults: &BTreeMap<String, BTreeMap<String, String>>,
+    release: Vec<TokenStream>,
+    options: &Options,
+) -> TokenStream {
+    let (local, ctl10n) = (options.local(), options.ctl10n());
+    let path = &reload.path;
+    let reload_options = quote! { &#local CTL10N_RELOAD_OPTIONS };
+    let mut arms: Vec<TokenStream> = keys
+        .clone()
+        .map(|key| {
+            quote! {
+                (#key) => { #ctl10n::reload_string(#path, #key, #local ctl10n_tr_inner!(#key), #reload_options) }
+            }
+        })
+        .collect();
+    arms.extend(gen_ident_arms(keys.clone(), "ctl10n_tr_reload", options));
+    for (key, ident) in key_idents(keys.clone()) {
+        if defaults.contains_key(key) {
+            let ident = format_ident!("{}", ident);
+            arms.push(quote! { (@defaults #ident) => { #local ctl10n_tr_inner!(@defaults #key) } });
+        }
+    }
+    for key in keys.filter(|key| defaults.contains_key(*key)) {
+        arms.push(quote! { (@defaults #key) => { #local ctl10n_tr_inner!(@defaults #key) } });
+    }
+    arms.push(quote! { (@defaults $key:tt) => { #local ctl10n_tr_reload!($key) } });
+    arms.push(quote! { ($key:tt) => { #local ctl10n_tr_inner!($key) } });
+
+    let debug = quote! { #[cfg(debug_assertions)] };
+    let mut tokens = gen_reload_options(reload, options);
+    tokens.extend(options.define_with(debug.clone(), "ctl10n_tr_reload", true, arms));
+    tokens.extend(options.define_with(debug, "tr", false, vec![
+        quote! { ($key:tt) => { #local ctl10n_tr_reload!(@defaults $key) } },
+        quote! {
+            ($key:tt, $( $name:ident = $value:expr ),* $(,)?) => {{
+                let value: &'static str = #local ctl10n_tr_reload!($key);
+                if value == #local ctl10n_tr_inner!($key) {
+                    ::std::format!(#local ctl10n_tr_inner!($key), $( $name = $value ),*)
+                } else {
+                    #ctl10n::format_named(
+                        value,
+                        &[ $( (::core::stringify!($name), &$value as &dyn ::std::fmt::Display) ),* ],
+                    )
+                }
+            }}
+        },
+        quote! { ($key:tt, $( $args:tt )* ) => { ::std::format!(#local ctl10n_tr_inner!($key), $( $args )* ) } },
+    ]));
+    tokens.extend(options.define_with(quote! { #[cfg(not(debug_assertions))] }, "tr", false, release));
+    tokens
+}
+
+/// Words which can't be used as keys without quotes
+const KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do",
+    "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static",
+    "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Identifier `key` can be written as, with `-` replaced by `_`, if it's a valid one
+fn key_ident(key: &str) -> Option<String> {
+    let ident = key.replace('-', "_");
+    let mut chars = ident.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && ident != "_"
+        && !KEYWORDS.contains(&ident.as_str());
+    Some(ident).filter(|_| valid)
+}
+
+/// Arms of macro `inner` forwarding identifiers of `keys` to the arms of keys, so
+/// `tr!(save_button)` works like `tr!("save-button")`. Keys mapped to the same
+/// identifier are only available as literals.
+fn gen_ident_arms<'a>(keys: impl Iterator<Item = &'a String>, inner: &str, options: &Options) -> Vec<TokenStream> {
+    let local = options.local();
+    let inner = format_ident!("{}", inner);
+    key_idents(keys)
+        .into_iter()
+        .map(|(key, ident)| {
+            let ident = format_ident!("{}", ident);
+            quote! { (#ident) => { #local #inner!(#key) } }
+        })
+        .collect()
+}
+
+/// Identifiers of `keys` which aren't shared with other keys
+fn key_idents<'a>(keys: impl Iterator<Item = &'a String>) -> Vec<(&'a String, String)> {
+    let idents: Vec<(&String, String)> = keys.filter_map(|key| Some((key, key_ident(key)?))).collect();
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, ident) in &idents {
+        *counts.entry(ident.clone()).or_default() += 1;
+    }
+    idents.into_iter().filter(|(_, ident)| counts[ident] == 1).collect()
+}
+
+/// Generate `CTL10N_KEYS` constant listing keys available via `tr!()`
+pub(crate) fn gen_keys<'a>(keys: impl Iterator<Item = &'a String>) -> TokenStream {
+    quote! {
+        /// All keys available via `tr!()`
+        #[allow(dead_code)]
+        pub const CTL10N_KEYS: &[&str] = &[ #( #keys ),* ];
+    }
+}
+
+/// Generate `CTL10N_CATALOG_HASH` constant with fingerprint of strings and, if they were
+/// read from files, `CTL10N_CATALOG_TIMESTAMP` with the latest modification time of them
+/// or `$SOURCE_DATE_EPOCH`
+pub(crate) fn gen_catalog_version(hash: &str, timestamp: Option<u64>, options: &Options) -> TokenStream {
+    let vis = options.vis();
+    let mut tokens = quote! {
+        /// Fingerprint of all keys, values and metadata the code was generated from
+        #[allow(dead_code)]
+        #vis const CTL10N_CATALOG_HASH: &str = #hash;
+    };
+    if let Some(timestamp) = timestamp {
+        tokens.extend(quote! {
+            /// Modification time of strings files the code was generated from, in seconds since Unix epoch
+            #[allow(dead_code)]
+            #vis const CTL10N_CATALOG_TIMESTAMP: u64 = #timestamp;
+        });
+    }
+    tokens
+}
+
+/// Generate `string_catalog()` function collecting strings available via `tr!()`, or via
+/// the binary catalog with `binary`. Keys with `cfg` which doesn't hold are left out.
+/// Values are literals, since exported macros can't be used in the crate defining them.
+pub(crate) fn gen_string_catalog(document: &Document, binary: bool, options: &Options) -> TokenStream {
+    let vis = options.vis();
+    let body = if binary {
+        quote! { CTL10N_CATALOG.iter().collect() }
+    } else {
+        let parse = |predicate: &String| -> TokenStream { predicate.parse().expect("cfg predicates are validated by parser") };
+        let inserts = document.in_order(&document.strings).into_iter().map(|(key, value)| {
+            if let Some(predicate) = document.cfgs.get(key).map(parse) {
+                return quote! { #[cfg(#predicate)] catalog.insert(#key, #value); };
+            }
+            let overrides = document.targets.get(key).into_iter().flatten();
+            let (predicates, values): (Vec<_>, Vec<_>) = overrides.map(|(predicate, value)| (parse(predicate), value)).unzip();
+            if predicates.is_empty() {
+                return quote! { catalog.insert(#key, #value); };
+            }
+            quote! {
+                #( #[cfg(#predicates)] catalog.insert(#key, #values); )*
+                #[cfg(not(any( #( #predicates ),* )))]
+                catalog.insert(#key, #value);
+            }
+        });
+        quote! {
+            let mut catalog = ::ctl10n::StringCatalog::new();
+            #( #inserts )*
+            catalog
+        }
+    };
+    quote! {
+        /// All strings available via `tr!()`
+        #[allow(dead_code)]
+        #vis fn string_catalog() -> ::ctl10n::StringCatalog {
+            #body
+        }
+    }
+}
+
+/// Generate `tr_static!()` macro expanding to `tr!()` of keys without placeholders or with
+/// defaults, so it's always `&'static str`, and to a compilation error for other keys. With
+/// hot reloading it uses compiled strings, so it still expands to literals. `tr_clap!()`
+/// is the same macro, named for use in `clap` attributes.
+pub(crate) fn gen_static(document: &Document, options: &Options) -> TokenStream {
+    let local = options.local();
+    let tr = if options.reload.is_some() {
+        format_ident!("ctl10n_tr_inner")
+    } else {
+        format_ident!("tr")
+    };
+    let strings = document.in_order(&document.strings);
+    let mut arms: Vec<TokenStream> = strings
+        .iter()
+        .map(|&(key, value)| {
+            let mut placeholders: Vec<String> = Vec::new();
+            for placeholder in parse_placeholders(value).unwrap_or_default() {
+                let placeholder = placeholder.to_string();
+                if !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                }
+            }
+            if document.defaults.contains_key(key) {
+                quote! { (#key) => { #local ctl10n_tr_inner!(@defaults #key) } }
+            } else if placeholders.is_empty() {
+                quote! { (#key) => { #local #tr!(#key) } }
+            } else {
+                let message = format!("Key `{}` requires arguments {}", key, placeholders.join(", "));
+                quote! { (#key) => { ::core::compile_error!(#message) } }
+            }
+        })
+        .collect();
+    arms.extend(gen_ident_arms(strings.iter().map(|(key, _)| *key), "tr_static", options));
+    arms.push(quote! {
+        ($key:tt) => {
+            ::core::compile_error!(::core::concat!("There is no string for key `", ::core::stringify!($key), "`"))
+        }
+    });
+    let mut tokens = options.define("tr_static", false, arms);
+    tokens.extend(options.define("tr_clap", false, vec![quote! { ($key:tt) => { #local tr_static!($key) } }]));
+    tokens
+}
+
+/// Generate `tr_cow!()` macro wrapping `tr!()` into `Cow<'static, str>`, borrowed
+/// without arguments and owned with them
+pub(crate) fn gen_cow(options: &Options) -> TokenStream {
+    let local = options.local();
+    options.define("tr_cow", false, vec![
+        quote! { ($key:tt) => { ::std::borrow::Cow::<'static, str>::Borrowed(#local tr!($key)) } },
+        quote! {
+            ($key:tt, $( $args:tt )* ) => {
+                ::std::borrow::Cow::<'static, str>::Owned(#local tr!($key, $( $args )* ))
+            }
+        },
+    ])
+}
+
+/// Generate `tr_list!()` macro for array values
+pub(crate) fn gen_lists(document: &Document, options: &Options) -> TokenStream {
+    let local = options.local();
+    let mut tokens = TokenStream::new();
+    let mut arms: Vec<TokenStream> = document
+        .in_order(&document.lists)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (key, items))| {
+            let list = quote! { &[ #( #items ),* ] };
+            match document.cfgs.get(key) {
+                Some(predicate) => {
+                    let helper = format!("ctl10n_tr_list_cfg_{}", idx);
+                    tokens.extend(gen_cfg_helper(&helper, key, predicate, list, options));
+                    let helper = format_ident!("{}", helper);
+                    quote! { (#key) => { #local #helper!() } }
+                }
+                None => quote! { (#key) => { #list } },
+            }
+        })
+        .collect();
+    arms.extend(gen_ident_arms(document.in_order(&document.lists).into_iter().map(|(key, _)| key), "ctl10n_tr_list_inner", options));
+    arms.push(quote! {
+        ($key:tt) => {
+            ::core::compile_error!(::core::concat!("There is no list for key `", ::core::stringify!($key), "`"))
+        }
+    });
+
+    tokens.extend(options.define("ctl10n_tr_list_inner", true, arms));
+    tokens.extend(options.define("tr_list", false, vec![
+        quote! {
+            ($key:tt) => {{
+                const CTL10N_LIST: &[&str] = #local ctl10n_tr_list_inner!($key);
+                CTL10N_LIST
+            }}
+        },
+        quote! { ($key:tt, $idx:expr) => { #local tr_list!($key)[$idx] } },
+    ]));
+    tokens
+}
+
+/// Identifier made of `name` with characters not allowed in identifiers replaced by `_`
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Body of a test calling `format!()` with `value` of `key`, passing `Dummy` for
+/// arguments it formats and `0usize` for the ones used as width or precision
+fn gen_format_call(key: &str, value: &str) -> TokenStream {
+    let (placeholders, counts) = match parse_placeholders(value).and_then(|p| Ok((p, parse_counts(value)?))) {
+        Ok(parsed) => parsed,
+        Err(detail) => {
+            let message = format!("Key `{}` isn't a valid format string: {}", key, detail);
+            return quote! { ::core::compile_error!(#message); };
+        }
+    };
+    let mut positional = 0;
+    let mut named = Vec::new();
+    for placeholder in placeholders.iter().chain(&counts) {
+        match placeholder {
+            Placeholder::Indexed(idx) | Placeholder::Implicit(idx) => positional = positional.max(idx + 1),
+            Placeholder::Named(name) if !named.contains(name) => named.push(name.clone()),
+            Placeholder::Named(_) => {}
+        }
+    }
+    let dummy = |is_count: bool| if is_count { quote! { 0usize } } else { quote! { Dummy } };
+    let positional = (0..positional).map(|idx| {
+        dummy(counts.iter().any(|count| matches!(count, Placeholder::Indexed(i) | Placeholder::Implicit(i) if *i == idx)))
+    });
+    let named = named.iter().map(|name| {
+        let value = dummy(counts.contains(&Placeholder::Named(name.clone())));
+        let name = format_ident!("{}", name);
+        quote! { #name = #value }
+    });
+    quote! { let _ = format!(#value, #( #positional, )* #( #named ),*); }
+}
+
+/// Tests calling `format!()` with every string having placeholders
+fn gen_format_test_fns(document: &Document) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for (idx, (key, value)) in document.in_order(&document.strings).into_iter().enumerate() {
+        if parse_placeholders(value).is_ok_and(|placeholders| placeholders.is_empty()) {
+            continue;
+        }
+        let cfg = match document.cfgs.get(key) {
+            Some(predicate) => {
+                let predicate: TokenStream = predicate.parse().expect("cfg predicates are validated by parser");
+                quote! { #[cfg(#predicate)] }
+            }
+            None => TokenStream::new(),
+        };
+        let name = format_ident!("format_{}_{}", idx, sanitize(key));
+        let call = gen_format_call(key, value);
+        tokens.extend(quote! {
+            #cfg
+            #[test]
+            fn #name() {
+                #call
+            }
+        });
+        let overrides = document.targets.get(key).into_iter().flatten();
+        for (target, (predicate, value)) in overrides.enumerate() {
+            let predicate: TokenStream = predicate.parse().expect("cfg predicates are validated by parser");
+            let name = format_ident!("format_{}_{}_target_{}", idx, sanitize(key), target);
+            let call = gen_format_call(key, value);
+            tokens.extend(quote! {
+                #[cfg(#predicate)]
+                #[test]
+                fn #name() {
+                    #call
+                }
+            });
+        }
+    }
+    tokens
+}
+
+/// Items using `tr_static!()` of every string without placeholders in `#[doc]` attribute,
+/// so the module doesn't compile if one doesn't expand to a literal
+fn gen_literal_checks(document: &Document) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for (key, value) in document.in_order(&document.strings) {
+        if !parse_placeholders(value).unwrap_or_default().is_empty() {
+            continue;
+        }
+        if let Some(predicate) = document.cfgs.get(key) {
+            let predicate: TokenStream = predicate.parse().expect("cfg predicates are validated by parser");
+            tokens.extend(quote! { #[cfg(#predicate)] });
+        }
+        tokens.extend(quote! {
+            #[doc = tr_static!(#key)]
+            const _: () = ();
+        });
+    }
+    tokens
+}
+
+/// Expression for `path`, relative to the crate being tested if it's relative
+fn path_expr(path: &Path) -> TokenStream {
+    let display = path.display().to_string();
+    if path.is_relative() {
+        quote! { ::core::concat!(::core::env!("CARGO_MANIFEST_DIR"), "/", #display) }
+    } else {
+        quote! { #display }
+    }
+}
+
+/// Options of [`Builder::check_locale`](crate::Builder::check_locale) in generated tests
+pub(crate) struct LocaleChecks<'a> {
+    pub parse_options: &'a ParseOptions,
+    pub include_duplicates: Severity,
+    pub fill_missing: bool,
+}
+
+/// Generate a test for every strings file of `locales` checking it against `template`
+/// with [`Builder::check_locale`](crate::Builder::check_locale) configured with `checks`
+pub(crate) fn gen_locale_tests(template: &Path, locales: &[(String, PathBuf)], checks: &LocaleChecks) -> TokenStream {
+    let parse_options = checks.parse_options;
+    let mut builder = quote! { ::ctl10n::Builder::new() };
+    if parse_options.lenient_values {
+        builder.extend(quote! { .lenient_values(true) });
+    }
+    if let Some(separator) = &parse_options.join_arrays {
+        builder.extend(quote! { .join_arrays(#separator) });
+    }
+    // Variants are named as they're printed
+    let whitespace = format_ident!("{}", format!("{:?}", parse_options.whitespace));
+    let escapes = format_ident!("{}", format!("{:?}", parse_options.escapes));
+    let include_duplicates = format_ident!("{}", format!("{:?}", checks.include_duplicates));
+    builder.extend(quote! {
+        .whitespace(::ctl10n::Whitespace::#whitespace)
+        .escapes(::ctl10n::Escapes::#escapes)
+        .include_duplicates(::ctl10n::Severity::#include_duplicates)
+    });
+    if checks.fill_missing {
+        builder.extend(quote! { .fill_missing(true) });
+    }
+    let template = path_expr(template);
+    let tests = locales.iter().map(|(locale, path)| {
+        let name = format_ident!("locale_{}", sanitize(locale));
+        let path = path_expr(path);
+        quote! {
+            #[test]
+            fn #name() {
+                if let Err(err) = #builder.check_locale(#path, #template) {
+                    panic!("{}", err);
+                }
+            }
+        }
+    });
+    quote! { #( #tests )* }
+}
+
+/// Generate `#[cfg(test)] mod ctl10n_format_tests` checking that every string with
+/// placeholders is a valid `format!()` string. Tests for named documents are put
+/// into `locale_<name>` submodules. For the unnamed document it also checks that
+/// strings without placeholders expand to literals if `literals` is set (strings
+/// of the binary backend aren't literals), unless macros aren't in scope of the
+/// module by name.
+pub(crate) fn gen_format_tests<'a>(
+    documents: impl IntoIterator<Item = (Option<&'a str>, &'a Document)>,
+    literals: bool,
+    options: &Options,
+) -> TokenStream {
+    let mut tests = TokenStream::new();
+    for (name, document) in documents {
+        let mut fns = gen_format_test_fns(document);
+        if literals && name.is_none() && !options.macro_export && !options.decl_macro {
+            fns.extend(gen_literal_checks(document));
+        }
+        match name {
+            Some(name) => {
+                let name = format_ident!("locale_{}", sanitize(name));
+                tests.extend(quote! {
+                    mod #name {
+                        use super::Dummy;
+                        #fns
+                    }
+                });
+            }
+            None => tests.extend(fns),
+        }
+    }
+    let traits = [
+        quote! { Display },
+        quote! { Debug },
+        quote! { LowerHex },
+        quote! { UpperHex },
+        quote! { Octal },
+        quote! { Binary },
+        quote! { LowerExp },
+        quote! { UpperExp },
+        quote! { Pointer },
+    ];
+    quote! {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod ctl10n_format_tests {
+            /// Argument implementing all formatting traits
+            #[allow(dead_code)]
+            struct Dummy;
+
+            #(
+                impl ::std::fmt::#traits for Dummy {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        f.write_str("dummy")
+                    }
+                }
+            )*
+
+            #tests
+        }
+    }
+}
+
+/// Generate code including one of generated locale files depending on enabled
+/// cargo features
+pub(crate) fn gen_locale_switch(locales: &[LocaleOutput]) -> TokenStream {
+    let features: Vec<&str> = locales.iter().map(|l| l.feature.as_str()).collect();
+    let outputs = locales.iter().map(|l| l.output.display().to_string());
+    let message = format!(
+        "ctl10n: exactly one of features {} must be enabled",
+        features.iter().map(|f| format!("`{}`", f)).collect::<Vec<_>>().join(", "),
+    );
+
+    quote! {
+        const _: () = assert!(0 #( + cfg!(feature = #features) as usize )* == 1, #message);
+
+        #(
+            #[cfg(feature = #features)]
+            include!(#outputs);
+        )*
+    }
+}
+
+/// Generate `Locale` enum with a variant for every locale. With `runtime_default` the current
+/// locale is the one selected in `CTL10N_CATALOG`, starting with it, otherwise the one
+/// enabled by cargo feature.
+pub(crate) fn gen_locale_enum(locales: &[&str], runtime_default: Option<&str>, options: &Options) -> TokenStream {
+    let vis = options.vis();
+    let variants: Vec<_> = locales.iter().map(|locale| format_ident!("{}", variant_name(locale))).collect();
+    let docs = locales.iter().map(|locale| format!("`{}`", locale));
+    let current = if let Some(default) = runtime_default {
+        quote! {
+            /// Locale currently selected in `CTL10N_CATALOG`
+            pub fn current() -> Self {
+                let tag = CTL10N_CATALOG.locale().unwrap_or(#default);
+                Self::from_tag(tag).expect("all embedded locales have variants")
+            }
+
+            /// Switch `CTL10N_CATALOG` to this locale
+            pub fn set_current(self) {
+                CTL10N_CATALOG.set_locale(self.as_tag());
+            }
+        }
+    } else {
+        let features = locales.iter().map(|locale| feature_name(locale));
+        quote! {
+            /// Locale enabled by cargo feature
+            pub const fn current() -> Self {
+                #( if ::core::cfg!(feature = #features) { return Self::#variants; } )*
+                ::core::unreachable!()
+            }
+        }
+    };
+    quote! {
+        /// Locales strings are generated for
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[allow(dead_code)]
+        #vis enum Locale {
+            #( #[doc = #docs] #variants, )*
+        }
+
+        #[allow(dead_code)]
+        impl Locale {
+            /// All locales
+            pub const ALL: &'static [Locale] = &[ #( Self::#variants ),* ];
+
+            /// Tag of the locale, as in name of its strings file
+            pub const fn as_tag(self) -> &'static str {
+                match self {
+                    #( Self::#variants => #locales, )*
+                }
+            }
+
+            /// Locale with given tag, ignoring case and treating `_` as `-`
+            pub fn from_tag(tag: &str) -> ::core::option::Option<Self> {
+                let tag = tag.replace('_', "-");
+                Self::ALL.iter().copied().find(|locale| locale.as_tag().replace('_', "-").eq_ignore_ascii_case(&tag))
+            }
+
+            #current
+        }
+    }
+}
+
+/// Generate `negotiate_locale()` choosing between embedded locales
+pub(crate) fn gen_negotiate(default: &str, locales: &[&str], options: &Options) -> TokenStream {
+    let vis = options.vis();
+    quote! {
+        /// Best embedded locale for given BCP-47 tags in order of preference
+        #[allow(dead_code)]
+        #vis fn negotiate_locale(requested: &[&str]) -> &'static str {
+            ::ctl10n::negotiate_locale(requested, &[ #( #locales ),* ], #default)
+        }
+    }
+}
+
+/// Generate hidden re-export of `ctl10n` for exported macros using it
+pub(crate) fn gen_reexport(options: &Options) -> TokenStream {
+    if options.macro_export || options.decl_macro {
+        quote! {
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            pub use ::ctl10n as __ctl10n;
+        }
+    } else {
+        TokenStream::new()
+    }
+}
+
+/// Generate `tr_html!()` macro for `html` keys, escaping arguments with `HtmlEscaped`.
+/// With `binary` arguments are substituted at runtime with `format_named()`.
+pub(crate) fn gen_html<'a>(
+    keys: impl Iterator<Item = &'a String>,
+    binary: bool,
+    options: &Options,
+) -> TokenStream {
+    let (local, ctl10n) = (options.local(), options.ctl10n());
+    let mut arms: Vec<TokenStream> = keys.map(|key| quote! { (#key) => { #local tr!(#key) } }).collect();
+    arms.push(quote! {
+        ($key:tt) => {
+            ::core::compile_error!(::core::concat!("Key `", ::core::stringify!($key), "` is not marked with `html = true`"))
+        }
+    });
+
+    let mut tokens = options.define("ctl10n_tr_html_inner", true, arms);
+    let with_args = if binary {
+        vec![quote! {
+            ($key:tt, $( $name:ident = $value:expr ),* $(,)?) => {
+                #ctl10n::format_named(
+                    #local ctl10n_tr_html_inner!($key),
+                    &[ $( (
+                        ::core::stringify!($name),
+                        &#ctl10n::HtmlEscaped(&$value) as &dyn ::std::fmt::Display,
+                    ) ),* ],
+                )
+            }
+        }]
+    } else {
+        vec![
+            quote! {
+                ($key:tt, $( $name:ident = $value:expr ),* $(,)?) => {
+                    ::std::format!(
+                        #local ctl10n_tr_html_inner!($key),
+                        $( $name = #ctl10n::HtmlEscaped(&$value) ),*
+                    )
+                }
+            },
+            quote! {
+                ($key:tt, $( $value:expr ),* $(,)?) => {
+                    ::std::format!(#local ctl10n_tr_html_inner!($key), $( #ctl10n::HtmlEscaped(&$value) ),*)
+                }
+            },
+        ]
+    };
+    let mut arms = vec![quote! { ($key:tt) => { #local ctl10n_tr_html_inner!($key) } }];
+    arms.extend(with_args);
+    tokens.extend(options.define("tr_html", false, arms));
+    tokens
+}
+
+/// Generate `FORMAT_<NAME>` constant for every entry of `[formats]` table
+pub(crate) fn gen_formats(formats: &BTreeMap<String, String>, options: &Options) -> TokenStream {
+    let vis = options.vis();
+    let names = formats.keys().map(|name| format_ident!("{}", format_constant(name)));
+    let docs = formats.keys().map(|name| format!("Format `{}` of the locale", name));
+    let patterns = formats.values();
+    quote! {
+        #(
+            #[doc = #docs]
+            #[allow(dead_code)]
+            #vis const #names: &str = #patterns;
+        )*
+    }
+}
+
+/// Generate `format_<name>()` returning format of locale currently selected in
+/// `CTL10N_CATALOG` for every format of any locale. Locales without the format
+/// use the one of `default` locale, if it has it.
+pub(crate) fn gen_runtime_formats(
+    locales: &[(&str, &BTreeMap<String, String>)],
+    default: &str,
+    options: &Options,
+) -> TokenStream {
+    let vis = options.vis();
+    let mut names: Vec<&String> = locales.iter().flat_map(|(_, formats)| formats.keys()).collect();
+    names.sort();
+    names.dedup();
+    let default_formats = locales.iter().find(|(locale, _)| *locale == default).map(|(_, formats)| *formats);
+    let mut tokens = TokenStream::new();
+    for name in names {
+        let function = format_ident!("{}", format_constant(name).to_lowercase());
+        let doc = format!("Format `{}` of the current locale", name);
+        let arms = locales.iter().filter_map(|(locale, formats)| {
+            let pattern = formats.get(name)?;
+            Some(quote! { #locale => #pattern, })
+        });
+        let fallback = default_formats
+            .and_then(|formats| formats.get(name))
+            .or_else(|| locales.iter().find_map(|(_, formats)| formats.get(name)));
+        tokens.extend(quote! {
+            #[doc = #doc]
+            #[allow(dead_code)]
+            #vis fn #function() -> &'static str {
+                match CTL10N_CATALOG.locale().unwrap_or(#default) {
+                    #( #arms )*
+                    _ => #fallback,
+                }
+            }
+        });
+    }
+    tokens
+}
+
+/// Generate `trn_num!()` macro formatting arguments with `NumberFormat` given by
+/// `format` expression. `positional` arguments are only supported by `Backend::Macro`.
+fn gen_trn_num(format: TokenStream, positional: bool, options: &Options) -> TokenStream {
+    let local = options.local();
+    let mut arms = vec![quote! {
+        ($key:tt, $( $name:ident = $value:expr ),* $(,)?) => {
+            #local tr!($key, $( $name = #format.format($value) ),*)
+        }
+    }];
+    if positional {
+        arms.push(quote! {
+            ($key:tt, $( $value:expr ),* $(,)?) => { #local tr!($key, $( #format.format($value) ),*) }
+        });
+    }
+    options.define("trn_num", false, arms)
+}
+
+/// Generate `CTL10N_NUMBER_FORMAT` constant with given separators and `trn_num!()` using it
+pub(crate) fn gen_number_format(separators: (&str, &str), positional: bool, options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let (decimal, group) = separators;
+    let mut tokens = quote! {
+        /// Number format of the locale, used by `trn_num!()`
+        #[allow(dead_code)]
+        #vis const CTL10N_NUMBER_FORMAT: ::ctl10n::NumberFormat = ::ctl10n::NumberFormat::new(#decimal, #group);
+    };
+    tokens.extend(gen_trn_num(quote! { #local CTL10N_NUMBER_FORMAT }, positional, options));
+    tokens
+}
+
+/// Generate `number_format()` returning separators of locale currently selected in
+/// `CTL10N_CATALOG` and `trn_num!()` using it
+pub(crate) fn gen_number_formats(formats: &[(&str, (&str, &str))], default: &str, options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let names = formats.iter().map(|(name, _)| name);
+    let decimals = formats.iter().map(|(_, (decimal, _))| decimal);
+    let groups = formats.iter().map(|(_, (_, group))| group);
+    let mut tokens = quote! {
+        /// Number format of the current locale, used by `trn_num!()`
+        #[allow(dead_code)]
+        #vis fn number_format() -> &'static ::ctl10n::NumberFormat {
+            static FORMATS: &[(&str, ::ctl10n::NumberFormat)] = &[
+                #( (#names, ::ctl10n::NumberFormat::new(#decimals, #groups)) ),*
+            ];
+            let locale = CTL10N_CATALOG.locale().unwrap_or(#default);
+            let (_, format) = FORMATS
+                .iter()
+                .find(|(name, _)| *name == locale)
+                .expect("formats of all embedded locales are generated");
+            format
+        }
+    };
+    tokens.extend(gen_trn_num(quote! { #local number_format() }, false, options));
+    tokens
+}
+
+/// Generate `tr_money!()` macro formatting amounts with `CurrencyFormat` given by `format` expression.
+/// Every named argument is an amount, values which aren't `Amount` (like a key without
+/// arguments) fail to compile.
+fn gen_tr_money(format: TokenStream, options: &Options) -> TokenStream {
+    let local = options.local();
+    let arms = vec![
+        quote! {
+            ($key:tt, $( $name:ident = $value:expr ),+ $(,)?) => {
+                #local tr!($key, $( $name = #format.format($value) ),+)
+            }
+        },
+        quote! {
+            ($amount:expr) => { #format.format($amount) }
+        },
+    ];
+    options.define("tr_money", false, arms)
+}
+
+/// Expression constructing `CurrencyFormat` from `[currency]` table and number separators
+fn currency_format(currency: &Currency, separators: (&str, &str)) -> TokenStream {
+    let Currency { symbol, position, space, digits } = currency;
+    let position = match position {
+        SymbolPosition::Before => quote! { ::ctl10n::SymbolPosition::Before },
+        SymbolPosition::After => quote! { ::ctl10n::SymbolPosition::After },
+    };
+    let (decimal, group) = separators;
+    quote! {
+        ::ctl10n::CurrencyFormat::new(#symbol, #position, #space, #digits, ::ctl10n::NumberFormat::new(#decimal, #group))
+    }
+}
+
+/// Generate `CTL10N_CURRENCY_FORMAT` constant and `tr_money!()` using it
+pub(crate) fn gen_currency_format(currency: &Currency, separators: (&str, &str), options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let format = currency_format(currency, separators);
+    let mut tokens = quote! {
+        /// Currency format of the locale, used by `tr_money!()`
+        #[allow(dead_code)]
+        #vis const CTL10N_CURRENCY_FORMAT: ::ctl10n::CurrencyFormat = #format;
+    };
+    tokens.extend(gen_tr_money(quote! { #local CTL10N_CURRENCY_FORMAT }, options));
+    tokens
+}
+
+/// Generate `currency_format()` returning currency format of locale currently selected
+/// in `CTL10N_CATALOG` and `tr_money!()` using it
+pub(crate) fn gen_currency_formats(
+    formats: &[(&str, &Currency, (&str, &str))],
+    default: &str,
+    options: &Options,
+) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let names = formats.iter().map(|(name, _, _)| name);
+    let formats = formats.iter().map(|(_, currency, separators)| currency_format(currency, *separators));
+    let mut tokens = quote! {
+        /// Currency format of the current locale, used by `tr_money!()`
+        #[allow(dead_code)]
+        #vis fn currency_format() -> &'static ::ctl10n::CurrencyFormat {
+            static FORMATS: &[(&str, ::ctl10n::CurrencyFormat)] = &[
+                #( (#names, #formats) ),*
+            ];
+            let locale = CTL10N_CATALOG.locale().unwrap_or(#default);
+            let (_, format) = FORMATS
+                .iter()
+                .find(|(name, _)| *name == locale)
+                .expect("formats of all embedded locales are generated");
+            format
+        }
+    };
+    tokens.extend(gen_tr_money(quote! { #local currency_format() }, options));
+    tokens
+}
+
+/// Generate `CTL10N_LIST_FORMAT` constant with given separators and `tr_join!()` using it
+pub(crate) fn gen_list_format(separators: [&str; 3], options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let [separator, last, pair] = separators;
+    let mut tokens = quote! {
+        /// List format of the locale, used by `tr_join!()`
+        #[allow(dead_code)]
+        #vis const CTL10N_LIST_FORMAT: ::ctl10n::ListFormat = ::ctl10n::ListFormat::new(#separator, #last, #pair);
+    };
+    tokens.extend(options.define("tr_join", false, vec![
+        quote! { ($items:expr) => { #local CTL10N_LIST_FORMAT.join($items) } },
+    ]));
+    tokens
+}
+
+/// Generate `list_format()` returning separators of locale currently selected in
+/// `CTL10N_CATALOG` and `tr_join!()` using it
+pub(crate) fn gen_list_formats(formats: &[(&str, [&str; 3])], default: &str, options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let names = formats.iter().map(|(name, _)| name);
+    let separators = formats.iter().map(|(_, [separator, last, pair])| quote! { #separator, #last, #pair });
+    let mut tokens = quote! {
+        /// List format of the current locale, used by `tr_join!()`
+        #[allow(dead_code)]
+        #vis fn list_format() -> &'static ::ctl10n::ListFormat {
+            static FORMATS: &[(&str, ::ctl10n::ListFormat)] = &[
+                #( (#names, ::ctl10n::ListFormat::new(#separators)) ),*
+            ];
+            let locale = CTL10N_CATALOG.locale().unwrap_or(#default);
+            let (_, format) = FORMATS
+                .iter()
+                .find(|(name, _)| *name == locale)
+                .expect("formats of all embedded locales are generated");
+            format
+        }
+    };
+    tokens.extend(options.define("tr_join", false, vec![
+        quote! { ($items:expr) => { #local list_format().join($items) } },
+    ]));
+    tokens
+}
+
+/// Generate `tr_trunc!()` macro truncating messages with ellipsis given by `ellipsis` expression
+fn gen_tr_trunc(ellipsis: TokenStream, options: &Options) -> TokenStream {
+    let (local, ctl10n) = (options.local(), options.ctl10n());
+    options.define("tr_trunc", false, vec![
+        quote! {
+            ($key:tt, $max:expr) => {
+                #ctl10n::truncate(&#local tr!($key), $max, #ellipsis).into_owned()
+            }
+        },
+        quote! {
+            ($key:tt, $max:expr, $( $args:tt )+) => {
+                #ctl10n::truncate(&#local tr!($key, $( $args )+), $max, #ellipsis).into_owned()
+            }
+        },
+    ])
+}
+
+/// Generate `CTL10N_ELLIPSIS` constant and `tr_trunc!()` using it
+pub(crate) fn gen_truncate(ellipsis: &str, options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let mut tokens = quote! {
+        /// Ellipsis of the locale, used by `tr_trunc!()`
+        #[allow(dead_code)]
+        #vis const CTL10N_ELLIPSIS: &str = #ellipsis;
+    };
+    tokens.extend(gen_tr_trunc(quote! { #local CTL10N_ELLIPSIS }, options));
+    tokens
+}
+
+/// Generate `ellipsis()` returning ellipsis of locale currently selected in
+/// `CTL10N_CATALOG` and `tr_trunc!()` using it
+pub(crate) fn gen_truncates(ellipses: &[(&str, &str)], default: &str, options: &Options) -> TokenStream {
+    let (local, vis) = (options.local(), options.vis());
+    let arms = ellipses.iter().map(|(locale, ellipsis)| quote! { #locale => #ellipsis, });
+    let mut tokens = quote! {
+        /// Ellipsis of the current locale, used by `tr_trunc!()`
+        #[allow(dead_code)]
+        #vis fn ellipsis() -> &'static str {
+            match CTL10N_CATALOG.locale().unwrap_or(#default) {
+                #( #arms )*
+                _ => "…",
+            }
+        }
+    };
+    tokens.extend(gen_tr_trunc(quote! { #local ellipsis() }, options));
+    tokens
+}
+
+/// Expression with plural category of `kind` of `n` in `locale`, using custom
+/// rules of the locale from `document` if there are any
+fn gen_plural_category(locale: &str, document: &Document, kind: PluralKind, options: &Options) -> TokenStream {
+    let ctl10n = options.ctl10n();
+    let (rules, builtin) = match kind {
+        PluralKind::Cardinal => (&document.cardinal_rules, quote! { cardinal_category }),
+        PluralKind::Ordinal => (&document.ordinal_rules, quote! { ordinal_category }),
+    };
+    if rules.is_empty() {
+        return quote! { #ctl10n::#builtin(#locale, n) };
+    }
+    let conditions = rules.values().map(|rule| parse_condition(rule).expect("plural rules are validated by parser"));
+    let categories = rules.keys().map(|category| format_ident!("{}", format!("{:?}", category)));
+    quote! {
+        #( if #conditions { #ctl10n::PluralCategory::#categories } else )* { #ctl10n::PluralCategory::Other }
+    }
+}
+
+/// Expression formatting plural message of `kind` of `locale` for `n`
+fn gen_plural_match(
+    locale: &str,
+    document: &Document,
+    kind: PluralKind,
+    messages: &BTreeMap<PluralCategory, String>,
+    options: &Options,
+) -> TokenStream {
+    let ctl10n = options.ctl10n();
+    let arms = messages.iter().map(|(category, message)| {
+        let pattern = match category {
+            PluralCategory::Other => quote! { _ },
+            category => {
+                let variant = format_ident!("{}", format!("{:?}", category));
+                quote! { #ctl10n::PluralCategory::#variant }
+            }
+        };
+        let uses_n = !parse_placeholders(message).unwrap_or_default().is_empty();
+        let args = if uses_n { quote! { , n = n } } else { TokenStream::new() };
+        quote! { #pattern => ::std::format!(#message #args), }
+    });
+    let category = gen_plural_category(locale, document, kind, options);
+    quote! {
+        match #category {
+            #( #arms )*
+        }
+    }
+}
+
+/// Generate `trn!()` for `[cardinal]` or `trn_ord!()` for `[ordinal]` messages of `locales`,
+/// depending on `kind`. With `runtime_default` there are multiple locales and the message
+/// is chosen by locale currently selected in `CTL10N_CATALOG`, using the default locale
+/// for keys missing from the current one.
+pub(crate) fn gen_plurals(
+    locales: &[(&str, &Document)],
+    kind: PluralKind,
+    runtime_default: Option<&str>,
+    options: &Options,
+) -> TokenStream {
+    let local = options.local();
+    let (default, default_document) = match runtime_default {
+        Some(default) => *locales.iter().find(|(locale, _)| *locale == default).expect("default locale is embedded"),
+        None => locales[0],
+    };
+    let expect = format!("{} must be a non-negative integer", kind.name());
+    let mut arms: Vec<TokenStream> = default_document.plurals(kind)
+        .iter()
+        .map(|(key, messages)| {
+            let fallback = gen_plural_match(default, default_document, kind, messages, options);
+            let body = match runtime_default {
+                Some(_) => {
+                    let arms = locales.iter().filter_map(|(locale, document)| {
+                        let messages = document.plurals(kind).get(key)?;
+                        let body = gen_plural_match(locale, document, kind, messages, options);
+                        Some(quote! { #locale => #body, })
+                    });
+                    quote! {
+                        match #local CTL10N_CATALOG.locale().unwrap_or(#default) {
+                            #( #arms )*
+                            _ => #fallback,
+                        }
+                    }
+                }
+                None => fallback,
+            };
+            quote! {
+                (#key, $n:expr) => {{
+                    let n = <u64 as ::std::convert::TryFrom<_>>::try_from($n).expect(#expect);
+                    #body
+                }}
+            }
+        })
+        .collect();
+    let missing = format!("There is no {} for key `", kind.name());
+    arms.push(quote! {
+        ($key:tt, $( $rest:tt )*) => {
+            ::core::compile_error!(::core::concat!(#missing, ::core::stringify!($key), "`"))
+        }
+    });
+
+    let (name, inner) = match kind {
+        PluralKind::Cardinal => ("trn", "ctl10n_trn_inner"),
+        PluralKind::Ordinal => ("trn_ord", "ctl10n_tr_ord_inner"),
+    };
+    let mut tokens = options.define(inner, true, arms);
+    let inner = format_ident!("{}", inner);
+    tokens.extend(options.define(name, false, vec![
+        quote! { ($key:tt, $n:expr) => { #local #inner!($key, $n) } },
+    ]));
+    tokens
+}
+
+/// Generate `tr!()` macro looking strings up in binary catalog at `catalog_path`.
+/// `locales` are embedded as well and can be selected at runtime; if the default catalog
+/// is one of them, it's only embedded once.
+pub(crate) fn gen_binary_loader(
+    catalog_path: &Path,
+    locales: &[(&str, &Path)],
+    options: &Options,
+) -> TokenStream {
+    let (local, ctl10n, vis) = (options.local(), options.ctl10n(), options.vis());
+    let default = locales.iter().position(|(_, path)| *path == catalog_path);
+    let catalog_path = catalog_path.display().to_string();
+    let names = locales.iter().map(|(name, _)| name);
+    let paths = locales.iter().map(|(_, path)| path.display().to_string());
+    let locales = quote! { &[ #( (#names, include_bytes!(#paths)) ),* ] };
+
+    let catalog = match default {
+        Some(default) => quote! { ::ctl10n::ActiveCatalog::with_default_locale(#locales, #default) },
+        None => quote! { ::ctl10n::ActiveCatalog::with_locales(include_bytes!(#catalog_path), #locales) },
+    };
+    let mut tokens = quote! {
+        #vis static CTL10N_CATALOG: ::ctl10n::ActiveCatalog = #catalog;
+    };
+    tokens.extend(options.define("tr", false, vec![
+        quote! {
+            ($key:expr) => {
+                match $key {
+                    key => #local CTL10N_CATALOG.get(key).unwrap_or(key),
+                }
+            }
+        },
+        quote! {
+            ($key:expr, $( $name:ident = $value:expr ),* $(,)?) => {
+                #ctl10n::format_named(
+                    #local tr!($key),
+                    &[ $( (::core::stringify!($name), &$value as &dyn ::std::fmt::Display) ),* ],
+                )
+            }
+        },
+    ]));
+    tokens.extend(options.define("tr_in", false, vec![
+        quote! { ($namespace:literal, $key:literal) => { #local tr!(::core::concat!($namespace, ".", $key)) } },
+        quote! {
+            ($namespace:literal, $key:literal, $( $args:tt )* ) => {
+                #local tr!(::core::concat!($namespace, ".", $key), $( $args )* )
+            }
+        },
+        // Other namespaces, e.g. constants, are joined with the key at runtime
+        quote! {
+            ($namespace:expr, $key:expr) => {
+                match ($namespace, $key) {
+                    (namespace, key) => #local CTL10N_CATALOG.get_in(namespace, key).unwrap_or(key),
+                }
+            }
+        },
+        quote! {
+            ($namespace:expr, $key:expr, $( $name:ident = $value:expr ),* $(,)?) => {
+                #ctl10n::format_named(
+                    #local tr_in!($namespace, $key),
+                    &[ $( (::core::stringify!($name), &$value as &dyn ::std::fmt::Display) ),* ],
+                )
+            }
+        },
+    ]));
+    tokens
+}
+
+/// Convert generated tokens to source code, formatting it if `pretty` is set and
+/// the `pretty` feature is enabled
+pub(crate) fn render(tokens: TokenStream, pretty: bool) -> String {
+    #[cfg(feature = "pretty")]
+    {
+        if pretty {
+            if let Ok(mut file) = syn::parse2::<syn::File>(tokens.clone()) {
+                // prettyplease can't format items syn doesn't understand, like `pub macro`
+                let mut result = String::new();
+                for item in std::mem::take(&mut file.items) {
+                    match item {
+                        syn::Item::Verbatim(tokens) => {
+                            result.push_str(&tokens.to_string());
+                            result.push('\n');
+                        }
+                        item => {
+                            file.items = vec![item];
+                            result.push_str(&prettyplease::unparse(&file));
+                        }
+                    }
+                }
+                return result;
+            }
+        }
+    }
+    #[cfg(not(feature = "pretty"))]
+    let _ = pretty;
+    tokens.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use super::{gen_binary_loader, Options};
+    use crate::{Backend, Builder};
+
+    /// Empty directory for crates of test `name`
+    fn crate_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ctl10n-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn rustc(dir: &Path, args: &[&str]) {
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = Command::new(rustc)
+            .args(["--edition", "2018", "--out-dir"])
+            .arg(dir)
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    /// Compile `args` expecting rustc to fail, returning its error output
+    fn rustc_fails(dir: &Path, args: &[&str]) -> String {
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = Command::new(rustc)
+            .args(["--edition", "2018", "--out-dir"])
+            .arg(dir)
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        assert!(!output.status.success(), "compiled successfully");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    }
+
+    fn run(dir: &Path, binary: &str) {
+        let status = Command::new(dir.join(binary)).status().unwrap();
+        fs::remove_dir_all(dir).unwrap();
+        assert!(status.success());
+    }
+
+    /// Compile and run `main` with macros generated from `toml` included
+    fn run_main(name: &str, toml: &str, main: &str) {
+        let dir = crate_dir(name);
+        let code = Builder::new().gen_strings_macro(toml).unwrap();
+        fs::write(dir.join("strings.rs"), code).unwrap();
+        fs::write(dir.join("main.rs"), format!("include!(\"strings.rs\");\n{}", main)).unwrap();
+        rustc(&dir, &["main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn list_macros() {
+        let toml = r#"
+            message = "Message: {arg}"
+            tips = ["first tip", "second tip"]
+        "#;
+        let main = r#"
+            fn main() {
+                assert_eq!(tr!("message", arg = 1), "Message: 1");
+                assert_eq!(tr_list!("tips"), &["first tip", "second tip"]);
+                assert_eq!(tr_list!("tips", 1), "second tip");
+            }
+        "#;
+        run_main("list-macros", toml, main);
+    }
+
+    #[test]
+    fn keys_constant() {
+        let main = r#"
+            fn main() {
+                let mut keys = CTL10N_KEYS.to_vec();
+                keys.sort_unstable();
+                assert_eq!(keys, ["cancel", "ok"]);
+            }
+        "#;
+        run_main("keys-constant", "ok = \"OK\"\ncancel = \"Cancel\"\ntips = [\"tip\"]", main);
+    }
+
+    #[test]
+    fn keys_in_source_order() {
+        let main = r#"
+            fn main() {
+                assert_eq!(CTL10N_KEYS, ["ok", "cancel", "apply"]);
+            }
+        "#;
+        run_main("source-order", "ok = \"OK\"\ncancel = \"Cancel\"\napply = \"Apply\"", main);
+    }
+
+    #[test]
+    fn identifier_keys() {
+        let toml = r#"
+            message-with-args = "Arg: {arg}"
+            tips = ["first tip", "second tip"]
+        "#;
+        let main = r#"
+            fn main() {
+                assert_eq!(tr!(message_with_args, arg = 1), "Arg: 1");
+                assert_eq!(format!(tr!(message_with_args), arg = 2), "Arg: 2");
+                assert_eq!(tr_list!(tips, 0), "first tip");
+            }
+        "#;
+        run_main("identifier-keys", toml, main);
+    }
+
+    #[test]
+    fn namespaced_keys() {
+        let main = r#"
+            fn main() {
+                assert_eq!(tr_in!("errors", "not-found", path = "/tmp"), "/tmp not found");
+                assert_eq!(tr!("errors.not-found", path = "/"), "/ not found");
+            }
+        "#;
+        run_main("namespaced-keys", r#""errors.not-found" = "{path} not found""#, main);
+    }
+
+    #[test]
+    fn macros_in_other_macros_and_modules() {
+        let main = r#"
+            macro_rules! tr_err {
+                ($key:tt $( $args:tt )*) => { tr_in!("errors", $key $( $args )*) };
+            }
+
+            mod nested {
+                pub fn message() -> &'static str {
+                    tr!("message")
+                }
+            }
+
+            fn main() {
+                assert_eq!(tr_err!("not-found", path = "/tmp"), "/tmp not found");
+                assert_eq!(nested::message(), "Message");
+            }
+        "#;
+        run_main("wrapped-macros", "message = \"Message\"\n\"errors.not-found\" = \"{path} not found\"", main);
+    }
+
+    #[test]
+    fn static_strings() {
+        let main = r#"
+            const ABOUT: &str = tr_static!("about");
+
+            fn main() {
+                assert_eq!(ABOUT, "About");
+            }
+        "#;
+        run_main("static-strings", r#"about = "About""#, main);
+    }
+
+    #[test]
+    fn static_string_errors() {
+        let toml = r#"
+            about = "About"
+            greeting = "Hello, {name}! You have {0} messages"
+        "#;
+        let cases = [
+            (r#"#[doc = tr_static!("greeting")]"#, "Key `greeting` requires arguments {name}, {0}"),
+            (r#"#[doc = tr_static!("missing")]"#, "There is no string for key `\"missing\"`"),
+        ];
+        for (idx, (item, message)) in cases.iter().enumerate() {
+            let dir = crate_dir(&format!("static-errors-{}", idx));
+            let code = Builder::new().gen_strings_macro(toml).unwrap();
+            fs::write(dir.join("strings.rs"), code).unwrap();
+            let main = format!("include!(\"strings.rs\");\n{}\nstruct Item;\nfn main() {{ let _ = Item; }}", item);
+            fs::write(dir.join("main.rs"), main).unwrap();
+            let stderr = rustc_fails(&dir, &["main.rs"]);
+            assert!(stderr.contains(message), "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn cow_strings() {
+        let main = r#"
+            fn main() {
+                let titles: Vec<std::borrow::Cow<'static, str>> =
+                    vec![tr_cow!("message"), tr_cow!("message-with-args", arg = 1)];
+                assert_eq!(titles, ["Message", "Arg: 1"]);
+                assert!(matches!(tr_cow!("message"), std::borrow::Cow::Borrowed(_)));
+            }
+        "#;
+        run_main("cow-strings", "message = \"Message\"\nmessage-with-args = \"Arg: {arg}\"", main);
+    }
+
+    #[test]
+    fn clap_attributes() {
+        let main = r#"
+            #[doc = tr_clap!("cli-about")]
+            struct Args;
+
+            fn main() {
+                let _ = Args;
+                assert_eq!(tr_clap!("cli-about"), "About");
+            }
+        "#;
+        run_main("clap-attributes", r#"cli-about = "About""#, main);
+    }
+
+    #[test]
+    fn exported_macros_work_in_defining_crate() {
+        let dir = crate_dir("macro-export");
+        let code = Builder::new()
+            .macro_export(true)
+            .gen_strings_macro("hello = \"Hello\"\ngreeting = \"Hi, {name}!\"")
+            .unwrap();
+        fs::write(dir.join("strings.rs"), code).unwrap();
+        let lib = r#"
+            include!("strings.rs");
+            pub fn hello() -> &'static str { tr!("hello") }
+            pub fn shout() -> &'static str { concat!(tr!("hello"), "!") }
+            pub mod nested {
+                pub fn greet(name: &str) -> String { tr!("greeting", name = name) }
+            }
+        "#;
+        fs::write(dir.join("lib.rs"), lib).unwrap();
+        rustc(&dir, &["--crate-type", "lib", "--crate-name", "strings", "lib.rs"]);
+        fs::write(
+            dir.join("main.rs"),
+            r#"
+                fn main() {
+                    assert_eq!(strings::tr!("hello"), strings::hello());
+                    assert_eq!(strings::shout(), "Hello!");
+                    assert_eq!(strings::tr!("greeting", name = "Bob"), strings::nested::greet("Bob"));
+                }
+            "#,
+        )
+        .unwrap();
+        rustc(&dir, &["--extern", "strings=libstrings.rlib", "-L", ".", "main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn exported_truncation_uses_reexported_ctl10n() {
+        let dir = crate_dir("exported-truncation");
+        fs::write(dir.join("en.toml"), "hello = \"Hello, world\"").unwrap();
+        Builder::new()
+            .macro_export(true)
+            .truncation(true)
+            .convert_strings_file(dir.join("en.toml"), dir.join("strings.rs"))
+            .unwrap();
+        // Stands in for ctl10n, which isn't linked to crates compiled by tests
+        let lib = r#"
+            pub extern crate self as ctl10n;
+            pub fn truncate<'a>(text: &'a str, max: usize, ellipsis: &str) -> std::borrow::Cow<'a, str> {
+                format!("{}{}", &text[..max], ellipsis).into()
+            }
+            include!("strings.rs");
+        "#;
+        fs::write(dir.join("lib.rs"), lib).unwrap();
+        rustc(&dir, &["--crate-type", "lib", "--crate-name", "strings", "lib.rs"]);
+        fs::write(dir.join("main.rs"), r#"fn main() { assert_eq!(strings::tr_trunc!("hello", 5), "Hello…"); }"#).unwrap();
+        rustc(&dir, &["--extern", "strings=libstrings.rlib", "-L", ".", "main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn binary_backend_evaluates_key_once() {
+        let dir = crate_dir("binary-key");
+        fs::write(dir.join("en.toml"), "hello = \"Hello\"").unwrap();
+        Builder::new()
+            .backend(Backend::Binary)
+            .convert_strings_file(dir.join("en.toml"), dir.join("strings.rs"))
+            .unwrap();
+        // Stands in for ctl10n, which isn't linked to crates compiled by tests
+        let main = r#"
+            extern crate self as ctl10n;
+            pub struct ActiveCatalog;
+            impl ActiveCatalog {
+                pub const fn with_locales(_: &'static [u8], _: &'static [(&'static str, &'static [u8])]) -> Self {
+                    ActiveCatalog
+                }
+                pub fn get(&self, key: &str) -> Option<&'static str> {
+                    if key == "hello" { Some("Hello") } else { None }
+                }
+            }
+            include!("strings.rs");
+
+            fn main() {
+                let mut calls = 0;
+                let mut key = |key| {
+                    calls += 1;
+                    key
+                };
+                assert_eq!(tr!(key("hello")), "Hello");
+                assert_eq!(tr!(key("missing")), "missing");
+                assert_eq!(calls, 2);
+            }
+        "#;
+        fs::write(dir.join("main.rs"), main).unwrap();
+        rustc(&dir, &["main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn binary_backend_const_namespace() {
+        let dir = crate_dir("binary-namespace");
+        fs::write(dir.join("en.toml"), "\"errors.not-found\" = \"Not found\"").unwrap();
+        Builder::new()
+            .backend(Backend::Binary)
+            .convert_strings_file(dir.join("en.toml"), dir.join("strings.rs"))
+            .unwrap();
+        // Stands in for ctl10n, which isn't linked to crates compiled by tests
+        let main = r#"
+            extern crate self as ctl10n;
+            pub struct ActiveCatalog;
+            impl ActiveCatalog {
+                pub const fn with_locales(_: &'static [u8], _: &'static [(&'static str, &'static [u8])]) -> Self {
+                    ActiveCatalog
+                }
+                pub fn get(&self, key: &str) -> Option<&'static str> {
+                    if key == "errors.not-found" { Some("Not found") } else { None }
+                }
+                pub fn get_in(&self, namespace: &str, key: &str) -> Option<&'static str> {
+                    self.get(&format!("{}.{}", namespace, key))
+                }
+            }
+            include!("strings.rs");
+
+            const ERRORS: &str = "errors";
+
+            fn main() {
+                assert_eq!(tr_in!("errors", "not-found"), "Not found");
+                assert_eq!(tr_in!(ERRORS, "not-found"), "Not found");
+                assert_eq!(tr_in!(ERRORS, "missing"), "missing");
+            }
+        "#;
+        fs::write(dir.join("main.rs"), main).unwrap();
+        rustc(&dir, &["main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn format_tests_pass_counts() {
+        let dir = crate_dir("format-counts");
+        let toml = r#"
+            width = "[{:w$}]"
+            position = "[{0:1$}]"
+            precision = "[{:.*}] [{name:.prec$}]"
+        "#;
+        fs::write(dir.join("en.toml"), toml).unwrap();
+        Builder::new()
+            .format_tests(true)
+            .convert_strings_file(dir.join("en.toml"), dir.join("strings.rs"))
+            .unwrap();
+        fs::write(dir.join("main.rs"), "include!(\"strings.rs\");").unwrap();
+        rustc(&dir, &["--test", "main.rs"]);
+        run(&dir, "main");
+    }
+
+    #[test]
+    fn plural_macros() {
+        let toml = r#"
+            [plural.cardinal]
+            one = "n % 10 = 1 and n % 100 != 11"
+            few = "n % 10 = 2..4 and n % 100 != 12..14"
+
+            [cardinal]
+            apples = { one = "{n} яблоко", few = "{n} яблока", other = "{n} яблок" }
+
+            [ordinal]
+            place = { one = "{n}st", other = "{n}th" }
+        "#;
+        // Stands in for ctl10n, which isn't linked to crates compiled by tests
+        let main = r#"
+            pub extern crate self as ctl10n;
+            #[derive(PartialEq)]
+            pub enum PluralCategory { One, Few, Other }
+            pub fn ordinal_category(_: &str, n: u64) -> PluralCategory {
+                if n == 1 { PluralCategory::One } else { PluralCategory::Other }
+            }
+
+            fn main() {
+                assert_eq!(trn!("apples", 21), "21 яблоко");
+                assert_eq!(trn!("apples", 3u8), "3 яблока");
+                assert_eq!(trn!("apples", 11), "11 яблок");
+                assert_eq!(trn_ord!("place", 1), "1st");
+                assert_eq!(trn_ord!("place", 2), "2th");
+            }
+        "#;
+        run_main("plural-macros", toml, main);
+    }
+
+    #[test]
+    fn default_catalog_embedded_once() {
+        let (en, de) = (Path::new("/out/strings_en.bin"), Path::new("/out/strings_de.bin"));
+        let tokens = gen_binary_loader(en, &[("de", de), ("en", en)], &Options::default()).to_string();
+        assert_eq!(tokens.matches("include_bytes").count(), 2);
+        assert!(tokens.contains("with_default_locale"));
+        let tokens = gen_binary_loader(en, &[], &Options::default()).to_string();
+        assert!(tokens.contains("with_locales"));
+    }
+
+    #[test]
+    fn binary_backend_format_tests() {
+        let dir = crate_dir("binary-format-tests");
+        fs::write(dir.join("en.toml"), "hello = \"Hello\"\ngreeting = \"Hello, {name}\"").unwrap();
+        Builder::new()
+            .backend(Backend::Binary)
+            .format_tests(true)
+            .convert_strings_file(dir.join("en.toml"), dir.join("strings.rs"))
+            .unwrap();
+        // Stands in for ctl10n, which isn't linked to crates compiled by tests
+        let main = r#"
+            extern crate self as ctl10n;
+            pub struct ActiveCatalog;
+            impl ActiveCatalog {
+                pub const fn with_locales(_: &'static [u8], _: &'static [(&'static str, &'static [u8])]) -> Self {
+                    ActiveCatalog
+                }
+                pub fn get(&self, _: &str) -> Option<&'static str> {
+                    None
+                }
+            }
+            include!("strings.rs");
+        "#;
+        fs::write(dir.join("main.rs"), main).unwrap();
+        rustc(&dir, &["--test", "main.rs"]);
+        run(&dir, "main");
+    }
+}
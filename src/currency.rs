@@ -0,0 +1,118 @@
+use std::fmt::{self, Display};
+
+use crate::number::NumberFormat;
+
+/// Where [`CurrencyFormat`] puts the currency symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymbolPosition {
+    /// `$5.00`
+    #[default]
+    Before,
+    /// `5,00 €`
+    After,
+}
+
+impl SymbolPosition {
+    /// Position with given lowercase name, e.g. `after`
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "before" => Some(Self::Before),
+            "after" => Some(Self::After),
+            _ => None,
+        }
+    }
+}
+
+/// Number which can be formatted as money with [`CurrencyFormat::format`], so strings
+/// like keys aren't formatted by mistake. It's implemented for primitive numbers and can
+/// be implemented for decimal types, whose `Display` must support precision like `f64` does.
+///
+/// ```compile_fail
+/// use ctl10n::{CurrencyFormat, NumberFormat, SymbolPosition};
+///
+/// let format = CurrencyFormat::new("$", SymbolPosition::Before, false, 2, NumberFormat::for_locale("en"));
+/// format.format("total");
+/// ```
+pub trait Amount: Display {}
+
+macro_rules! impl_amount {
+    ($( $ty:ty ),*) => {
+        $( impl Amount for $ty {} )*
+    };
+}
+
+impl_amount!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl<T: Amount + ?Sized> Amount for &T {}
+
+/// How a locale writes amounts of money, used by `tr_money!()`. Amounts are rounded to
+/// `digits` fractional digits and formatted with separators of `number`.
+///
+/// ```
+/// use ctl10n::{CurrencyFormat, NumberFormat, SymbolPosition};
+///
+/// let format = CurrencyFormat::new("€", SymbolPosition::After, true, 2, NumberFormat::for_locale("de"));
+/// assert_eq!(format.format(1234.5).to_string(), "1.234,50\u{a0}€");
+/// let format = CurrencyFormat::new("$", SymbolPosition::Before, false, 2, NumberFormat::for_locale("en"));
+/// assert_eq!(format.format(-5).to_string(), "-$5.00");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyFormat {
+    /// Currency symbol or code, e.g. `€` or `CHF`
+    pub symbol: &'static str,
+    /// Where the symbol is put
+    pub position: SymbolPosition,
+    /// Whether the symbol is separated from the amount by a no-break space
+    pub space: bool,
+    /// Number of fractional digits
+    pub digits: usize,
+    /// Separators of the amount
+    pub number: NumberFormat,
+}
+
+impl CurrencyFormat {
+    /// Currency format with given symbol and separators
+    pub const fn new(
+        symbol: &'static str,
+        position: SymbolPosition,
+        space: bool,
+        digits: usize,
+        number: NumberFormat,
+    ) -> Self {
+        Self { symbol, position, space, digits, number }
+    }
+
+    /// Wrap `amount` so it's displayed with currency symbol and separators of this format
+    pub fn format<T: Amount>(&self, amount: T) -> FormattedMoney<'_, T> {
+        FormattedMoney { format: self, amount }
+    }
+}
+
+/// Amount displayed according to [`CurrencyFormat`], returned by [`CurrencyFormat::format`]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedMoney<'a, T> {
+    format: &'a CurrencyFormat,
+    amount: T,
+}
+
+impl<T: Amount> Display for FormattedMoney<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = self.format;
+        let mut plain = format!("{:.*}", format.digits, self.amount);
+        // Precision doesn't apply to integers
+        if format.digits > 0 && !plain.contains('.') {
+            plain.push('.');
+            plain.extend(std::iter::repeat_n('0', format.digits));
+        }
+        let localized = format.number.localize(&plain);
+        let (sign, amount) = match localized.strip_prefix('-') {
+            Some(amount) => ("-", amount),
+            None => ("", localized.as_str()),
+        };
+        let space = if format.space { "\u{a0}" } else { "" };
+        match format.position {
+            SymbolPosition::Before => write!(f, "{}{}{}{}", sign, format.symbol, space, amount),
+            SymbolPosition::After => write!(f, "{}{}{}{}", sign, amount, space, format.symbol),
+        }
+    }
+}
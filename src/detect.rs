@@ -0,0 +1,16 @@
+use std::env;
+
+/// Locales preferred by the user according to the OS, most preferred first.
+///
+/// Uses `sys-locale` and falls back to `$LC_ALL`, `$LC_MESSAGES` and `$LANG`.
+pub fn system_locales() -> Vec<String> {
+    let mut locales: Vec<String> = sys_locale::get_locales().collect();
+    for var in &["LC_ALL", "LC_MESSAGES", "LANG"] {
+        if let Ok(value) = env::var(var) {
+            if !value.is_empty() && !locales.contains(&value) {
+                locales.push(value);
+            }
+        }
+    }
+    locales
+}
@@ -1,8 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use crate::toml_parser::Document;
+
 #[derive(Debug)]
 pub enum Error {
     IOError(std::io::Error),
     TOMLParseError(toml::de::Error),
-    TOMLStructureError,
+    JSONParseError(serde_json::Error),
+    /// Value in JSON strings file can't be converted to a string or a list
+    InvalidJson {
+        key: String,
+        detail: String,
+    },
+    /// Value isn't a string or an array of strings
+    NonStringValue {
+        key: String,
+        /// TOML type of the value (or array item), e.g. `integer`
+        found_type: &'static str,
+        /// 1-based line and column of the key
+        position: (usize, usize),
+    },
+    /// Key is defined more than once (e.g. as `key` and `"key"`)
+    DuplicateKey {
+        key: String,
+        /// 1-based line and column of the first definition
+        first: (usize, usize),
+        /// 1-based line and column of the second definition
+        second: (usize, usize),
+    },
+    /// Key is an empty string
+    EmptyKey {
+        /// 1-based line and column of the key
+        position: (usize, usize),
+    },
+    /// Value isn't a valid `format!()` string
+    InvalidPlaceholder {
+        key: String,
+        detail: String,
+    },
+    /// Value contains a character forbidden by [`Escapes::Forbid`](crate::Escapes::Forbid)
+    InvalidEscape {
+        key: String,
+        detail: String,
+    },
+    /// Placeholders of value aren't the arguments declared with `args`
+    ArgsMismatch {
+        key: String,
+        detail: String,
+    },
+    /// Reserved table like `[limits]` is malformed
+    InvalidMetadata {
+        key: String,
+        detail: String,
+    },
+    /// Value is longer than its limit from `[limits]`
+    TooLong {
+        key: String,
+        /// Length of the value in characters
+        length: usize,
+        limit: usize,
+    },
+    /// Value failed a check enabled with [`Builder::lint`](crate::Builder::lint)
+    Lint {
+        key: String,
+        lint: crate::Lint,
+        detail: String,
+    },
+    /// Static text of a message marked with `html = true` contains scripts
+    UnsafeHtml {
+        key: String,
+        detail: String,
+    },
+    /// Strings file isn't valid UTF-8 or UTF-16
+    InvalidEncoding(String),
+    /// Key is defined both in strings file and in a file it includes
+    /// (or in two included files)
+    ConflictingInclude {
+        key: String,
+        /// Included file defining the key
+        included: PathBuf,
+    },
+    /// File includes itself, directly or via other includes
+    IncludeCycle(PathBuf),
+    /// Overrides file defines key which isn't in the overridden file
+    UnknownOverride(String),
+    /// Keys would be overridden by the same variable of [`Builder::env_overrides`](crate::Builder::env_overrides),
+    /// e.g. `product-name` and `product_name`
+    OverrideCollision {
+        key: String,
+        /// Key defined earlier with the same variable
+        other: String,
+        var: String,
+    },
+    /// Locale doesn't have the same keys as the template it's checked against. It's reported
+    /// at the line of the first unexpected key, if any.
+    KeysMismatch {
+        /// Keys of the template missing from the locale
+        missing: Vec<String>,
+        /// Keys of the locale which aren't in the template
+        unexpected: Vec<String>,
+    },
+    /// Key listed in [lockfile](crate::Builder::lockfile) was removed or changed its arguments
+    LockedKey {
+        key: String,
+        detail: String,
+    },
+    /// Option requiring a [`Template`](crate::Template) is used without
+    /// [`Builder::template`](crate::Builder::template) or [`Builder::source_locale`](crate::Builder::source_locale)
+    NoSourceLocale,
+    /// Generation produced warnings with [`Builder::fail_on_warnings`](crate::Builder::fail_on_warnings)
+    Warnings(Vec<String>),
+    CatalogFormatError,
+    /// Locale with given name wasn't found
+    UnknownLocale(String),
+    /// Pattern set with [`Builder::locale_file_name`](crate::Builder::locale_file_name)
+    /// doesn't contain `{locale}`, so every locale would be written to the same file
+    InvalidFileNamePattern(String),
+    /// Locale is defined by more than one strings file, e.g. `de.toml` and `de.json`
+    DuplicateLocale(String),
+    /// Translated gettext file can't be imported, e.g. it's malformed or has an entry
+    /// which isn't in the template
+    InvalidPo(String),
+    /// Locale file name isn't a BCP-47 tag
+    InvalidLocaleName {
+        name: String,
+        /// Valid tag the name was probably meant to be, e.g. `en-US` for `en_US`
+        suggestion: Option<String>,
+    },
+    /// Error while processing given file. Its message includes the wrapped error,
+    /// so [`source`](std::error::Error::source) is the source of the wrapped error.
+    InFile {
+        path: PathBuf,
+        /// 1-based line the error refers to, if it's about a key
+        line: Option<usize>,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    pub(crate) fn in_file(self, path: impl Into<PathBuf>) -> Self {
+        match self {
+            Self::InFile { .. } => self,
+            error => Self::InFile {
+                path: path.into(),
+                line: None,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Error at 1-based `line` of file at `path`
+    pub(crate) fn at_line(self, path: impl Into<PathBuf>, line: usize) -> Self {
+        match self {
+            Self::InFile { .. } => self,
+            error => Self::InFile {
+                path: path.into(),
+                line: Some(line),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Error in file defining its key, at the line of the key, for an error about a key
+    /// of `document` read from `path`
+    pub(crate) fn at_key(self, document: &Document, path: &Path) -> Self {
+        let (file, line) = match self.key() {
+            Some(key) => document.location(key, path),
+            None => (path, None),
+        };
+        match self {
+            Self::InFile { .. } => self,
+            error => Self::InFile {
+                path: file.to_path_buf(),
+                line,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Key the error is about, if any
+    fn key(&self) -> Option<&str> {
+        match self {
+            Self::InvalidJson { key, .. }
+            | Self::NonStringValue { key, .. }
+            | Self::DuplicateKey { key, .. }
+            | Self::InvalidPlaceholder { key, .. }
+            | Self::InvalidEscape { key, .. }
+            | Self::ArgsMismatch { key, .. }
+            | Self::InvalidMetadata { key, .. }
+            | Self::TooLong { key, .. }
+            | Self::Lint { key, .. }
+            | Self::UnsafeHtml { key, .. }
+            | Self::ConflictingInclude { key, .. }
+            | Self::OverrideCollision { key, .. } => Some(key),
+            // Missing keys aren't in the file, so the error is reported at an unexpected one
+            Self::KeysMismatch { unexpected, .. } => unexpected.first().map(String::as_str),
+            _ => None,
+        }
+    }
 }
 
 
@@ -12,6 +207,12 @@ impl From<toml::de::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(other: serde_json::Error) -> Self {
+        Self::JSONParseError(other)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(other: std::io::Error) -> Self {
         Self::IOError(other)
@@ -27,9 +228,159 @@ impl std::fmt::Display for Error {
             Self::TOMLParseError(err) => {
                 write!(f, "Error parsing TOML: {}", err)
             },
-            Self::TOMLStructureError => {
-                write!(f, "Strings TOML must be flat string/string table")
+            Self::JSONParseError(err) => {
+                write!(f, "Error parsing JSON: {}", err)
+            },
+            Self::InvalidJson { key, detail } => {
+                write!(f, "Invalid value of key `{}`: {}", key, detail)
+            },
+            Self::NonStringValue { key, found_type, position: (line, column) } => {
+                write!(
+                    f,
+                    "Values in strings TOML must be strings or arrays of strings, but key `{}` at line {}, column {} is {}",
+                    key, line, column, found_type,
+                )
+            },
+            Self::DuplicateKey { key, first, second } => {
+                write!(
+                    f,
+                    "Key `{}` is defined twice: at line {}, column {} and at line {}, column {}",
+                    key, first.0, first.1, second.0, second.1,
+                )
+            },
+            Self::EmptyKey { position: (line, column) } => {
+                write!(f, "Empty key at line {}, column {}", line, column)
             },
+            Self::InvalidPlaceholder { key, detail } => {
+                write!(f, "Invalid format string for key `{}`: {}", key, detail)
+            },
+            Self::InvalidEscape { key, detail } => {
+                write!(f, "Forbidden escape in key `{}`: {}", key, detail)
+            },
+            Self::ArgsMismatch { key, detail } => {
+                write!(f, "Placeholders of key `{}` don't match its arguments: {}", key, detail)
+            },
+            Self::InvalidMetadata { key, detail } => {
+                write!(f, "Invalid metadata for key `{}`: {}", key, detail)
+            },
+            Self::TooLong { key, length, limit } => {
+                write!(
+                    f,
+                    "Value of key `{}` is {} characters long, but its limit is {}",
+                    key, length, limit,
+                )
+            },
+            Self::Lint { key, lint, detail } => {
+                write!(f, "Key `{}` failed lint {:?}: {}", key, lint, detail)
+            },
+            Self::UnsafeHtml { key, detail } => {
+                write!(f, "Unsafe HTML in key `{}`: {}", key, detail)
+            },
+            Self::InvalidEncoding(detail) => {
+                write!(f, "Invalid encoding: {}", detail)
+            },
+            Self::ConflictingInclude { key, included } => {
+                write!(f, "Key `{}` is also defined in included file {}", key, included.display())
+            },
+            Self::IncludeCycle(path) => {
+                write!(f, "{} includes itself", path.display())
+            },
+            Self::UnknownOverride(key) => {
+                write!(f, "Key `{}` is overridden, but isn't defined in the overridden file", key)
+            },
+            Self::OverrideCollision { key, other, var } => {
+                write!(f, "Keys `{}` and `{}` would both be overridden by {}, rename one of them", other, key, var)
+            },
+            Self::KeysMismatch { missing, unexpected } => {
+                let list = |keys: &[String]| keys.iter().map(|key| format!("`{}`", key)).collect::<Vec<_>>().join(", ");
+                write!(f, "Keys don't match the template")?;
+                if !missing.is_empty() {
+                    write!(f, ", missing {}", list(missing))?;
+                }
+                if !unexpected.is_empty() {
+                    write!(f, ", unexpected {}", list(unexpected))?;
+                }
+                Ok(())
+            },
+            Self::LockedKey { key, detail } => {
+                write!(f, "Locked key `{}` {}, update the lockfile if it's intended", key, detail)
+            },
+            Self::NoSourceLocale => {
+                write!(f, "Neither template nor source locale is set")
+            },
+            Self::Warnings(warnings) => {
+                write!(f, "Generation produced {} warnings:", warnings.len())?;
+                for warning in warnings {
+                    write!(f, "\n  {}", warning)?;
+                }
+                Ok(())
+            },
+            Self::CatalogFormatError => {
+                write!(f, "Invalid binary catalog")
+            },
+            Self::UnknownLocale(locale) => {
+                write!(f, "Locale `{}` not found", locale)
+            },
+            Self::InvalidFileNamePattern(pattern) => {
+                write!(f, "Locale file name pattern `{}` doesn't contain `{{locale}}`", pattern)
+            },
+            Self::DuplicateLocale(locale) => {
+                write!(f, "Locale `{}` is defined by more than one strings file", locale)
+            },
+            Self::InvalidPo(detail) => {
+                write!(f, "Invalid PO file: {}", detail)
+            },
+            Self::InvalidLocaleName { name, suggestion } => {
+                write!(f, "Locale name `{}` is not a valid BCP-47 tag", name)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", did you mean `{}`?", suggestion)?;
+                }
+                Ok(())
+            },
+            Self::InFile { path, line: Some(line), error } => {
+                write!(f, "{}:{}: {}", path.display(), line, error)
+            },
+            Self::InFile { path, line: None, error } => {
+                write!(f, "{}: {}", path.display(), error)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IOError(err) => Some(err),
+            Self::TOMLParseError(err) => Some(err),
+            Self::JSONParseError(err) => Some(err),
+            // Display of `InFile` already includes the wrapped error
+            Self::InFile { error, .. } => error.source(),
+            Self::InvalidJson { .. }
+            | Self::NonStringValue { .. }
+            | Self::DuplicateKey { .. }
+            | Self::EmptyKey { .. }
+            | Self::InvalidPlaceholder { .. }
+            | Self::InvalidEscape { .. }
+            | Self::ArgsMismatch { .. }
+            | Self::InvalidMetadata { .. }
+            | Self::TooLong { .. }
+            | Self::Lint { .. }
+            | Self::UnsafeHtml { .. }
+            | Self::InvalidEncoding(_)
+            | Self::ConflictingInclude { .. }
+            | Self::IncludeCycle(_)
+            | Self::UnknownOverride(_)
+            | Self::OverrideCollision { .. }
+            | Self::KeysMismatch { .. }
+            | Self::LockedKey { .. }
+            | Self::NoSourceLocale
+            | Self::Warnings(_)
+            | Self::CatalogFormatError
+            | Self::UnknownLocale(_)
+            | Self::InvalidFileNamePattern(_)
+            | Self::DuplicateLocale(_)
+            | Self::InvalidPo(_)
+            | Self::InvalidLocaleName { .. } => None,
         }
     }
 }
@@ -0,0 +1,77 @@
+/// What to do with backslash sequences like `\n` left in values, e.g. by literal
+/// `'...'` TOML strings or exports escaping strings twice, and with control characters
+/// output by them. Terminals handle newlines and tabs, while GUI labels often need
+/// single-line text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Escapes {
+    /// Values are used as written
+    #[default]
+    Keep,
+    /// Sequences `\n`, `\r`, `\t`, `\"` and `\\` are converted to the characters they stand
+    /// for, other backslashes are kept
+    Unescape,
+    /// Values containing newlines, tabs, carriage returns or any of the sequences
+    /// converted by [`Escapes::Unescape`] fail generation
+    Forbid,
+}
+
+/// Characters sequences `\<c>` stand for
+const SEQUENCES: &[(char, char)] = &[('n', '\n'), ('r', '\r'), ('t', '\t'), ('"', '"'), ('\\', '\\')];
+
+impl Escapes {
+    /// Policy with given lowercase name, e.g. `unescape`
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Self::Keep),
+            "unescape" => Some(Self::Unescape),
+            "forbid" => Some(Self::Forbid),
+            _ => None,
+        }
+    }
+
+    /// `value` with escapes handled according to this policy, or description of
+    /// the forbidden character
+    pub(crate) fn apply(self, value: &str) -> Result<String, String> {
+        match self {
+            Self::Keep => Ok(value.to_string()),
+            Self::Unescape => {
+                let mut unescaped = String::with_capacity(value.len());
+                let mut chars = value.chars().peekable();
+                while let Some(c) = chars.next() {
+                    let sequence = SEQUENCES.iter().find(|(name, _)| Some(name) == chars.peek());
+                    match (c, sequence) {
+                        ('\\', Some(&(_, character))) => {
+                            chars.next();
+                            unescaped.push(character);
+                        }
+                        _ => unescaped.push(c),
+                    }
+                }
+                Ok(unescaped)
+            }
+            Self::Forbid => {
+                if let Some((pos, c)) = value.char_indices().find(|&(_, c)| matches!(c, '\n' | '\r' | '\t')) {
+                    return Err(format!("{:?} at byte {}", c, pos));
+                }
+                match sequence_position(value) {
+                    Some(pos) => Err(format!("backslash sequence `{}` at byte {}", &value[pos..pos + 2], pos)),
+                    None => Ok(value.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Byte position of the first backslash sequence [`Escapes::Unescape`] would convert
+pub(crate) fn sequence_position(value: &str) -> Option<usize> {
+    let mut chars = value.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        if c == '\\' {
+            match chars.peek() {
+                Some(&(_, next)) if SEQUENCES.iter().any(|&(name, _)| name == next) => return Some(pos),
+                _ => {}
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use crate::toml_parser::Document;
+
+/// Stable fingerprint of a message: 64-bit FNV-1a hash of its UTF-8 bytes as 16 hex digits.
+///
+/// It's written to the manifest with [`Builder::fingerprints`](crate::Builder::fingerprints),
+/// so translation tools can notice that a source string changed while its key didn't.
+/// The algorithm won't change between versions of ctl10n.
+///
+/// ```
+/// assert_eq!(ctl10n::fingerprint(""), "cbf29ce484222325");
+/// assert_ne!(ctl10n::fingerprint("Save"), ctl10n::fingerprint("Save all"));
+/// ```
+pub fn fingerprint(message: &str) -> String {
+    format!("{:016x}", fnv1a(message.as_bytes()))
+}
+
+/// Fingerprint of raw contents of a file
+pub(crate) fn bytes_fingerprint(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
+
+/// Fingerprint of array value, hashing items separated by zero bytes
+pub(crate) fn list_fingerprint(items: &[String]) -> String {
+    fingerprint(&items.join("\0"))
+}
+
+/// Fingerprint of every key, value and metadata table of `documents` of given locales,
+/// which changes whenever any of them does. Order of definition, positions of keys and
+/// paths of files don't matter.
+pub(crate) fn catalog_fingerprint(documents: &[(&str, &Document)]) -> String {
+    let mut bytes = Vec::new();
+    for (locale, document) in documents {
+        let contents = Document {
+            order: Vec::new(),
+            positions: BTreeMap::new(),
+            files: BTreeMap::new(),
+            includes: Vec::new(),
+            ..(*document).clone()
+        };
+        bytes.extend_from_slice(format!("{}\0{:?}\0", locale, contents).as_bytes());
+    }
+    format!("{:016x}", fnv1a(&bytes))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::{parse_toml, ParseOptions};
+
+    fn hash(toml: &str) -> String {
+        catalog_fingerprint(&[("en", &parse_toml(toml, &ParseOptions::default()).unwrap())])
+    }
+
+    #[test]
+    fn catalog_fingerprint_ignores_order() {
+        assert_eq!(hash("a = \"A\"\nb = \"B\""), hash("b = \"B\"\n\n# Comment\na = \"A\""));
+    }
+
+    #[test]
+    fn catalog_fingerprint_covers_metadata() {
+        let base = hash("a = \"A {n}\"");
+        assert_ne!(base, hash("a = \"A {n}\"\n[formats]\nshort-date = \"%d.%m\""));
+        assert_ne!(base, hash("a = { value = \"A {n}\", defaults = { n = \"1\" } }"));
+        assert_ne!(base, hash("a = { value = \"A {n}\", cfg = \"unix\" }"));
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::{self, Display, Write};
+
+/// Displays wrapped value with `&`, `<`, `>`, `"` and `'` replaced by HTML entities.
+///
+/// Arguments of `tr_html!()` are wrapped in it.
+///
+/// ```
+/// let name = "<b>Bobby</b> & \"friends\"";
+/// assert_eq!(
+///     ctl10n::HtmlEscaped(name).to_string(),
+///     "&lt;b&gt;Bobby&lt;/b&gt; &amp; &quot;friends&quot;",
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlEscaped<T>(pub T);
+
+/// Writer escaping everything written to the inner formatter
+struct Escaper<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl Write for Escaper<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(idx) = rest.find(['&', '<', '>', '"', '\'']) {
+            self.0.write_str(&rest[..idx])?;
+            self.0.write_str(match rest.as_bytes()[idx] {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                _ => "&#39;",
+            })?;
+            rest = &rest[idx + 1..];
+        }
+        self.0.write_str(rest)
+    }
+}
+
+impl<T: Display> Display for HtmlEscaped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(Escaper(f), "{}", self.0)
+    }
+}
+
+/// Check that static text of HTML message doesn't contain scripts: `<script>`
+/// and other executable elements, event handler attributes and `javascript:` URLs.
+pub(crate) fn validate(value: &str) -> Result<(), String> {
+    let lower = value.to_lowercase();
+    for element in ["<script", "<iframe", "<object", "<embed", "<style"] {
+        if lower.contains(element) {
+            return Err(format!("`{}>` element is not allowed", element));
+        }
+    }
+    if lower.contains("javascript:") {
+        return Err("`javascript:` URLs are not allowed".to_string());
+    }
+    // Event handlers like `onclick=` inside tags
+    let mut in_tag = false;
+    for (idx, c) in lower.char_indices() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if in_tag && c.is_whitespace() => {
+                let attribute = lower[idx..].trim_start();
+                let name: String = attribute.chars().take_while(char::is_ascii_alphabetic).collect();
+                let after = attribute[name.len()..].trim_start();
+                if name.starts_with("on") && after.starts_with('=') {
+                    return Err(format!("event handler attribute `{}` is not allowed", name));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::placeholder::parse_placeholders;
+use crate::plural::{PluralCategory, PluralKind};
+use crate::toml_parser::{check_plural, Document, ParseOptions};
+
+/// Keys of a message object in structured JSON exports, which is treated as a single string
+const MESSAGE_FIELDS: &[&str] = &["message", "description", "maxLength", "context"];
+
+/// Parse JSON export of a translation management system like Crowdin or Lokalise.
+///
+/// Nested objects are flattened with `.`, except objects of plural forms like
+/// `{ "items": { "one": "...", "other": "..." } }`, which become `[cardinal]` messages
+/// available via `trn!()`. So do keys with suffixes like `items_one` and `items_other`.
+/// Objects with `message` string (structured JSON) are messages, their `maxLength`
+/// becomes a limit.
+pub(crate) fn parse_json(input: &str, options: &ParseOptions) -> Result<Document> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut document = Document::default();
+    match serde_json::from_str(input)? {
+        Value::Object(object) => {
+            for (key, value) in object {
+                insert(&mut document, key, value, options)?;
+            }
+        }
+        _ => {
+            return Err(Error::InvalidJson {
+                key: String::new(),
+                detail: "strings file must be an object".to_string(),
+            })
+        }
+    }
+    group_plural_suffixes(&mut document)?;
+    crate::toml_parser::apply_whitespace(&mut document, options.whitespace)?;
+    crate::toml_parser::apply_escapes(&mut document, options.escapes)?;
+    Ok(document)
+}
+
+/// Convert scalar value to string, if it's allowed by `options`
+fn to_string(value: Value, options: &ParseOptions) -> std::result::Result<String, Value> {
+    match value {
+        Value::String(string) => Ok(string),
+        Value::Number(number) if options.lenient_values => Ok(number.to_string()),
+        Value::Bool(boolean) if options.lenient_values => Ok(boolean.to_string()),
+        other => Err(other),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn insert(document: &mut Document, key: String, value: Value, options: &ParseOptions) -> Result<()> {
+    let invalid = |key: &String, detail: String| Error::InvalidJson { key: key.clone(), detail };
+    let value = match value {
+        Value::Object(object) if object.get("message").is_some_and(Value::is_string)
+            && object.keys().all(|field| MESSAGE_FIELDS.contains(&field.as_str())) =>
+        {
+            match object.get("maxLength").and_then(Value::as_u64) {
+                Some(limit) => {
+                    document.limits.insert(key.clone(), limit as usize);
+                }
+                None if object.contains_key("maxLength") => {
+                    return Err(invalid(&key, "maxLength must be a non-negative integer".to_string()));
+                }
+                None => {}
+            }
+            object["message"].clone()
+        }
+        Value::Object(object) if is_plural(&object) => {
+            let messages = object
+                .into_iter()
+                .map(|(name, value)| {
+                    let category = PluralCategory::from_name(&name).expect("checked by is_plural()");
+                    (category, value.as_str().expect("checked by is_plural()").to_string())
+                })
+                .collect();
+            check_plural(&key, PluralKind::Cardinal, &messages)?;
+            if document.cardinals.insert(key.clone(), messages).is_some() {
+                return Err(invalid(&key, "key is defined twice, possibly as nested and dotted key".to_string()));
+            }
+            return Ok(());
+        }
+        Value::Object(object) => {
+            for (name, value) in object {
+                insert(document, format!("{}.{}", key, name), value, options)?;
+            }
+            return Ok(());
+        }
+        value => value,
+    };
+    if key.is_empty() {
+        return Err(invalid(&key, "key is empty".to_string()));
+    }
+    if document.strings.contains_key(&key) || document.lists.contains_key(&key) {
+        return Err(invalid(&key, "key is defined twice, possibly as nested and dotted key".to_string()));
+    }
+    match value {
+        Value::Array(array) => {
+            let list = array
+                .into_iter()
+                .map(|item| to_string(item, options))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|item| invalid(&key, format!("array items must be strings, found {}", type_name(&item))))?;
+            match &options.join_arrays {
+                Some(separator) => insert_string(document, key, list.join(separator)),
+                None => {
+                    document.order.push(key.clone());
+                    document.lists.insert(key, list);
+                    Ok(())
+                }
+            }
+        }
+        value => {
+            let string = to_string(value, options)
+                .map_err(|value| invalid(&key, format!("value must be a string, found {}", type_name(&value))))?;
+            insert_string(document, key, string)
+        }
+    }
+}
+
+/// Whether `object` has string plural forms by CLDR category, including `other`
+fn is_plural(object: &serde_json::Map<String, Value>) -> bool {
+    object.contains_key("other")
+        && object
+            .iter()
+            .all(|(name, value)| PluralCategory::from_name(name).is_some() && value.is_string())
+}
+
+/// Move strings with keys like `items_one` and `items_other` to `[cardinal]` messages
+/// of `items`, if there are at least two forms including `other`
+fn group_plural_suffixes(document: &mut Document) -> Result<()> {
+    let mut groups: BTreeMap<String, Vec<(String, PluralCategory)>> = BTreeMap::new();
+    for key in document.strings.keys() {
+        if let Some((base, category)) = key.rsplit_once('_') {
+            if let Some(category) = PluralCategory::from_name(category) {
+                groups.entry(base.to_string()).or_default().push((key.clone(), category));
+            }
+        }
+    }
+    for (base, forms) in groups {
+        if forms.len() < 2 || !forms.iter().any(|(_, category)| *category == PluralCategory::Other) {
+            continue;
+        }
+        if document.strings.contains_key(&base) || document.cardinals.contains_key(&base) {
+            return Err(Error::InvalidJson {
+                detail: "key is defined both as a string and as plural forms".to_string(),
+                key: base,
+            });
+        }
+        let mut messages = BTreeMap::new();
+        for (key, category) in forms {
+            messages.insert(category, document.strings.remove(&key).expect("key is a string"));
+            document.order.retain(|ordered| *ordered != key);
+        }
+        check_plural(&base, PluralKind::Cardinal, &messages)?;
+        document.cardinals.insert(base, messages);
+    }
+    Ok(())
+}
+
+fn insert_string(document: &mut Document, key: String, string: String) -> Result<()> {
+    if let Err(detail) = parse_placeholders(&string) {
+        return Err(Error::InvalidPlaceholder { key, detail });
+    }
+    document.order.push(key.clone());
+    document.strings.insert(key, string);
+    Ok(())
+}
+
+/// Flat JSON object with every string and array value of `document`, for frontends
+/// using the same strings
+pub(crate) fn bundle(document: &Document) -> String {
+    let strings = document.strings.iter().map(|(key, value)| (key.clone(), Value::from(value.as_str())));
+    let lists = document.lists.iter().map(|(key, items)| (key.clone(), Value::from(items.clone())));
+    let object: serde_json::Map<String, Value> = strings.chain(lists).collect();
+    serde_json::to_string_pretty(&object).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plural_forms() {
+        let input = r#"{
+            "files": { "one": "{n} file", "other": "{n} files" },
+            "items_one": "{n} item",
+            "items_other": "{n} items",
+            "show_other": "Show other",
+            "menu": { "open": "Open", "other": "Other" }
+        }"#;
+        let document = parse_json(input, &ParseOptions::default()).unwrap();
+        assert_eq!(document.cardinals["files"][&PluralCategory::One], "{n} file");
+        assert_eq!(document.cardinals["items"][&PluralCategory::Other], "{n} items");
+        assert!(!document.strings.contains_key("items_one"));
+        assert!(!document.order.contains(&"items_one".to_string()));
+        assert_eq!(document.strings["show_other"], "Show other");
+        assert_eq!(document.strings["menu.other"], "Other");
+        let input = r#"{ "files": { "one": "{count} file", "other": "{count} files" } }"#;
+        assert!(parse_json(input, &ParseOptions::default()).is_err());
+    }
+}
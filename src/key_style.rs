@@ -0,0 +1,175 @@
+/// Case of words in segments of keys following [`KeyStyle`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    /// `save-button`
+    Kebab,
+    /// `save_button`
+    Snake,
+}
+
+impl KeyCase {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Kebab => "kebab-case",
+            Self::Snake => "snake_case",
+        }
+    }
+
+    fn separator(self) -> char {
+        match self {
+            Self::Kebab => '-',
+            Self::Snake => '_',
+        }
+    }
+}
+
+/// Naming convention of keys checked by [`Lint::KeyStyle`](crate::Lint::KeyStyle).
+/// Keys are namespaces and a name separated by `.`, all of them lowercase words of
+/// ASCII letters and digits joined according to [`KeyCase`], like `settings.save-button`.
+///
+/// ```
+/// use ctl10n::{Builder, KeyCase, KeyStyle};
+///
+/// let builder = Builder::new().key_style(KeyStyle::new(KeyCase::Kebab).max_depth(2));
+/// assert!(builder.gen_strings_macro(r#""settings.save-button" = "Save""#).is_ok());
+/// let err = builder.gen_strings_macro(r#"saveButton = "Save""#).unwrap_err();
+/// assert!(err.to_string().contains("rename to `save-button`"));
+/// assert!(builder.gen_strings_macro(r#""a.b.c" = "Too deep""#).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStyle {
+    case: KeyCase,
+    max_depth: Option<usize>,
+    allowed: String,
+}
+
+impl KeyStyle {
+    /// Style with words in given case and any number of namespaces
+    pub fn new(case: KeyCase) -> Self {
+        Self {
+            case,
+            max_depth: None,
+            allowed: String::new(),
+        }
+    }
+
+    /// Allow at most `depth` segments separated by `.`, so `1` forbids namespaces
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Also allow characters of `chars` in words, e.g. `+` for `zoom-+`
+    pub fn allow_chars(mut self, chars: &str) -> Self {
+        self.allowed.push_str(chars);
+        self
+    }
+
+    fn is_word_char(&self, c: char) -> bool {
+        c.is_ascii_lowercase() || c.is_ascii_digit() || self.allowed.contains(c)
+    }
+
+    /// Problem with `key`, if it doesn't follow this style
+    pub(crate) fn check(&self, key: &str) -> Option<String> {
+        let separator = self.case.separator();
+        let segments: Vec<&str> = key.split('.').collect();
+        if let Some(depth) = self.max_depth.filter(|&depth| segments.len() > depth) {
+            return Some(format!("key has {} segments, but at most {} are allowed", segments.len(), depth));
+        }
+        let valid = segments.iter().all(|segment| {
+            let words: Vec<&str> = segment.split(separator).collect();
+            words.iter().all(|word| !word.is_empty() && word.chars().all(|c| self.is_word_char(c)))
+        });
+        if valid {
+            None
+        } else {
+            Some(format!("key isn't {}, rename to `{}`", self.case.name(), self.suggest(key)))
+        }
+    }
+
+    /// Name of `key` following this style: words are split on characters which aren't
+    /// allowed and on case changes, as in `saveButton`
+    pub(crate) fn suggest(&self, key: &str) -> String {
+        let separator = self.case.separator().to_string();
+        let segments: Vec<String> = key
+            .split('.')
+            .map(|segment| {
+                let mut words: Vec<String> = vec![String::new()];
+                let mut previous_lower = false;
+                for c in segment.chars() {
+                    if c.is_ascii_uppercase() && previous_lower {
+                        words.push(String::new());
+                    }
+                    previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+                    let c = c.to_ascii_lowercase();
+                    if self.is_word_char(c) {
+                        words.last_mut().unwrap().push(c);
+                    } else {
+                        words.push(String::new());
+                    }
+                }
+                let words: Vec<String> = words.into_iter().filter(|word| !word.is_empty()).collect();
+                words.join(&separator)
+            })
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        segments.join(".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggest_splits_camel_case() {
+        let kebab = KeyStyle::new(KeyCase::Kebab);
+        assert_eq!(kebab.suggest("saveButton"), "save-button");
+        assert_eq!(kebab.suggest("settings.saveButton"), "settings.save-button");
+        assert_eq!(KeyStyle::new(KeyCase::Snake).suggest("saveButton"), "save_button");
+    }
+
+    #[test]
+    fn suggest_replaces_separators() {
+        let kebab = KeyStyle::new(KeyCase::Kebab);
+        assert_eq!(kebab.suggest("save_button"), "save-button");
+        assert_eq!(kebab.suggest("Save  Button!"), "save-button");
+        assert_eq!(KeyStyle::new(KeyCase::Snake).suggest("save-button"), "save_button");
+    }
+
+    #[test]
+    fn suggest_keeps_digits_and_acronyms() {
+        let kebab = KeyStyle::new(KeyCase::Kebab);
+        assert_eq!(kebab.suggest("step2Title"), "step2-title");
+        assert_eq!(kebab.suggest("HTTPError"), "httperror");
+    }
+
+    #[test]
+    fn suggest_drops_empty_segments() {
+        let kebab = KeyStyle::new(KeyCase::Kebab);
+        assert_eq!(kebab.suggest("menu..open"), "menu.open");
+        assert_eq!(kebab.suggest("!!!.open"), "open");
+    }
+
+    #[test]
+    fn suggest_keeps_allowed_chars() {
+        let kebab = KeyStyle::new(KeyCase::Kebab).allow_chars("+");
+        assert_eq!(kebab.suggest("zoomIn+"), "zoom-in+");
+        assert_eq!(kebab.check("zoom-+"), None);
+    }
+
+    #[test]
+    fn suggestions_follow_style() {
+        let kebab = KeyStyle::new(KeyCase::Kebab);
+        for key in ["saveButton", "settings.save_button", "Step2Title"] {
+            assert_eq!(kebab.check(&kebab.suggest(key)), None, "{}", key);
+        }
+    }
+
+    #[test]
+    fn check_depth() {
+        let style = KeyStyle::new(KeyCase::Snake).max_depth(2);
+        assert_eq!(style.check("a.b"), None);
+        assert!(style.check("a.b.c").unwrap().contains("3 segments"));
+    }
+}
@@ -21,8 +21,6 @@
 //!
 //! Add the following to your `build.rs`:
 //! ```no_run
-//! use ctl10n;
-//!
 //! fn main() {
 //!     println!("cargo:rerun-if-changed=build.rs");
 //!     println!("cargo:rerun-if-changed=strings.toml");
@@ -44,8 +42,6 @@
 //! using `include!()`.
 //! After including the macro it can be used like this:
 //! ```ignore
-//! use ctl10n;
-//!
 //! ctl10n::include_strings!();
 //!
 //! fn main() {
@@ -65,15 +61,79 @@
 //! ```
 //! Trying to use an unknown key or wrong format arguments is a compile-time error.
 //!
+//! Keys which are identifiers after replacing `-` with `_` can also be written without
+//! quotes, e.g. `tr!(message_with_args, arg = "foobar")`, except with [`Backend::Binary`],
+//! where `tr!()` accepts any expression as key.
+//!
+//! `tr_in!("errors", "not-found")` is `tr!("errors.not-found")`, so modules can define a
+//! macro for their namespace instead of repeating it in every call:
+//! ```ignore
+//! macro_rules! tr_err {
+//!     ($key:tt $( $args:tt )*) => { tr_in!("errors", $key $( $args )*) };
+//! }
+//!
+//! println!("{}", tr_err!("not-found", path = "/tmp"));
+//! ```
+//! The namespace and key must be string literals, joined at expansion time. With [`Backend::Binary`]
+//! they can be any `&str` expressions, e.g. a `const`, and are joined when looking the
+//! string up, falling back to the key without namespace.
+//!
+//! `tr_static!("message")` is `tr!("message")` for keys without placeholders and is always
+//! `&'static str`, e.g. for APIs like `clap` which need one. For keys with placeholders it's a
+//! compile-time error listing the arguments they require, like ``Key `message-with-args` requires
+//! arguments {arg}``. With [`Backend::Binary`] it's looked up at runtime, so it can't initialize constants.
+//!
+//! `tr_clap!()` is `tr_static!()` for `clap` attributes, which accept expressions, so `--help`
+//! can be localized with keys checked at compile time:
+//! ```ignore
+//! #[derive(clap::Parser)]
+//! #[command(about = tr_clap!("cli-about"))]
+//! struct Args {
+//!     #[arg(long, help = tr_clap!("cli-verbose"))]
+//!     verbose: bool,
+//! }
+//! ```
+//!
+//! With [`Backend::Macro`] `tr!("key")` and `tr_static!("key")` expand to string literals, so
+//! they can be used in `#[doc = ...]` attributes and `concat!()`, which tests generated with
+//! [`Builder::format_tests`] check for every string without placeholders. With [hot reloading](Builder::hot_reload)
+//! only `tr_static!()` is a literal in debug builds. Attributes like `#[deprecated(note = ...)]`
+//! don't accept macros at all.
+//!
+//! `tr_cow!()` takes the same arguments as `tr!()` and returns `Cow<'static, str>`, borrowing
+//! the string without arguments and owning the formatted one with them, so results of both
+//! can be stored in one field:
+//! ```ignore
+//! let titles: Vec<std::borrow::Cow<'static, str>> = vec![tr_cow!("message"), tr_cow!("message-with-args", arg = 1)];
+//! ```
+//!
+//! Values can also be arrays of strings, which are available via `tr_list!()` as `&'static [&'static str]`:
+//! ```toml
+//! tips = ["first tip", "second tip"]
+//! ```
+//!
+//! ```ignore
+//! assert_eq!(tr_list!("tips"), &["first tip", "second tip"]);
+//! assert_eq!(tr_list!("tips", 1), "second tip");
+//! ```
+//!
+//! All keys available via `tr!()` are listed in the generated constant `CTL10N_KEYS: &[&str]`.
+//!
+//! `CTL10N_CATALOG_HASH: &str` is a fingerprint of all keys, values and metadata tables, which
+//! changes whenever any of them does, and `CTL10N_CATALOG_TIMESTAMP: u64` is the modification
+//! time of strings files in seconds since Unix epoch (or `$SOURCE_DATE_EPOCH`, if it's set), so
+//! binaries can report which strings they were built with.
+//!
+//! With [`Builder::string_catalog`] the generated `string_catalog()` returns all strings as
+//! [`StringCatalog`], which implements `Serialize` and `Deserialize`, so a server can send them to clients.
+//!
 //! # Multiple locales
 //! You can use environment variables to provide a different locale at compile time:
 //!
-//! ```
+//! ```no_run
 //! use std::env;
 //! use std::path::Path;
 //!
-//! use ctl10n;
-//!
 //! const LOCALES: &[&str] = &["de", "en"];
 //!
 //! fn main() {
@@ -94,20 +154,321 @@
 //! ```
 //!
 //! `LOCALE=de cargo build`
+//!
+//! Alternatively, [`convert_locales`] generates `strings_<locale>.rs` for every file in a directory
+//! and returns the list of found locales:
+//!
+//! ```no_run
+//! fn main() {
+//!     let locales = ctl10n::convert_locales("locales").expect("ctl10n failed");
+//!     for locale in locales {
+//!         println!("cargo:warning=generated {}", locale.locale);
+//!     }
+//! }
+//! ```
+//!
+//! ```ignore
+//! ctl10n::include_strings!("strings_de.rs");
+//! ```
+//!
+//! # Binary catalog
+//! For catalogs with thousands of keys, [`Backend::Binary`] stores strings in a compact
+//! binary catalog embedded with `include_bytes!()` instead of generating a macro arm per key.
+//! `tr!()` then looks strings up at runtime and additional locales can be shipped as external
+//! catalog files:
+//!
+//! ```no_run
+//! fn main() {
+//!     println!("cargo:rerun-if-changed=strings.toml");
+//!     ctl10n::Builder::new()
+//!         .backend(ctl10n::Backend::Binary)
+//!         .convert_default_strings_file()
+//!         .expect("ctl10n failed");
+//! }
+//! ```
+//!
+//! ```ignore
+//! ctl10n::include_strings!();
+//!
+//! fn main() {
+//!     let catalog = ctl10n::BinaryCatalog::load("locales/de.bin").unwrap();
+//!     CTL10N_CATALOG.replace(catalog);
+//!     println!("{}", tr!("message-with-args", arg = "foobar"));
+//! }
+//! ```
+//!
+//! # Runtime locale switching
+//! With [`Builder::runtime_locales`] all locales from a directory are embedded as binary catalogs
+//! and can be switched at runtime. With the `detect` feature the locale can be chosen
+//! according to OS settings:
+//!
+//! ```no_run
+//! fn main() {
+//!     ctl10n::Builder::new()
+//!         .runtime_locales("en")
+//!         .convert_locales("locales")
+//!         .expect("ctl10n failed");
+//! }
+//! ```
+//!
+//! ```ignore
+//! ctl10n::include_strings!();
+//!
+//! fn main() {
+//!     CTL10N_CATALOG.detect_locale();
+//!     // or CTL10N_CATALOG.set_locale("de");
+//!     println!("{}", tr!("message"));
+//! }
+//! ```
+//!
+//! # Conditional keys
+//! Keys written as `{ value, cfg }` tables exist only when the `cfg` predicate holds,
+//! so debug-only strings don't end up in release binaries. Using such key otherwise is
+//! a compilation error. With [`Backend::Binary`] the predicate is evaluated by the build
+//! script from `CARGO_CFG_*` and `CARGO_FEATURE_*` variables and the key is left out
+//! of the catalog, so `tr!()` returns the key itself. `CTL10N_KEYS` lists all keys.
+//!
+//! ```toml
+//! debug-panic-hint = { value = "Run with RUST_BACKTRACE=1 for a backtrace", cfg = "debug_assertions" }
+//! ```
+//!
+//! # Platform overrides
+//! Strings in `[target.'cfg(...)']` tables replace values of keys with the same names
+//! when the predicate holds, e.g. for keyboard shortcuts which differ between systems.
+//! Overrides must have the same placeholders as the base value, and predicates of
+//! overrides of the same key must not hold at once. With [`Backend::Binary`] the
+//! override is chosen by the build script, as for conditional keys.
+//!
+//! ```toml
+//! copy-hint = "Press Ctrl+C to copy"
+//!
+//! [target.'cfg(target_os = "macos")']
+//! copy-hint = "Press ⌘C to copy"
+//! ```
+//!
+//! # Includes
+//! Strings shared between locales or crates can be moved to separate files and included
+//! by path relative to the including file. Keys defined twice are an error by default,
+//! see [`Builder::include_duplicates`]:
+//!
+//! ```toml
+//! include = ["../common.toml"]
+//!
+//! greeting = "Hello!"
+//! ```
+//!
+//! # JSON exports
+//! Strings files with `.json` extension are read as nested JSON exported by Crowdin or
+//! Lokalise, with nested keys joined by `.`, see [`Builder::convert_strings_file`].
+//! Objects of plural forms like `{ "one": "{n} file", "other": "{n} files" }` and keys
+//! with suffixes like `files_one` and `files_other` become `[cardinal]` messages
+//! (see [Plurals](#plurals)). [`Builder::convert_locales`] reads `*.json` files too:
+//!
+//! ```no_run
+//! ctl10n::convert_strings_file("crowdin/de.json", "target/strings_de.rs").expect("ctl10n failed");
+//! ```
+//!
+//! # HTML
+//! Keys set to `true` in the reserved `[html]` table are also available via `tr_html!()`,
+//! which escapes arguments with [`HtmlEscaped`]. Static text of such messages may contain
+//! markup, but not scripts, which is checked during generation:
+//!
+//! ```toml
+//! welcome = "<b>Welcome</b>, {name}!"
+//!
+//! [html]
+//! welcome = true
+//! ```
+//!
+//! ```ignore
+//! // <b>Welcome</b>, &lt;script&gt;!
+//! let html = tr_html!("welcome", name = "<script>");
+//! ```
+//!
+//! # Numbers
+//! With [`Builder::number_format`] `trn_num!()` formats arguments with decimal and group
+//! separators of the locale, see [`NumberFormat`]:
+//!
+//! ```toml
+//! total = "Total: {sum}"
+//! ```
+//!
+//! ```ignore
+//! // Total: 1.234,5 with `locales/de.toml`
+//! let total = trn_num!("total", sum = 1234.5);
+//! ```
+//!
+//! # Lists
+//! With [`Builder::list_format`] `tr_join!()` joins items with separators of the locale,
+//! see [`ListFormat`]:
+//!
+//! ```ignore
+//! // "Alice, Bob et Carol" with `locales/fr.toml`
+//! let names = tr_join!(["Alice", "Bob", "Carol"]);
+//! ```
+//!
+//! # Plurals
+//! Messages in the reserved `[cardinal]` table have a variant per CLDR plural category
+//! and are available via `trn!(key, n)`, which picks the variant with [`cardinal_category`]
+//! for the locale named like the strings file. Messages in the `[ordinal]` table are
+//! available via `trn_ord!(key, n)`, which uses [`ordinal_category`]. Messages may only
+//! use `{n}` placeholder and must have `other` variant. Generated code requires `ctl10n`
+//! in `dependencies`.
+//!
+//! ```toml
+//! [cardinal]
+//! messages = { one = "{n} message", other = "{n} messages" }
+//!
+//! [ordinal]
+//! place = { one = "{n}st place", two = "{n}nd place", few = "{n}rd place", other = "{n}th place" }
+//! ```
+//!
+//! ```ignore
+//! assert_eq!(trn!("messages", 1), "1 message");
+//! assert_eq!(trn_ord!("place", 22), "22nd place");
+//! ```
+//!
+//! Languages the built-in rules don't cover can define conditions of categories in CLDR
+//! syntax in `cardinal` and `ordinal` tables of the reserved `[plural]` table. Categories
+//! are tried in CLDR order and `other` is used when none matches:
+//!
+//! ```
+//! let strings = r#"
+//! [plural.ordinal]
+//! one = "n % 10 = 1 and n % 100 != 11"
+//! few = "n = 3..4, 13"
+//!
+//! [ordinal]
+//! place = { one = "{n}-a", few = "{n}-ya", other = "{n}-i" }
+//! "#;
+//! assert!(ctl10n::gen_strings_macro(strings).is_ok());
+//! assert!(ctl10n::gen_strings_macro("[plural.ordinal]\none = \"n ~ 1\"").is_err());
+//! ```
+//!
+//! # Formats
+//! Values of the reserved `[formats]` table aren't available via `tr!()`, but as constants
+//! `FORMAT_<NAME>`, so locale-specific patterns for `chrono` or `time` can be kept with the
+//! other strings. With [`Builder::runtime_locales`] they're functions `format_<name>()`
+//! returning the pattern of the current locale:
+//!
+//! ```
+//! let code = ctl10n::gen_strings_macro("[formats]\nshort-date = \"%d.%m.%Y\"").unwrap();
+//! assert!(code.contains("FORMAT_SHORT_DATE"));
+//! ```
+//!
+//! ```ignore
+//! let date = chrono::Local::now().format(FORMAT_SHORT_DATE);
+//! ```
+
+#![allow(clippy::needless_doctest_main)]
 
-use std::env;
 use std::fmt::Display;
-use std::fs;
-use std::io::{Read, Write};
 use std::path::Path;
 
-use quote::quote;
-
 mod error;
 pub use crate::error::{Error, Result};
 
+mod placeholder;
+
 mod toml_parser;
-use toml_parser::parse_toml;
+
+mod escapes;
+pub use crate::escapes::Escapes;
+
+mod whitespace;
+pub use crate::whitespace::Whitespace;
+
+mod json;
+
+mod cfg;
+
+mod binary;
+pub use crate::binary::{ActiveCatalog, BinaryCatalog};
+
+mod lint;
+pub use crate::lint::{Lint, Severity};
+
+mod key_style;
+pub use crate::key_style::{KeyCase, KeyStyle};
+
+mod report;
+pub use crate::report::{GenerationReport, LintFinding};
+
+mod builder;
+pub use crate::builder::{Backend, Builder};
+
+mod codegen;
+
+mod manifest;
+
+mod metadata;
+
+mod schema;
+
+mod pot;
+
+mod markdown;
+
+mod snapshot;
+
+mod stats;
+pub use crate::stats::LocaleStats;
+
+mod lockfile;
+
+mod cache;
+
+mod fingerprint;
+pub use crate::fingerprint::fingerprint;
+
+mod catalog;
+pub use crate::catalog::Catalog;
+
+mod template;
+pub use crate::template::Template;
+
+mod string_catalog;
+pub use crate::string_catalog::StringCatalog;
+
+mod locales;
+pub use crate::locales::LocaleOutput;
+
+#[cfg(feature = "normalize")]
+mod normalize;
+#[cfg(feature = "normalize")]
+pub use crate::normalize::NormalizationForm;
+
+mod html;
+pub use crate::html::HtmlEscaped;
+
+mod number;
+pub use crate::number::{FormattedNumber, NumberFormat};
+
+mod currency;
+pub use crate::currency::{Amount, CurrencyFormat, FormattedMoney, SymbolPosition};
+
+mod truncate;
+pub use crate::truncate::{ellipsis_for_locale, grapheme_count, truncate};
+
+mod list;
+pub use crate::list::ListFormat;
+
+mod plural;
+pub use crate::plural::{cardinal_category, ordinal_category, PluralCategory};
+
+mod runtime;
+pub use crate::runtime::format_named;
+
+mod reload;
+pub use crate::reload::{reload_string, ReloadOptions};
+
+mod negotiate;
+pub use crate::negotiate::negotiate_locale;
+
+#[cfg(feature = "detect")]
+mod detect;
+#[cfg(feature = "detect")]
+pub use crate::detect::system_locales;
 
 /// Include `tr!()` macro from generated file to current namespace.
 /// If called without arguments includes file `$OUT_DIR/strings.rs`.
@@ -123,29 +484,18 @@ macro_rules! include_strings {
 }
 
 /// Convert TOML string to Rust source code with `tr!()` macro
+///
+/// Macro arms are in order of keys in the input, so output only depends on input and
+/// related messages stay together:
+/// ```
+/// let input = "b = \"B\"\na = \"A\"\nc = \"C\"";
+/// let first = ctl10n::gen_strings_macro(input).unwrap();
+/// let second = ctl10n::gen_strings_macro(input).unwrap();
+/// assert_eq!(first.as_bytes(), second.as_bytes());
+/// assert!(first.find("\"b\"").unwrap() < first.find("\"a\"").unwrap());
+/// ```
 pub fn gen_strings_macro(input: &str) -> Result<String> {
-    let strings = parse_toml(input)?;
-    let kv: Vec<(&str, &str)> = strings
-        .iter()
-        .map(|(k, v)| (k.as_ref(), v.as_ref()))
-        .collect();
-    let keys = kv.iter().map(|(fst, _)| fst);
-    let values = kv.iter().map(|(_, snd)| snd);
-
-    let result = quote! {
-        macro_rules! ctl10n_tr_inner {
-            #( (#keys) => { #values } );*;
-            ($key:tt) => {
-                compile_error!(concat!("There is no string for key `", stringify!($key), "`"))
-            };
-        }
-
-        macro_rules! tr {
-            ($key:tt) => { ctl10n_tr_inner!($key) };
-            ($key:tt, $( $args:tt )* ) => { format!(ctl10n_tr_inner!($key), $( $args )* ) };
-        }
-    };
-    Ok(result.to_string())
+    Builder::new().gen_strings_macro(input)
 }
 
 /// Convert given TOML file to Rust source code in given location, providing
@@ -153,26 +503,56 @@ pub fn gen_strings_macro(input: &str) -> Result<String> {
 pub fn convert_strings_file(
     toml_file: impl AsRef<Path> + Display,
     rs_file: impl AsRef<Path>,
-) -> Result<()> {
-    let mut input_file = fs::File::open(toml_file)?;
-    let mut input = String::new();
-    input_file.read_to_string(&mut input)?;
-    let code = gen_strings_macro(&input)?;
-    let mut output_file = fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(rs_file)?;
-    output_file.write(&code.as_bytes())?;
-    Ok(())
+) -> Result<GenerationReport> {
+    Builder::new().convert_strings_file(toml_file, rs_file)
 }
 
 /// Convert file `strings.toml` in current diretory to file `strings.rs` in `$OUT_DIR`
 /// # Panics
 /// If environment variable `OUT_DIR` is not set. You should call this function only
 /// from `build.rs` script
-pub fn convert_default_strings_file() -> Result<()> {
-    convert_strings_file(
-        "strings.toml",
-        Path::new(&env::var("OUT_DIR").unwrap()).join("strings.rs"),
-    )
+pub fn convert_default_strings_file() -> Result<GenerationReport> {
+    Builder::new().convert_default_strings_file()
+}
+
+/// Convert every `*.toml` and `*.json` file in `locales_dir` to `strings_<locale>.rs` in `$OUT_DIR`.
+/// See [`Builder::convert_locales`].
+/// # Panics
+/// If environment variable `OUT_DIR` is not set. You should call this function only
+/// from `build.rs` script
+pub fn convert_locales(locales_dir: impl AsRef<Path>) -> Result<Vec<LocaleOutput>> {
+    Builder::new().convert_locales(locales_dir)
+}
+
+/// Convert strings files of given locales to `strings_<locale>.rs` in `out_dir`.
+/// See [`Builder::convert_all`].
+pub fn convert_all(locales: &[(&str, &Path)], out_dir: impl AsRef<Path>) -> Result<GenerationReport> {
+    Builder::new().convert_all(locales, out_dir)
+}
+
+/// Convert strings as configured in `[package.metadata.ctl10n]` of `Cargo.toml`.
+/// See [`Builder::convert_from_manifest`].
+/// # Panics
+/// If environment variable `CARGO_MANIFEST_DIR` or `OUT_DIR` is not set. You should call
+/// this function only from `build.rs` script
+pub fn convert_from_manifest() -> Result<GenerationReport> {
+    Builder::new().convert_from_manifest()
+}
+
+/// Write JSON Schema describing keys of `template` strings file to `out_path`.
+/// See [`Builder::emit_schema`].
+pub fn emit_schema(template: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+    Builder::new().emit_schema(template, out_path)
+}
+
+/// Write gettext template for `template` strings file to `pot_path`.
+/// See [`Builder::export_pot`].
+pub fn export_pot(template: impl AsRef<Path>, pot_path: impl AsRef<Path>) -> Result<()> {
+    Builder::new().export_pot(template, pot_path)
+}
+
+/// Write strings file with translations of gettext file `po_path` to `out_path`.
+/// See [`Builder::import_po`].
+pub fn import_po(template: impl AsRef<Path>, po_path: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+    Builder::new().import_po(template, po_path, out_path)
 }
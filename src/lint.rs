@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::Error;
+use crate::key_style::KeyStyle;
+use crate::placeholder::{self, parse_placeholders, Placeholder};
+use crate::toml_parser::{Document, Limits, Strings};
+
+/// What to do when a check fails during generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Ignore the problem
+    Allow,
+    /// Report the problem with `cargo:warning`
+    Warn,
+    /// Fail generation
+    Deny,
+}
+
+/// Check of values, enabled with [`Builder::lint`](crate::Builder::lint)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lint {
+    /// Value starts or ends with whitespace
+    SurroundingWhitespace,
+    /// Value contains two spaces in a row
+    DoubleSpace,
+    /// Value ends with punctuation (`.`, `!`, `?`, `:`, `…` and their CJK variants)
+    /// while the same key in the source locale doesn't, or vice versa. Only checked by
+    /// [`Builder::convert_locales`](crate::Builder::convert_locales) with
+    /// [`Builder::source_locale`](crate::Builder::source_locale) set.
+    TerminalPunctuation,
+    /// Non-breaking space (U+00A0 or U+202F) at the start or end of value
+    /// or next to other whitespace
+    NonBreakingSpace,
+    /// Value contains invisible characters which are usually pasted accidentally:
+    /// zero-width space, word joiner, byte order mark or bidirectional formatting
+    /// characters (except left-to-right and right-to-left marks). This lint warns by default.
+    InvisibleCharacter,
+    /// Value uses different arguments than the same key in the source locale, e.g.
+    /// `{nom}` instead of `{name}`. Implicit `{}` placeholders are counted as positional
+    /// arguments. Only checked by [`Builder::convert_locales`](crate::Builder::convert_locales)
+    /// with [`Builder::source_locale`](crate::Builder::source_locale) set.
+    PlaceholderMismatch,
+    /// Value is identical to the values of other keys, so the keys could probably be
+    /// merged into one. Every group of keys sharing a value is reported once, for the key
+    /// defined first. With [`Builder::source_locale`](crate::Builder::source_locale)
+    /// set, keys with different values in the source locale aren't reported, since
+    /// languages don't always make the same distinctions. This lint warns by default.
+    DuplicateValue,
+    /// Value is identical to the same key in the source locale, so it was probably
+    /// copied and never translated. Values allowed with
+    /// [`Builder::allow_untranslated`](crate::Builder::allow_untranslated) and keys filled by
+    /// [`Builder::fill_missing`](crate::Builder::fill_missing) aren't reported. Only checked by
+    /// [`Builder::convert_locales`](crate::Builder::convert_locales) with
+    /// [`Builder::source_locale`](crate::Builder::source_locale) set, for locales other than it.
+    Untranslated,
+    /// Value contains a backslash sequence like `\n` which wasn't converted to the
+    /// character it stands for, usually because it was written in a literal `'...'` string
+    /// or exported escaped twice. See [`Builder::escapes`](crate::Builder::escapes).
+    SuspiciousEscape,
+    /// Key doesn't follow the naming convention set with
+    /// [`Builder::key_style`](crate::Builder::key_style), which enables this lint
+    KeyStyle,
+}
+
+impl Lint {
+    /// All lints
+    pub(crate) const ALL: [Lint; 10] = [
+        Lint::SurroundingWhitespace,
+        Lint::DoubleSpace,
+        Lint::TerminalPunctuation,
+        Lint::NonBreakingSpace,
+        Lint::InvisibleCharacter,
+        Lint::PlaceholderMismatch,
+        Lint::DuplicateValue,
+        Lint::Untranslated,
+        Lint::SuspiciousEscape,
+        Lint::KeyStyle,
+    ];
+
+    /// Name of lint in kebab case, e.g. `double-space`
+    pub(crate) fn name(self) -> String {
+        let mut name = String::new();
+        for c in format!("{:?}", self).chars() {
+            if c.is_ascii_uppercase() && !name.is_empty() {
+                name.push('-');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    /// Lint with given kebab case name
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+}
+
+impl Severity {
+    /// Severity with given lowercase name, e.g. `warn`
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(Severity::Allow),
+            "warn" => Some(Severity::Warn),
+            "deny" => Some(Severity::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Severities of enabled lints
+pub(crate) type Lints = BTreeMap<Lint, Severity>;
+
+/// Severities of lints enabled without calling [`Builder::lint`](crate::Builder::lint)
+pub(crate) fn default_lints() -> Lints {
+    let mut lints = Lints::new();
+    lints.insert(Lint::InvisibleCharacter, Severity::Warn);
+    lints.insert(Lint::DuplicateValue, Severity::Warn);
+    lints
+}
+
+const TERMINAL_PUNCTUATION: &[char] = &['.', '!', '?', ':', '…', '。', '！', '？', '：'];
+
+fn is_nbsp(c: char) -> bool {
+    matches!(c, '\u{a0}' | '\u{202f}')
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200b}' | '\u{2060}' | '\u{feff}' | '\u{180e}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Arguments referenced by format string, with implicit placeholders as indexed ones
+fn arguments(value: &str) -> BTreeSet<Placeholder> {
+    parse_placeholders(value)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|placeholder| match placeholder {
+            Placeholder::Implicit(idx) => Placeholder::Indexed(idx),
+            other => other,
+        })
+        .collect()
+}
+
+fn ends_with_punctuation(value: &str) -> bool {
+    value.trim_end().ends_with(TERMINAL_PUNCTUATION)
+}
+
+/// Problem with `value` found by `lint`, if any
+fn check_value(lint: Lint, value: &str, source: Option<&str>) -> Option<String> {
+    match lint {
+        Lint::SurroundingWhitespace => {
+            if value.starts_with(char::is_whitespace) {
+                Some("value starts with whitespace".to_string())
+            } else if value.ends_with(char::is_whitespace) {
+                Some("value ends with whitespace".to_string())
+            } else {
+                None
+            }
+        }
+        Lint::DoubleSpace => value.find("  ").map(|pos| format!("double space at byte {}", pos)),
+        Lint::TerminalPunctuation => {
+            let source = source?;
+            match (ends_with_punctuation(source), ends_with_punctuation(value)) {
+                (true, false) => Some("value doesn't end with punctuation, but source does".to_string()),
+                (false, true) => Some("value ends with punctuation, but source doesn't".to_string()),
+                _ => None,
+            }
+        }
+        Lint::NonBreakingSpace => {
+            let chars: Vec<char> = value.chars().collect();
+            let misused = chars.iter().enumerate().any(|(idx, &c)| {
+                let neighbours = [idx.checked_sub(1).map(|idx| chars[idx]), chars.get(idx + 1).copied()];
+                is_nbsp(c)
+                    && neighbours
+                        .iter()
+                        .any(|neighbour| neighbour.is_none_or(char::is_whitespace))
+            });
+            misused.then(|| "non-breaking space at the edge of value or next to whitespace".to_string())
+        }
+        Lint::PlaceholderMismatch => {
+            let (expected, found) = (arguments(source?), arguments(value));
+            let list = |placeholders: Vec<&Placeholder>| {
+                let placeholders: Vec<String> = placeholders.iter().map(|p| format!("`{}`", p)).collect();
+                placeholders.join(", ")
+            };
+            let missing: Vec<_> = expected.difference(&found).collect();
+            let unexpected: Vec<_> = found.difference(&expected).collect();
+            match (missing.is_empty(), unexpected.is_empty()) {
+                (true, true) => None,
+                (false, true) => Some(format!("missing {} used in source", list(missing))),
+                (true, false) => Some(format!("{} not used in source", list(unexpected))),
+                (false, false) => Some(format!(
+                    "missing {} used in source, {} not used in source",
+                    list(missing),
+                    list(unexpected),
+                )),
+            }
+        }
+        Lint::InvisibleCharacter => value
+            .char_indices()
+            .find(|&(_, c)| is_invisible(c))
+            .map(|(pos, c)| format!("invisible character U+{:04X} at byte {}", c as u32, pos)),
+        Lint::DuplicateValue | Lint::KeyStyle => None,
+        Lint::Untranslated => (source? == value).then(|| "value is the same as in source".to_string()),
+        Lint::SuspiciousEscape => crate::escapes::sequence_position(value)
+            .map(|pos| format!("backslash sequence `{}` at byte {}", &value[pos..pos + 2], pos)),
+    }
+}
+
+/// Groups of keys of `document` sharing a value, in order of definition. Keys with
+/// different values in `source` are put into different groups.
+fn duplicate_values<'a>(document: &'a Document, source: Option<&Strings>) -> Vec<Vec<&'a String>> {
+    let source_value = |key: &String| source.and_then(|source| source.get(key));
+    let mut groups: Vec<Vec<&String>> = Vec::new();
+    let mut by_value: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (key, value) in document.in_order(&document.strings) {
+        let indexes = by_value.entry(value.as_str()).or_default();
+        match indexes.iter().find(|&&idx| source_value(groups[idx][0]) == source_value(key)) {
+            Some(&idx) => groups[idx].push(key),
+            None => {
+                indexes.push(groups.len());
+                groups.push(vec![key]);
+            }
+        }
+    }
+    groups.retain(|keys| keys.len() > 1);
+    groups
+}
+
+/// Run enabled `lints` on values (and every item of array values) of `document`,
+/// comparing strings to `source` locale where needed. Values in `untranslated` may
+/// be the same as in source.
+pub(crate) fn check_lints(
+    document: &Document,
+    source: Option<&Strings>,
+    lints: &Lints,
+    untranslated: &BTreeSet<String>,
+) -> Vec<(Severity, Error)> {
+    let mut errors = Vec::new();
+    for (&lint, &severity) in lints {
+        if severity == Severity::Allow {
+            continue;
+        }
+        // Keys are checked by `check_key_style`
+        if lint == Lint::KeyStyle {
+            continue;
+        }
+        if lint == Lint::DuplicateValue {
+            for keys in duplicate_values(document, source) {
+                let others: Vec<String> = keys[1..].iter().map(|key| format!("`{}`", key)).collect();
+                let detail = format!("same value as {}", others.join(", "));
+                errors.push((severity, Error::Lint { key: keys[0].clone(), lint, detail }));
+            }
+            continue;
+        }
+        let strings = document.strings.iter().map(|(key, value)| {
+            let source = source.and_then(|source| source.get(key)).map(String::as_str);
+            (key, value, source)
+        });
+        let lists = document
+            .lists
+            .iter()
+            .flat_map(|(key, items)| items.iter().map(move |item| (key, item, None)));
+        for (key, value, source) in strings.chain(lists) {
+            if lint == Lint::Untranslated && untranslated.contains(value) {
+                continue;
+            }
+            if let Some(detail) = check_value(lint, value, source) {
+                errors.push((severity, Error::Lint { key: key.clone(), lint, detail }));
+            }
+        }
+    }
+    errors
+}
+
+/// Check keys of strings and arrays of `document` against `style`
+pub(crate) fn check_key_style(document: &Document, style: &KeyStyle) -> Vec<Error> {
+    document
+        .order
+        .iter()
+        .filter_map(|key| {
+            let detail = style.check(key)?;
+            Some(Error::Lint { key: key.clone(), lint: Lint::KeyStyle, detail })
+        })
+        .collect()
+}
+
+/// Check values (and every item of array values) against `limits`.
+/// Keys missing from `document` are skipped.
+pub(crate) fn check_limits(document: &Document, limits: &Limits) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for (key, &limit) in limits {
+        let values = document
+            .strings
+            .get(key)
+            .into_iter()
+            .chain(document.lists.get(key).into_iter().flatten())
+            .chain(document.targets.get(key).into_iter().flat_map(|overrides| overrides.values()));
+        for value in values {
+            let length = value.chars().count();
+            if length > limit {
+                errors.push(Error::TooLong {
+                    key: key.clone(),
+                    length,
+                    limit,
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Check that values of keys with declared `args` (and their target overrides) use exactly
+/// those placeholders. Keys missing from `document` are skipped.
+pub(crate) fn check_args(document: &Document, args: &BTreeMap<String, Vec<String>>) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for (key, args) in args {
+        let values = document
+            .strings
+            .get(key)
+            .into_iter()
+            .chain(document.targets.get(key).into_iter().flat_map(|overrides| overrides.values()));
+        for value in values {
+            if let Err(detail) = placeholder::check_args(value, args) {
+                errors.push(Error::ArgsMismatch {
+                    key: key.clone(),
+                    detail,
+                });
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::{parse_toml, ParseOptions};
+
+    fn document(toml: &str) -> Document {
+        parse_toml(toml, &ParseOptions::default()).unwrap()
+    }
+
+    fn details(errors: Vec<(Severity, Error)>) -> Vec<String> {
+        errors.into_iter().map(|(_, error)| error.to_string()).collect()
+    }
+
+    #[test]
+    fn duplicates_are_reported_once_per_group() {
+        let document = document("a = \"Cancel\"\nb = \"OK\"\nc = \"Cancel\"\nd = \"Cancel\"\ne = \"OK\"\nf = \"Other\"");
+        let details = details(check_lints(&document, None, &default_lints(), &BTreeSet::new()));
+        assert_eq!(details.len(), 2, "{:?}", details);
+        assert!(details[0].contains("Key `a`") && details[0].ends_with("same value as `c`, `d`"));
+        assert!(details[1].contains("Key `b`") && details[1].ends_with("same value as `e`"));
+    }
+
+    #[test]
+    fn duplicates_with_different_source_values() {
+        let source = document("a = \"Close\"\nb = \"Close\"\nc = \"Shut\"").strings;
+        let document = document("a = \"Fermer\"\nb = \"Fermer\"\nc = \"Fermer\"");
+        let details = details(check_lints(&document, Some(&source), &default_lints(), &BTreeSet::new()));
+        assert_eq!(details.len(), 1, "{:?}", details);
+        assert!(details[0].contains("Key `a`") && details[0].ends_with("same value as `b`"));
+    }
+
+    #[test]
+    fn invisible_characters_warn_by_default() {
+        let document = document("ok = \"O\u{200b}K\"");
+        let errors = check_lints(&document, None, &default_lints(), &BTreeSet::new());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, Severity::Warn);
+        assert!(errors[0].1.to_string().ends_with("invisible character U+200B at byte 1"));
+    }
+}
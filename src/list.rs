@@ -0,0 +1,97 @@
+use std::fmt::{Display, Write};
+
+use crate::negotiate::{language, normalize};
+
+/// Separators of a locale for joining lists of items into a sentence, used by `tr_join!()`.
+///
+/// ```
+/// let format = ctl10n::ListFormat::for_locale("fr");
+/// assert_eq!(format.join(["a", "b", "c"]), "a, b et c");
+/// assert_eq!(ctl10n::ListFormat::for_locale("en").join([1, 2, 3]), "1, 2, and 3");
+/// assert_eq!(ctl10n::ListFormat::for_locale("en").join([1, 2]), "1 and 2");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListFormat {
+    /// Separator between items except the last two
+    pub separator: &'static str,
+    /// Separator between the last two items of three or more
+    pub last: &'static str,
+    /// Separator between the only two items
+    pub pair: &'static str,
+}
+
+/// Separators used by languages not listed below
+const DEFAULT: ListFormat = ListFormat::new(", ", ", and ", " and ");
+
+/// Separator before the last item in languages which don't put a comma before conjunction
+const CONJUNCTIONS: &[(&str, &str)] = &[
+    ("ca", " i "),
+    ("cs", " a "),
+    ("da", " og "),
+    ("de", " und "),
+    ("es", " y "),
+    ("fi", " ja "),
+    ("fr", " et "),
+    ("hu", " és "),
+    ("it", " e "),
+    ("nb", " og "),
+    ("nl", " en "),
+    ("nn", " og "),
+    ("no", " og "),
+    ("pl", " i "),
+    ("pt", " e "),
+    ("ro", " și "),
+    ("ru", " и "),
+    ("sk", " a "),
+    ("sv", " och "),
+    ("tr", " ve "),
+    ("uk", " і "),
+];
+
+impl ListFormat {
+    /// List format with given separators
+    pub const fn new(separator: &'static str, last: &'static str, pair: &'static str) -> Self {
+        Self { separator, last, pair }
+    }
+
+    /// List format commonly used in locale with given BCP-47 tag. Unknown locales
+    /// use English separators.
+    pub fn for_locale(tag: &str) -> Self {
+        let tag = normalize(tag);
+        match language(&tag) {
+            "ja" => Self::new("、", "、", "、"),
+            "zh" => Self::new("、", "和", "和"),
+            language => match CONJUNCTIONS.iter().find(|(name, _)| *name == language) {
+                Some((_, conjunction)) => Self::new(DEFAULT.separator, conjunction, conjunction),
+                None => DEFAULT,
+            },
+        }
+    }
+
+    /// Join `items` with separators of this format
+    pub fn join<I>(&self, items: I) -> String
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let items: Vec<I::Item> = items.into_iter().collect();
+        let mut result = String::new();
+        for (idx, item) in items.iter().enumerate() {
+            let separator = match (idx, items.len()) {
+                (0, _) => "",
+                (1, 2) => self.pair,
+                (idx, len) if idx == len - 1 => self.last,
+                _ => self.separator,
+            };
+            result.push_str(separator);
+            let _ = write!(result, "{}", item);
+        }
+        result
+    }
+}
+
+impl Default for ListFormat {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::report::GenerationReport;
+
+/// Locale generated by [`Builder::convert_locales`]
+///
+/// [`Builder::convert_locales`]: crate::Builder::convert_locales
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleOutput {
+    /// Locale name, i.e. file stem of the strings file
+    pub locale: String,
+    /// Strings file
+    pub source: PathBuf,
+    /// Generated Rust file
+    pub output: PathBuf,
+    /// Name of cargo feature selecting this locale with [`Builder::locale_features`]
+    ///
+    /// [`Builder::locale_features`]: crate::Builder::locale_features
+    pub feature: String,
+    /// Warnings and statistics collected while generating the locale
+    pub report: GenerationReport,
+}
+
+/// Cargo feature name for locale, e.g. `locale-pt-br` for `pt_BR`
+pub(crate) fn feature_name(locale: &str) -> String {
+    format!("locale-{}", locale.to_lowercase().replace('_', "-"))
+}
+
+/// Name of `Locale` enum variant for locale, e.g. `PtBr` for `pt-BR`
+pub(crate) fn variant_name(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars.map(|c| c.to_ascii_lowercase())).collect::<String>()
+        })
+        .collect()
+}
+
+/// Overrides file for strings file, e.g. `en.overrides.toml` for `en.toml`
+pub(crate) fn overrides_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.overrides.toml", stem))
+}
+
+/// Whether `tag` is a well-formed BCP-47 language tag like `en`, `pt-BR` or `sr-Cyrl`
+pub(crate) fn is_bcp47(tag: &str) -> bool {
+    let alpha = |s: &str| s.chars().all(|c| c.is_ascii_alphabetic());
+    let digit = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    let alnum = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric());
+
+    let mut subtags = tag.split('-').peekable();
+    match subtags.next() {
+        Some(lang) if alpha(lang) && matches!(lang.len(), 2..=3 | 5..=8) => {}
+        _ => return false,
+    }
+    if let Some(script) = subtags.peek() {
+        if script.len() == 4 && alpha(script) {
+            subtags.next();
+        }
+    }
+    if let Some(region) = subtags.peek() {
+        if (region.len() == 2 && alpha(region)) || (region.len() == 3 && digit(region)) {
+            subtags.next();
+        }
+    }
+    // Variants, then extensions: singleton followed by at least one subtag
+    let (mut in_extension, mut empty_extension) = (false, false);
+    for subtag in subtags {
+        if subtag.is_empty() || subtag.len() > 8 || !alnum(subtag) {
+            return false;
+        }
+        if subtag.len() == 1 {
+            if empty_extension {
+                return false;
+            }
+            in_extension = true;
+            empty_extension = true;
+        } else if in_extension {
+            empty_extension = false;
+        } else if !(subtag.len() >= 5 || subtag.starts_with(|c: char| c.is_ascii_digit())) {
+            return false;
+        }
+    }
+    !empty_extension
+}
+
+/// Locale named `name`, whose strings file is `path`, with `_` replaced by `-` if
+/// `normalize` is set. It must be a BCP-47 tag.
+pub(crate) fn locale_name(name: &str, path: &Path, normalize: bool) -> Result<String> {
+    let locale = if normalize {
+        name.replace('_', "-")
+    } else {
+        name.to_string()
+    };
+    if !is_bcp47(&locale) {
+        let fixed = locale.replace('_', "-");
+        let error = Error::InvalidLocaleName {
+            suggestion: Some(fixed).filter(|fixed| is_bcp47(fixed)),
+            name: locale,
+        };
+        return Err(error.in_file(path));
+    }
+    Ok(locale)
+}
+
+/// Check that no locale of `locales` is defined by more than one strings file
+pub(crate) fn check_duplicates(locales: &[(String, PathBuf)]) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    match locales.iter().find(|(locale, _)| !seen.insert(locale)) {
+        Some((locale, path)) => Err(Error::DuplicateLocale(locale.clone()).in_file(path)),
+        None => Ok(()),
+    }
+}
+
+/// Find `*.toml` and `*.json` files in `dir` except overrides files, sorted by locale name.
+/// File stems must be BCP-47 tags; with `normalize` underscores in them are replaced with
+/// dashes first. A locale can only be defined by one file.
+pub(crate) fn discover(dir: &Path, normalize: bool) -> Result<Vec<(String, PathBuf)>> {
+    let in_dir = |err: std::io::Error| Error::from(err).in_file(dir);
+    let mut locales = Vec::new();
+    for entry in fs::read_dir(dir).map_err(in_dir)? {
+        let path = entry.map_err(in_dir)?.path();
+        let extension = path.extension().unwrap_or_default();
+        if !path.is_file() || (extension != "toml" && extension != "json") {
+            continue;
+        }
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        if stem.ends_with(".overrides") {
+            continue;
+        }
+        locales.push((locale_name(&stem, &path, normalize)?, path));
+    }
+    locales.sort();
+    check_duplicates(&locales)?;
+    Ok(locales)
+}
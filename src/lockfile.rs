@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::{Error, Result};
+use crate::placeholder::{parse_placeholders, Placeholder};
+use crate::toml_parser::Document;
+
+/// What a key of the lockfile is expected to be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Signature {
+    /// String taking given arguments, like `{name}` and `{0}`
+    String(BTreeSet<String>),
+    /// Array of strings
+    List,
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(arguments) if arguments.is_empty() => write!(f, "a string without arguments"),
+            Self::String(arguments) => {
+                let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+                write!(f, "a string with arguments {}", arguments.join(", "))
+            }
+            Self::List => write!(f, "an array"),
+        }
+    }
+}
+
+pub(crate) type Signatures = BTreeMap<String, Signature>;
+
+/// Signatures of all keys of `document`, with implicit placeholders as indexed ones
+pub(crate) fn signatures(document: &Document) -> Signatures {
+    let strings = document.strings.iter().map(|(key, value)| {
+        let arguments = parse_placeholders(value)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|placeholder| match placeholder {
+                Placeholder::Implicit(idx) => Placeholder::Indexed(idx),
+                other => other,
+            })
+            .map(|placeholder| placeholder.to_string())
+            .collect();
+        (key.clone(), Signature::String(arguments))
+    });
+    let lists = document.lists.keys().map(|key| (key.clone(), Signature::List));
+    strings.chain(lists).collect()
+}
+
+/// Lockfile listing `signatures`: array `lists` with keys of arrays and table `strings`
+/// with arguments of every string key
+pub(crate) fn render(signatures: &Signatures) -> String {
+    let mut lists = Vec::new();
+    let mut strings = toml::value::Table::new();
+    for (key, signature) in signatures {
+        match signature {
+            Signature::String(arguments) => {
+                let arguments = arguments.iter().cloned().map(toml::Value::String).collect();
+                strings.insert(key.clone(), toml::Value::Array(arguments));
+            }
+            Signature::List => lists.push(toml::Value::String(key.clone())),
+        }
+    }
+    let mut lockfile = toml::value::Table::new();
+    lockfile.insert("lists".to_string(), toml::Value::Array(lists));
+    lockfile.insert("strings".to_string(), toml::Value::Table(strings));
+    format!(
+        "# Keys of strings known to ctl10n, set CTL10N_UPDATE_LOCKFILE=1 to update\n{}",
+        toml::to_string(&toml::Value::Table(lockfile)).unwrap()
+    )
+}
+
+/// Read lockfile written by [`render`]
+pub(crate) fn parse(input: &str) -> Result<Signatures> {
+    let invalid = |key: &str, detail: &str| Error::InvalidMetadata {
+        key: key.to_string(),
+        detail: detail.to_string(),
+    };
+    let lockfile: toml::Value = toml::from_str(input)?;
+    let mut signatures = Signatures::new();
+    let lists = lockfile.get("lists").and_then(toml::Value::as_array);
+    for key in lists.ok_or_else(|| invalid("lists", "must be an array"))? {
+        let key = key.as_str().ok_or_else(|| invalid("lists", "must contain only strings"))?;
+        signatures.insert(key.to_string(), Signature::List);
+    }
+    let strings = lockfile.get("strings").and_then(toml::Value::as_table);
+    for (key, arguments) in strings.ok_or_else(|| invalid("strings", "must be a table"))? {
+        let arguments = arguments
+            .as_array()
+            .and_then(|arguments| arguments.iter().map(|argument| argument.as_str().map(str::to_string)).collect())
+            .ok_or_else(|| invalid(key, "must be an array of strings"))?;
+        signatures.insert(key.clone(), Signature::String(arguments));
+    }
+    Ok(signatures)
+}
+
+/// Keys of `locked` which were removed or changed signature in `current`.
+/// New keys aren't reported, since adding one can't break translations.
+pub(crate) fn changes(locked: &Signatures, current: &Signatures) -> Vec<Error> {
+    locked
+        .iter()
+        .filter_map(|(key, signature)| {
+            let detail = match current.get(key) {
+                None => "was removed".to_string(),
+                Some(found) if found != signature => format!("was {}, but now is {}", signature, found),
+                Some(_) => return None,
+            };
+            Some(Error::LockedKey { key: key.clone(), detail })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::{parse_toml, ParseOptions};
+
+    fn signatures_of(toml: &str) -> Signatures {
+        signatures(&parse_toml(toml, &ParseOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn implicit_placeholders_are_indexed() {
+        let signatures = signatures_of("hi = \"Hi {name}, {} {}\"\ntips = [\"a\"]");
+        let arguments = ["{0}", "{1}", "{name}"].iter().map(|argument| argument.to_string()).collect();
+        assert_eq!(signatures["hi"], Signature::String(arguments));
+        assert_eq!(signatures["tips"], Signature::List);
+    }
+
+    #[test]
+    fn round_trip() {
+        let signatures = signatures_of("ok = \"OK\"\n\"a.b\" = \"{0}\"\ntips = [\"a\"]");
+        assert_eq!(parse(&render(&signatures)).unwrap(), signatures);
+    }
+
+    #[test]
+    fn invalid_lockfile() {
+        assert!(parse("lists = 1\n[strings]").is_err());
+        assert!(parse("lists = []\nstrings = []").is_err());
+        assert!(parse("lists = []\n[strings]\nok = [1]").is_err());
+    }
+
+    #[test]
+    fn changes_ignore_new_keys() {
+        let locked = signatures_of("ok = \"OK\"\nhi = \"Hi {name}\"\ntips = [\"a\"]");
+        let current = signatures_of("ok = \"Okay\"\nhi = \"Hi {user}\"\nnew = \"New\"");
+        let details: Vec<String> = changes(&locked, &current).iter().map(ToString::to_string).collect();
+        assert_eq!(details.len(), 2, "{:?}", details);
+        assert!(details[0].contains("hi") && details[0].contains("user"), "{}", details[0]);
+        assert!(details[1].contains("tips") && details[1].contains("was removed"), "{}", details[1]);
+    }
+}
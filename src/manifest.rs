@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::fingerprint::{fingerprint, list_fingerprint};
+use crate::placeholder::parse_placeholders;
+use crate::toml_parser::Document;
+
+/// Version of manifest format, incremented on incompatible changes
+const MANIFEST_VERSION: u32 = 1;
+
+/// Machine-readable description of strings file for external tooling. With `fingerprints`
+/// every key has fingerprint of its value and, if `source_document` of the source locale
+/// has the key, fingerprint of the source value.
+pub(crate) fn manifest(
+    document: &Document,
+    source: &Path,
+    fingerprints: bool,
+    source_document: Option<&Document>,
+) -> Value {
+    let position = |key: &str| document.positions.get(key).copied().unwrap_or_default();
+    let file = |key: &str| document.location(key, source).0.display().to_string();
+    let mut keys = Vec::new();
+    for (key, value) in &document.strings {
+        let (line, column) = position(key);
+        let mut seen = BTreeSet::new();
+        let placeholders: Vec<String> = parse_placeholders(value)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|placeholder| seen.insert(placeholder.clone()))
+            .map(|placeholder| placeholder.to_string())
+            .collect();
+        keys.push(json!({
+            "key": key,
+            "kind": "string",
+            "placeholders": placeholders,
+            "file": file(key),
+            "line": line,
+            "column": column,
+        }));
+    }
+    for (key, items) in &document.lists {
+        let (line, column) = position(key);
+        keys.push(json!({
+            "key": key,
+            "kind": "list",
+            "items": items.len(),
+            "file": file(key),
+            "line": line,
+            "column": column,
+        }));
+    }
+    for key in &mut keys {
+        if let Some(predicate) = key["key"].as_str().and_then(|name| document.cfgs.get(name)) {
+            key["cfg"] = json!(predicate);
+        }
+        if let Some(defaults) = key["key"].as_str().and_then(|name| document.defaults.get(name)) {
+            key["defaults"] = json!(defaults);
+        }
+        let args = key["key"].as_str().and_then(|name| {
+            document.args.get(name).or_else(|| source_document?.args.get(name))
+        });
+        if let Some(args) = args {
+            key["args"] = json!(args);
+        }
+    }
+    if fingerprints {
+        for key in &mut keys {
+            let name = key["key"].as_str().unwrap_or_default().to_string();
+            let value_fingerprint = |document: &Document| match (document.strings.get(&name), document.lists.get(&name)) {
+                (Some(value), _) => Some(fingerprint(value)),
+                (None, Some(items)) => Some(list_fingerprint(items)),
+                (None, None) => None,
+            };
+            key["fingerprint"] = json!(value_fingerprint(document));
+            if let Some(source_fingerprint) = source_document.and_then(value_fingerprint) {
+                key["source_fingerprint"] = json!(source_fingerprint);
+            }
+        }
+    }
+    keys.sort_by(|a, b| a["key"].as_str().cmp(&b["key"].as_str()));
+    json!({
+        "version": MANIFEST_VERSION,
+        "source": source.display().to_string(),
+        "keys": keys,
+    })
+}
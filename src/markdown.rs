@@ -0,0 +1,68 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::placeholder::parse_placeholders;
+use crate::toml_parser::Document;
+
+/// Escape text for a Markdown table cell
+fn cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace('\n', "<br>")
+}
+
+/// Text of `key` in `document`, with list items on separate lines
+fn value(document: &Document, key: &str) -> Option<String> {
+    match (document.strings.get(key), document.lists.get(key)) {
+        (Some(value), _) => Some(value.clone()),
+        (None, Some(items)) => Some(items.join("\n")),
+        (None, None) => None,
+    }
+}
+
+/// Markdown document with a table row per key, listing its placeholders and text in
+/// every locale. `locales` are in column order, the first one is the source.
+pub(crate) fn catalog_report(title: &str, locales: &[(String, Document)]) -> String {
+    let keys: BTreeSet<&String> = locales
+        .iter()
+        .flat_map(|(_, document)| document.strings.keys().chain(document.lists.keys()))
+        .collect();
+    let mut report = format!("# {}\n\n", title);
+    let mut missing: BTreeMap<&str, usize> = BTreeMap::new();
+
+    report.push_str("| Key | Placeholders |");
+    for (locale, _) in locales {
+        write!(report, " {} |", cell(locale)).unwrap();
+    }
+    report.push_str("\n|---|---|");
+    report.push_str(&"---|".repeat(locales.len()));
+    report.push('\n');
+    for key in keys {
+        let mut placeholders = BTreeSet::new();
+        for (_, document) in locales {
+            if let Some(value) = document.strings.get(key) {
+                placeholders.extend(parse_placeholders(value).unwrap_or_default());
+            }
+        }
+        let placeholders: Vec<String> = placeholders.iter().map(|p| format!("`{}`", p)).collect();
+        write!(report, "| `{}` | {} |", cell(key), placeholders.join(", ")).unwrap();
+        for (locale, document) in locales {
+            match value(document, key) {
+                Some(text) => write!(report, " {} |", cell(&text)).unwrap(),
+                None => {
+                    *missing.entry(locale).or_default() += 1;
+                    report.push_str(" *missing* |");
+                }
+            }
+        }
+        report.push('\n');
+    }
+    if !missing.is_empty() {
+        report.push_str("\n## Missing translations\n\n");
+        for (locale, count) in missing {
+            writeln!(report, "- {}: {}", locale, count).unwrap();
+        }
+    }
+    report
+}
@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+
+use crate::builder::{Backend, Builder};
+use crate::error::{Error, Result};
+use crate::escapes::Escapes;
+use crate::key_style::{KeyCase, KeyStyle};
+use crate::lint::{Lint, Severity};
+use crate::whitespace::Whitespace;
+
+/// What `[package.metadata.ctl10n]` asks to convert
+pub(crate) enum Input {
+    /// Strings file, converted to `strings.rs`
+    Strings(PathBuf),
+    /// Directory with a strings file per locale
+    Locales(PathBuf),
+}
+
+/// Read `[package.metadata.ctl10n]` table of `manifest`, returning `builder` with options
+/// set in it and what to convert. Paths are relative to the manifest.
+pub(crate) fn read(manifest: &Path, builder: Builder) -> Result<(Builder, Input)> {
+    let in_file = |err: Error| err.in_file(manifest);
+    let input = std::fs::read(manifest)
+        .map_err(Error::from)
+        .and_then(crate::toml_parser::decode)
+        .map_err(in_file)?;
+    let manifest_value: toml::Value = toml::from_str(&input).map_err(|err| in_file(err.into()))?;
+    let config = manifest_value
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("ctl10n"));
+    let config = match config {
+        Some(toml::Value::Table(config)) => config.clone(),
+        Some(other) => return Err(in_file(invalid("ctl10n", format!("must be a table, found {}", other.type_str())))),
+        None => toml::value::Table::new(),
+    };
+    apply(manifest.parent().unwrap_or_else(|| Path::new("")), config, builder).map_err(in_file)
+}
+
+fn invalid(option: &str, detail: String) -> Error {
+    Error::InvalidMetadata {
+        key: format!("package.metadata.{}", option),
+        detail,
+    }
+}
+
+fn apply(dir: &Path, config: toml::value::Table, mut builder: Builder) -> Result<(Builder, Input)> {
+    let mut input = Input::Strings(dir.join("strings.toml"));
+    // Loaded after other options are set, since they affect how it's read
+    let mut template = None;
+    for (option, value) in config {
+        let key = format!("ctl10n.{}", option);
+        let wrong_type = |expected: &str, value: &toml::Value| {
+            invalid(&key, format!("must be {}, found {}", expected, value.type_str()))
+        };
+        let string = |value: &toml::Value| value.as_str().map(str::to_string).ok_or_else(|| wrong_type("a string", value));
+        let boolean = |value: &toml::Value| value.as_bool().ok_or_else(|| wrong_type("a boolean", value));
+        let severity = |value: &toml::Value| {
+            let name = string(value)?;
+            Severity::from_name(&name).ok_or_else(|| invalid(&key, format!("unknown severity `{}`", name)))
+        };
+        builder = match option.as_str() {
+            "strings" => {
+                input = Input::Strings(dir.join(string(&value)?));
+                builder
+            }
+            "locales" => {
+                input = Input::Locales(dir.join(string(&value)?));
+                builder
+            }
+            "backend" => match string(&value)?.as_str() {
+                "macro" => builder.backend(Backend::Macro),
+                "binary" => builder.backend(Backend::Binary),
+                other => return Err(invalid(&key, format!("unknown backend `{}`", other))),
+            },
+            "default-locale" => builder.runtime_locales(string(&value)?),
+            "locale-features" => builder.locale_features(boolean(&value)?),
+            "source-locale" => builder.source_locale(string(&value)?),
+            "template" => {
+                template = Some(dir.join(string(&value)?));
+                builder
+            }
+            "fill-missing" => builder.fill_missing(boolean(&value)?),
+            "normalize-locale-names" => builder.normalize_locale_names(boolean(&value)?),
+            "pretty" => builder.pretty(boolean(&value)?),
+            "lenient-values" => builder.lenient_values(boolean(&value)?),
+            "join-arrays" => builder.join_arrays(string(&value)?),
+            "macro-export" => builder.macro_export(boolean(&value)?),
+            "decl-macro" => builder.decl_macro(boolean(&value)?),
+            "out-dir" => builder.out_dir(dir.join(string(&value)?)),
+            "locale-file-name" => builder.locale_file_name(string(&value)?),
+            "manifest" => builder.manifest(boolean(&value)?),
+            "json-bundle" => builder.json_bundle(boolean(&value)?),
+            "string-catalog" => builder.string_catalog(boolean(&value)?),
+            "whitespace" => {
+                let name = string(&value)?;
+                let mode = Whitespace::from_name(&name)
+                    .ok_or_else(|| invalid(&key, format!("unknown whitespace mode `{}`", name)))?;
+                builder.whitespace(mode)
+            }
+            "escapes" => {
+                let name = string(&value)?;
+                let escapes = Escapes::from_name(&name)
+                    .ok_or_else(|| invalid(&key, format!("unknown escapes policy `{}`", name)))?;
+                builder.escapes(escapes)
+            }
+            #[cfg(feature = "normalize")]
+            "normalize-unicode" => match string(&value)?.as_str() {
+                "nfc" => builder.normalize_unicode(crate::NormalizationForm::Nfc),
+                "nfkc" => builder.normalize_unicode(crate::NormalizationForm::Nfkc),
+                other => return Err(invalid(&key, format!("unknown normalization form `{}`", other))),
+            },
+            #[cfg(not(feature = "normalize"))]
+            "normalize-unicode" => return Err(invalid(&key, "requires the `normalize` feature".to_string())),
+            "show-keys" => builder.show_keys(boolean(&value)?),
+            "lockfile" => builder.lockfile(dir.join(string(&value)?)),
+            "locked-keys" => builder.locked_keys(severity(&value)?),
+            "fingerprints" => builder.fingerprints(boolean(&value)?),
+            "env-overrides" => builder.env_overrides(boolean(&value)?),
+            "format-tests" => builder.format_tests(boolean(&value)?),
+            "number-format" => builder.number_format(boolean(&value)?),
+            "list-format" => builder.list_format(boolean(&value)?),
+            "truncation" => builder.truncation(boolean(&value)?),
+            "parallel" => builder.parallel(boolean(&value)?),
+            "hot-reload" => builder.hot_reload(boolean(&value)?),
+            "print-warnings" => builder.print_warnings(boolean(&value)?),
+            "fail-on-warnings" => builder.fail_on_warnings(boolean(&value)?),
+            "length-limits" => builder.length_limits(severity(&value)?),
+            "include-duplicates" => builder.include_duplicates(severity(&value)?),
+            "allow-untranslated" => {
+                let values = value.as_array().ok_or_else(|| wrong_type("an array", &value))?;
+                builder.allow_untranslated(values.iter().map(string).collect::<Result<Vec<_>>>()?)
+            }
+            "macro-budget" => match value.as_integer() {
+                Some(arms) if arms >= 0 => builder.macro_budget(arms as usize),
+                _ => return Err(invalid(&key, "must be a non-negative integer".to_string())),
+            },
+            "auto-binary" => builder.auto_binary(boolean(&value)?),
+            "incremental" => builder.incremental(boolean(&value)?),
+            "split-prefixes" => {
+                let values = value.as_array().ok_or_else(|| wrong_type("an array", &value))?;
+                builder.split_prefixes(values.iter().map(string).collect::<Result<Vec<_>>>()?)
+            }
+            "key-style" => {
+                let case = |name: &str| match name {
+                    "kebab-case" => Ok(KeyCase::Kebab),
+                    "snake_case" => Ok(KeyCase::Snake),
+                    other => Err(invalid(&key, format!("unknown case `{}`", other))),
+                };
+                let style = match &value {
+                    toml::Value::String(name) => KeyStyle::new(case(name)?),
+                    toml::Value::Table(table) => {
+                        let mut style = KeyStyle::new(case(&string(table.get("case").unwrap_or(&value))?)?);
+                        for (name, value) in table {
+                            style = match name.as_str() {
+                                "case" => style,
+                                "max-depth" => match value.as_integer() {
+                                    Some(depth) if depth > 0 => style.max_depth(depth as usize),
+                                    _ => return Err(invalid(&key, "max-depth must be a positive integer".to_string())),
+                                },
+                                "allow-chars" => style.allow_chars(&string(value)?),
+                                other => return Err(invalid(&key, format!("unknown field `{}`", other))),
+                            };
+                        }
+                        style
+                    }
+                    other => return Err(wrong_type("a string or a table", other)),
+                };
+                builder.key_style(style)
+            }
+            "lints" => {
+                let lints = value.as_table().ok_or_else(|| wrong_type("a table", &value))?;
+                for (name, value) in lints {
+                    let lint = Lint::from_name(name).ok_or_else(|| invalid(&key, format!("unknown lint `{}`", name)))?;
+                    builder = builder.lint(lint, severity(value)?);
+                }
+                builder
+            }
+            _ => return Err(invalid(&key, "unknown option".to_string())),
+        };
+    }
+    if let Some(path) = template {
+        let template = builder.load_template(path)?;
+        builder = builder.template(template);
+    }
+    Ok((builder, input))
+}
@@ -0,0 +1,114 @@
+/// Normalize locale identifier for comparison: `de_DE.UTF-8@euro` becomes `de-de`
+pub(crate) fn normalize(tag: &str) -> String {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    tag.trim().replace('_', "-").to_lowercase()
+}
+
+pub(crate) fn language(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or_default()
+}
+
+/// Pick the best of `available` locales for `requested` tags in order of preference.
+///
+/// For every requested tag tries an exact match, then progressively shorter prefixes
+/// (`zh-Hant-TW`, `zh-Hant`, `zh`) and then any available locale with the same language.
+/// Comparison is case-insensitive, `_` is treated as `-` and POSIX suffixes like `.UTF-8`
+/// are ignored, so values of `$LANG` can be used as is.
+pub(crate) fn negotiate<'a, 'r>(
+    requested: impl IntoIterator<Item = &'r str>,
+    available: &[&'a str],
+) -> Option<&'a str> {
+    let normalized: Vec<String> = available.iter().map(|tag| normalize(tag)).collect();
+    let find = |tag: &str| normalized.iter().position(|available| available == tag);
+    for tag in requested {
+        let mut tag = normalize(tag);
+        if tag.is_empty() || tag == "c" || tag == "posix" {
+            continue;
+        }
+        let lang = language(&tag).to_string();
+        loop {
+            if let Some(idx) = find(&tag) {
+                return Some(available[idx]);
+            }
+            match tag.rfind('-') {
+                Some(end) => tag.truncate(end),
+                None => break,
+            }
+        }
+        if let Some(idx) = normalized.iter().position(|available| language(available) == lang) {
+            return Some(available[idx]);
+        }
+    }
+    None
+}
+
+/// Pick the best of `available` locales for `requested` BCP-47 tags in order of
+/// preference (e.g. parsed from `Accept-Language`), or `default` if none matches.
+///
+/// For every requested tag tries an exact match, then progressively shorter prefixes
+/// and then any available locale with the same language.
+///
+/// ```
+/// let available = ["de", "en", "pt-BR"];
+/// assert_eq!(ctl10n::negotiate_locale(&["pt-PT", "de"], &available, "en"), "pt-BR");
+/// assert_eq!(ctl10n::negotiate_locale(&["de-AT", "en"], &available, "en"), "de");
+/// assert_eq!(ctl10n::negotiate_locale(&["fr"], &available, "en"), "en");
+/// ```
+pub fn negotiate_locale<'a>(requested: &[&str], available: &[&'a str], default: &'a str) -> &'a str {
+    negotiate(requested.iter().copied(), available).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_posix_locales() {
+        assert_eq!(normalize("de_DE.UTF-8@euro"), "de-de");
+        assert_eq!(normalize(" en_US "), "en-us");
+        assert_eq!(language("zh-hant-tw"), "zh");
+    }
+
+    #[test]
+    fn exact_match_wins() {
+        assert_eq!(negotiate(["en-GB"], &["en", "en-GB"]), Some("en-GB"));
+        assert_eq!(negotiate(["EN_gb.UTF-8"], &["en", "en-GB"]), Some("en-GB"));
+    }
+
+    #[test]
+    fn shorter_prefixes() {
+        let available = ["zh", "zh-Hant", "en"];
+        assert_eq!(negotiate(["zh-Hant-TW"], &available), Some("zh-Hant"));
+        assert_eq!(negotiate(["zh-Hans-CN"], &available), Some("zh"));
+    }
+
+    #[test]
+    fn same_language() {
+        assert_eq!(negotiate(["pt-PT"], &["en", "pt-BR"]), Some("pt-BR"));
+    }
+
+    #[test]
+    fn earlier_requests_are_preferred() {
+        let available = ["de", "fr"];
+        assert_eq!(negotiate(["fr-CA", "de"], &available), Some("fr"));
+        assert_eq!(negotiate(["es", "de-AT", "fr"], &available), Some("de"));
+    }
+
+    #[test]
+    fn posix_and_empty_tags_are_skipped() {
+        assert_eq!(negotiate(["C", "POSIX", "", "fr"], &["c", "fr"]), Some("fr"));
+        assert_eq!(negotiate(["C.UTF-8"], &["en"]), None);
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(negotiate(["ja"], &["en", "de"]), None);
+    }
+
+    #[test]
+    fn default_locale() {
+        assert_eq!(negotiate_locale(&["ja"], &["en", "de"], "en"), "en");
+        assert_eq!(negotiate_locale(&[], &["en", "de"], "de"), "de");
+        assert_eq!(negotiate_locale(&["de-AT"], &["en", "de"], "en"), "de");
+    }
+}
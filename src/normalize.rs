@@ -0,0 +1,26 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::toml_parser::Document;
+
+/// Unicode normalization form applied by [`Builder::normalize_unicode`](crate::Builder::normalize_unicode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical composition: `e` followed by combining acute accent becomes `é`
+    Nfc,
+    /// Compatibility composition: also replaces ligatures, full-width forms etc.,
+    /// e.g. `ﬁ` becomes `fi`
+    Nfkc,
+}
+
+/// Normalize every value (and array item) of `document`
+pub(crate) fn normalize(document: &mut Document, form: NormalizationForm) {
+    let normalize = |value: &mut String| {
+        *value = match form {
+            NormalizationForm::Nfc => value.nfc().collect(),
+            NormalizationForm::Nfkc => value.nfkc().collect(),
+        };
+    };
+    document.strings.values_mut().for_each(normalize);
+    document.lists.values_mut().flatten().for_each(normalize);
+    document.targets.values_mut().flat_map(|overrides| overrides.values_mut()).for_each(normalize);
+}
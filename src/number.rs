@@ -0,0 +1,124 @@
+use std::fmt::{self, Alignment, Display};
+
+use crate::negotiate::{language, normalize};
+
+/// Decimal and group separators of a locale, used by `trn_num!()` to format numbers
+/// before interpolating them.
+///
+/// ```
+/// let format = ctl10n::NumberFormat::for_locale("de-DE");
+/// assert_eq!(format.format(1234.5).to_string(), "1.234,5");
+/// assert_eq!(format!("{:.2}", format.format(-1234567)), "-1.234.567");
+/// assert_eq!(format!("{:>8.1}", format.format(1234.56)), " 1.234,6");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Separator between integer and fractional part
+    pub decimal: &'static str,
+    /// Separator between groups of three digits of integer part
+    pub group: &'static str,
+}
+
+/// Separators used by languages not listed below, e.g. English, Chinese and Japanese
+const DEFAULT: NumberFormat = NumberFormat::new(".", ",");
+
+/// Languages using `,` as decimal and `.` as group separator
+const COMMA_DOT: &[&str] = &[
+    "da", "de", "el", "es", "hr", "id", "it", "nl", "pt", "ro", "sl", "sr", "tr", "vi",
+];
+
+/// Languages using `,` as decimal separator and no-break space as group separator
+const COMMA_SPACE: &[&str] = &[
+    "bg", "cs", "et", "fi", "fr", "hu", "lt", "lv", "nb", "nn", "no", "pl", "ru", "sk", "sv", "uk",
+];
+
+impl NumberFormat {
+    /// Number format with given separators
+    pub const fn new(decimal: &'static str, group: &'static str) -> Self {
+        Self { decimal, group }
+    }
+
+    /// Number format commonly used in locale with given BCP-47 tag. Unknown
+    /// locales use `.` as decimal and `,` as group separator.
+    pub fn for_locale(tag: &str) -> Self {
+        let tag = normalize(tag);
+        match tag.as_str() {
+            "de-ch" | "de-li" | "it-ch" => return Self::new(".", "\u{2019}"),
+            "es-mx" | "es-us" | "pt-mo" => return Self::new(".", ","),
+            _ => {}
+        }
+        let language = language(&tag);
+        if COMMA_DOT.contains(&language) {
+            Self::new(",", ".")
+        } else if COMMA_SPACE.contains(&language) {
+            Self::new(",", "\u{a0}")
+        } else {
+            DEFAULT
+        }
+    }
+
+    /// Wrap `value` so it's displayed with separators of this format. Precision, width,
+    /// alignment and fill of the format spec are respected.
+    pub fn format<T: Display>(&self, value: T) -> FormattedNumber<'_, T> {
+        FormattedNumber { format: self, value }
+    }
+
+    /// Replace separators in number formatted with [`Display`]
+    pub(crate) fn localize(&self, plain: &str) -> String {
+        let (sign, rest) = plain.split_at(plain.find(|c: char| c.is_ascii_digit()).unwrap_or(plain.len()));
+        let (integer, fraction) = rest.split_at(rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len()));
+        let mut result = sign.to_string();
+        for (idx, digit) in integer.chars().enumerate() {
+            if idx > 0 && (integer.len() - idx) % 3 == 0 {
+                result.push_str(self.group);
+            }
+            result.push(digit);
+        }
+        match fraction.strip_prefix('.') {
+            Some(fraction) => {
+                result.push_str(self.decimal);
+                result.push_str(fraction);
+            }
+            None => result.push_str(fraction),
+        }
+        result
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+/// Number displayed according to [`NumberFormat`], returned by [`NumberFormat::format`]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedNumber<'a, T> {
+    format: &'a NumberFormat,
+    value: T,
+}
+
+impl<T: Display> Display for FormattedNumber<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plain = match f.precision() {
+            Some(precision) => format!("{:.*}", precision, self.value),
+            None => self.value.to_string(),
+        };
+        let localized = self.format.localize(&plain);
+        let padding = f.width().unwrap_or(0).saturating_sub(localized.chars().count());
+        let (before, after) = match f.align() {
+            Some(Alignment::Left) => (0, padding),
+            Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+            Some(Alignment::Right) | None => (padding, 0),
+        };
+        let fill = f.fill();
+        for _ in 0..before {
+            f.write_fmt(format_args!("{}", fill))?;
+        }
+        f.write_str(&localized)?;
+        for _ in 0..after {
+            f.write_fmt(format_args!("{}", fill))?;
+        }
+        Ok(())
+    }
+}
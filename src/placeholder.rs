@@ -0,0 +1,252 @@
+/// Argument referenced by a `format!()` placeholder
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Placeholder {
+    /// `{name}`
+    Named(String),
+    /// `{0}`
+    Indexed(usize),
+    /// `{}`, the `n`-th implicit positional argument
+    Implicit(usize),
+}
+
+impl std::fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, "{{{}}}", name),
+            Self::Indexed(idx) => write!(f, "{{{}}}", idx),
+            Self::Implicit(_) => write!(f, "{{}}"),
+        }
+    }
+}
+
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+        && s != "_"
+}
+
+/// Parse placeholders of a `format!()` string in order of appearance.
+/// Returns description of the problem if the string isn't a valid format string.
+pub(crate) fn parse_placeholders(value: &str) -> Result<Vec<Placeholder>, String> {
+    parse_format(value).map(|(placeholders, _)| placeholders)
+}
+
+/// Parse arguments used as width or precision by placeholders of a `format!()` string,
+/// like `w` of `{:w$}`, `1` of `{:1$}` and the implicit argument taken by `{:.*}`.
+/// These must be `usize`.
+pub(crate) fn parse_counts(value: &str) -> Result<Vec<Placeholder>, String> {
+    parse_format(value).map(|(_, counts)| counts)
+}
+
+/// Placeholders of valid `format!()` string `value` which have a format spec, like `{name:>10}`
+pub(crate) fn with_specs(value: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut rest = value;
+    while let Some(idx) = rest.find('{') {
+        if rest[idx..].starts_with("{{") {
+            rest = &rest[idx + 2..];
+            continue;
+        }
+        let end = rest[idx..].find('}').map_or(rest.len(), |end| idx + end + 1);
+        if rest[idx..end].contains(':') {
+            result.push(&rest[idx..end]);
+        }
+        rest = &rest[end..];
+    }
+    result
+}
+
+/// Read parameter of a width or precision at the start of `spec`, returning the rest
+fn parse_count(spec: &str) -> (Option<Placeholder>, &str) {
+    let end = spec.find(|c: char| c != '_' && !c.is_alphanumeric()).unwrap_or(spec.len());
+    match spec[end..].strip_prefix('$') {
+        Some(rest) => match spec[..end].parse() {
+            Ok(idx) => (Some(Placeholder::Indexed(idx)), rest),
+            Err(_) => (Some(Placeholder::Named(spec[..end].to_string())), rest),
+        },
+        None => (None, &spec[end..]),
+    }
+}
+
+/// Parse width and precision parameters of `spec`, the part of a placeholder after `:`.
+/// `implicit` is the number of implicit positional arguments taken so far.
+fn parse_spec_counts(spec: &str, implicit: &mut usize) -> Vec<Placeholder> {
+    let mut counts = Vec::new();
+    let mut chars = spec.chars();
+    let mut rest = match (chars.next(), chars.next()) {
+        (Some(fill), Some('<' | '^' | '>')) => &spec[fill.len_utf8() + 1..],
+        (Some('<' | '^' | '>'), _) => &spec[1..],
+        _ => spec,
+    };
+    rest = rest.strip_prefix(['+', '-']).unwrap_or(rest);
+    rest = rest.strip_prefix('#').unwrap_or(rest);
+    if !rest.starts_with("0$") {
+        rest = rest.strip_prefix('0').unwrap_or(rest);
+    }
+    let (width, after) = parse_count(rest);
+    counts.extend(width);
+    if let Some(precision) = after.strip_prefix('.') {
+        if precision.starts_with('*') {
+            *implicit += 1;
+            counts.push(Placeholder::Implicit(*implicit - 1));
+        } else {
+            counts.extend(parse_count(precision).0);
+        }
+    }
+    counts
+}
+
+fn parse_format(value: &str) -> Result<(Vec<Placeholder>, Vec<Placeholder>), String> {
+    let mut result = Vec::new();
+    let mut counts = Vec::new();
+    let mut implicit = 0;
+    let mut chars = value.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                chars.next();
+            }
+            '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                chars.next();
+            }
+            '}' => return Err(format!("unmatched `}}` at offset {}", idx)),
+            '{' => {
+                let end = loop {
+                    match chars.next() {
+                        Some((end, '}')) => break end,
+                        Some((_, '{')) | None => {
+                            return Err(format!("unclosed `{{` at offset {}", idx))
+                        }
+                        Some(_) => {}
+                    }
+                };
+                let inner = &value[idx + 1..end];
+                let (argument, spec) = inner.split_once(':').unwrap_or((inner, ""));
+                counts.extend(parse_spec_counts(spec, &mut implicit));
+                let placeholder = if argument.is_empty() {
+                    implicit += 1;
+                    Placeholder::Implicit(implicit - 1)
+                } else if let Ok(idx) = argument.parse() {
+                    Placeholder::Indexed(idx)
+                } else if is_identifier(argument) {
+                    Placeholder::Named(argument.to_string())
+                } else {
+                    return Err(format!("invalid argument name `{}` in `{{{}}}`", argument, inner));
+                };
+                result.push(placeholder);
+            }
+            _ => {}
+        }
+    }
+    Ok((result, counts))
+}
+
+/// Number of single-character edits and swaps of adjacent characters turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1).min(distances[i][j - 1] + 1).min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// Check that `value` uses exactly the named placeholders `args`. Returns description
+/// of the first problem, suggesting the declared argument an unknown placeholder is
+/// probably a typo of.
+pub(crate) fn check_args(value: &str, args: &[String]) -> Result<(), String> {
+    let placeholders = parse_placeholders(value)?;
+    for placeholder in &placeholders {
+        let name = match placeholder {
+            Placeholder::Named(name) => name,
+            _ => return Err(format!("positional placeholder `{}` is used, but arguments are declared", placeholder)),
+        };
+        if args.contains(name) {
+            continue;
+        }
+        let suggestion = args
+            .iter()
+            .map(|arg| (edit_distance(name, arg), arg))
+            .filter(|&(distance, arg)| distance <= (arg.chars().count() / 3).max(1))
+            .min();
+        return Err(match suggestion {
+            Some((_, arg)) => format!("placeholder `{}` isn't a declared argument, did you mean `{{{}}}`?", placeholder, arg),
+            None => format!("placeholder `{}` isn't a declared argument", placeholder),
+        });
+    }
+    match args.iter().find(|arg| !placeholders.contains(&Placeholder::Named(arg.to_string()))) {
+        Some(arg) => Err(format!("declared argument `{}` isn't used", arg)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        let placeholders = parse_placeholders("{name:>8} {} {0} {{literal}} {:?}").unwrap();
+        assert_eq!(
+            placeholders,
+            [
+                Placeholder::Named("name".to_string()),
+                Placeholder::Implicit(0),
+                Placeholder::Indexed(0),
+                Placeholder::Implicit(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn specs() {
+        assert_eq!(with_specs("{{a:b}} {name:>10} {other} {:.2}"), ["{name:>10}", "{:.2}"]);
+        assert!(with_specs("{name}").is_empty());
+    }
+
+    #[test]
+    fn counts() {
+        let value = "{:w$} {:1$} {:.*} {name:>0$.prec$} {:08.3}";
+        assert_eq!(
+            parse_counts(value).unwrap(),
+            [
+                Placeholder::Named("w".to_string()),
+                Placeholder::Indexed(1),
+                Placeholder::Implicit(2),
+                Placeholder::Indexed(0),
+                Placeholder::Named("prec".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_placeholders(value).unwrap(),
+            [
+                Placeholder::Implicit(0),
+                Placeholder::Implicit(1),
+                Placeholder::Implicit(3),
+                Placeholder::Named("name".to_string()),
+                Placeholder::Implicit(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_placeholders() {
+        assert!(parse_placeholders("{ name }").is_err());
+        assert!(parse_placeholders("{ }").is_err());
+        assert!(parse_placeholders("{name").is_err());
+        assert!(parse_placeholders("name}").is_err());
+        assert!(parse_placeholders("{na-me}").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::negotiate::{language, normalize};
+
+/// CLDR plural category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// All categories in CLDR order
+    pub const ALL: [PluralCategory; 6] = [
+        PluralCategory::Zero,
+        PluralCategory::One,
+        PluralCategory::Two,
+        PluralCategory::Few,
+        PluralCategory::Many,
+        PluralCategory::Other,
+    ];
+
+    /// Name of category as used in CLDR and strings files, e.g. `few`
+    pub fn name(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+
+    /// Category with given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|category| category.name() == name)
+    }
+}
+
+/// Kind of plural messages, which have their own table, rules and macro
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PluralKind {
+    /// Messages of `[cardinal]` table, available via `trn!()`
+    Cardinal,
+    /// Messages of `[ordinal]` table, available via `trn_ord!()`
+    Ordinal,
+}
+
+impl PluralKind {
+    /// Name of the kind as used in strings files, e.g. `ordinal`
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            PluralKind::Cardinal => "cardinal",
+            PluralKind::Ordinal => "ordinal",
+        }
+    }
+}
+
+/// Cardinal plural category of `n` in locale with given BCP-47 tag according to CLDR
+/// rules for integers, used by `trn!()`. Languages without plural forms and unknown
+/// languages always use [`PluralCategory::Other`].
+///
+/// ```
+/// use ctl10n::{cardinal_category, PluralCategory};
+///
+/// assert_eq!(cardinal_category("en", 1), PluralCategory::One);
+/// assert_eq!(cardinal_category("en", 0), PluralCategory::Other);
+/// assert_eq!(cardinal_category("fr", 0), PluralCategory::One);
+/// assert_eq!(cardinal_category("ru", 22), PluralCategory::Few);
+/// assert_eq!(cardinal_category("ru", 12), PluralCategory::Many);
+/// assert_eq!(cardinal_category("ja", 1), PluralCategory::Other);
+/// ```
+pub fn cardinal_category(locale: &str, n: u64) -> PluralCategory {
+    use PluralCategory::*;
+
+    let (n10, n100) = (n % 10, n % 100);
+    match language(&normalize(locale)) {
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" | "ms" | "lo" | "my" | "km" => Other,
+        "fr" | "pt" if n <= 1 => One,
+        "fr" => match n % 1_000_000 {
+            0 => Many,
+            _ => Other,
+        },
+        "hi" | "bn" | "fa" | "gu" | "kn" | "mr" | "zu" | "am" if n <= 1 => One,
+        "ru" | "uk" | "be" => match (n10, n100) {
+            (1, _) if n100 != 11 => One,
+            (2..=4, _) if !(12..=14).contains(&n100) => Few,
+            _ => Many,
+        },
+        "pl" => match (n10, n100) {
+            _ if n == 1 => One,
+            (2..=4, _) if !(12..=14).contains(&n100) => Few,
+            _ => Many,
+        },
+        "hr" | "sr" | "bs" => match (n10, n100) {
+            (1, _) if n100 != 11 => One,
+            (2..=4, _) if !(12..=14).contains(&n100) => Few,
+            _ => Other,
+        },
+        "cs" | "sk" => match n {
+            1 => One,
+            2..=4 => Few,
+            _ => Other,
+        },
+        "lt" => match (n10, n100) {
+            (_, 11..=19) => Other,
+            (1, _) => One,
+            (2..=9, _) => Few,
+            _ => Other,
+        },
+        "lv" => match (n10, n100) {
+            (0, _) | (_, 11..=19) => Zero,
+            (1, _) => One,
+            _ => Other,
+        },
+        "ro" => match n100 {
+            _ if n == 1 => One,
+            _ if n == 0 => Few,
+            1..=19 => Few,
+            _ => Other,
+        },
+        "sl" => match n100 {
+            1 => One,
+            2 => Two,
+            3 | 4 => Few,
+            _ => Other,
+        },
+        "he" => match n {
+            1 => One,
+            2 => Two,
+            _ => Other,
+        },
+        "ar" => match (n, n100) {
+            (0, _) => Zero,
+            (1, _) => One,
+            (2, _) => Two,
+            (_, 3..=10) => Few,
+            (_, 11..=99) => Many,
+            _ => Other,
+        },
+        "ga" => match n {
+            1 => One,
+            2 => Two,
+            3..=6 => Few,
+            7..=10 => Many,
+            _ => Other,
+        },
+        "cy" => match n {
+            0 => Zero,
+            1 => One,
+            2 => Two,
+            3 => Few,
+            6 => Many,
+            _ => Other,
+        },
+        "is" | "mk" if n10 == 1 && n100 != 11 => One,
+        "is" | "mk" => Other,
+        _ if n == 1 => One,
+        _ => Other,
+    }
+}
+
+/// Ordinal plural category of `n` in locale with given BCP-47 tag according to CLDR
+/// rules, used by `trn_ord!()`. Languages without ordinal distinctions and unknown
+/// languages always use [`PluralCategory::Other`].
+///
+/// ```
+/// use ctl10n::{ordinal_category, PluralCategory};
+///
+/// assert_eq!(ordinal_category("en", 22), PluralCategory::Two);
+/// assert_eq!(ordinal_category("en", 12), PluralCategory::Other);
+/// assert_eq!(ordinal_category("fr", 1), PluralCategory::One);
+/// assert_eq!(ordinal_category("de", 1), PluralCategory::Other);
+/// ```
+pub fn ordinal_category(locale: &str, n: u64) -> PluralCategory {
+    use PluralCategory::*;
+
+    let (n10, n100) = (n % 10, n % 100);
+    match language(&normalize(locale)) {
+        "en" => match (n10, n100) {
+            (1, 11) | (2, 12) | (3, 13) => Other,
+            (1, _) => One,
+            (2, _) => Two,
+            (3, _) => Few,
+            _ => Other,
+        },
+        "fr" | "ga" | "hy" | "ms" | "ro" | "vi" if n == 1 => One,
+        "ca" => match n {
+            1 | 3 => One,
+            2 => Two,
+            4 => Few,
+            _ => Other,
+        },
+        "hu" if n == 1 || n == 5 => One,
+        "it" if matches!(n, 8 | 11 | 80 | 800) => Many,
+        "sv" if matches!(n10, 1 | 2) && !matches!(n100, 11 | 12) => One,
+        _ => Other,
+    }
+}
+
+/// Convert CLDR plural rule condition like `n % 10 = 1 and n % 100 != 11` to Rust
+/// expression checking `n: u64`. Only integers are counted, so operands `v`, `w`, `f`,
+/// `t`, `c` and `e` are always zero.
+pub(crate) fn parse_condition(condition: &str) -> Result<TokenStream, String> {
+    let tokens = tokenize(condition)?;
+    let mut parser = Parser { tokens: &tokens, pos: 0 };
+    let expr = parser.condition()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected `{}` in plural rule `{}`", token, condition)),
+    }
+}
+
+fn tokenize(condition: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    // Samples like `@integer 1, 21, 31` aren't part of the condition
+    let rule = condition.split('@').next().unwrap_or_default();
+    let mut chars = rule.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphanumeric() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(word);
+        } else {
+            chars.next();
+            let token = match (c, chars.peek()) {
+                ('!', Some('=')) | ('.', Some('.')) => {
+                    let second = chars.next().unwrap();
+                    format!("{}{}", c, second)
+                }
+                ('%' | '=' | ',', _) => c.to_string(),
+                _ => return Err(format!("unexpected `{}` in plural rule `{}`", c, condition)),
+            };
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matches = self.peek() == Some(token);
+        self.pos += matches as usize;
+        matches
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&str, String> {
+        let token = self.tokens.get(self.pos).ok_or_else(|| format!("expected {} at end of plural rule", expected))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn condition(&mut self) -> Result<TokenStream, String> {
+        let mut conditions = vec![self.and_condition()?];
+        while self.eat("or") {
+            conditions.push(self.and_condition()?);
+        }
+        Ok(quote! { ( #( #conditions )||* ) })
+    }
+
+    fn and_condition(&mut self) -> Result<TokenStream, String> {
+        let mut relations = vec![self.relation()?];
+        while self.eat("and") {
+            relations.push(self.relation()?);
+        }
+        Ok(quote! { ( #( #relations )&&* ) })
+    }
+
+    fn relation(&mut self) -> Result<TokenStream, String> {
+        let operand = match self.next("operand")? {
+            "n" | "i" => quote! { n },
+            "v" | "w" | "f" | "t" | "c" | "e" => quote! { 0u64 },
+            other => return Err(format!("unknown plural operand `{}`", other)),
+        };
+        let expr = if self.eat("%") || self.eat("mod") {
+            let modulus = self.value()?;
+            quote! { (#operand % #modulus) }
+        } else {
+            operand
+        };
+        let negated = match self.next("relation")?.to_string().as_str() {
+            "=" | "in" | "within" => false,
+            "!=" => true,
+            "is" => self.eat("not"),
+            "not" if self.eat("in") || self.eat("within") => true,
+            other => return Err(format!("unknown plural relation `{}`", other)),
+        };
+        let mut items = Vec::new();
+        loop {
+            let start = self.value()?;
+            items.push(if self.eat("..") {
+                let end = self.value()?;
+                quote! { (#start..=#end).contains(&#expr) }
+            } else {
+                quote! { #expr == #start }
+            });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        let any = quote! { ( #( #items )||* ) };
+        Ok(if negated { quote! { !#any } } else { any })
+    }
+
+    fn value(&mut self) -> Result<u64, String> {
+        let token = self.next("number")?;
+        token.parse().map_err(|_| format!("expected number in plural rule, found `{}`", token))
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::fingerprint::fingerprint;
+use crate::toml_parser::Document;
+
+/// Quote `text` as PO string, splitting it into lines after every `\n`
+fn po_string(text: &str) -> String {
+    let escape = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t")
+            .replace('\r', "\\r")
+    };
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        return format!("\"{}\"", escape(text));
+    }
+    let mut result = "\"\"".to_string();
+    for line in lines {
+        write!(result, "\n\"{}\"", escape(line)).unwrap();
+    }
+    result
+}
+
+/// Gettext template with an entry per string and list item of `document`. Keys are
+/// used as `msgctxt`, so entries with the same text stay separate. With `fingerprints`
+/// every entry has an extracted comment with fingerprint of its text.
+pub(crate) fn pot(document: &Document, source: &Path, fingerprints: bool) -> String {
+    // Sorted by key, then by index, so `key[10]` comes after `key[9]`
+    let mut entries = Vec::new();
+    for (key, value) in &document.strings {
+        entries.push((key, None, value));
+    }
+    for (key, items) in &document.lists {
+        entries.extend(items.iter().enumerate().map(|(idx, item)| (key, Some(idx), item)));
+    }
+    entries.sort();
+
+    // Package of build script calling ctl10n, if any
+    let project = match (env::var("CARGO_PKG_NAME"), env::var("CARGO_PKG_VERSION")) {
+        (Ok(name), Ok(version)) => format!("{} {}", name, version),
+        _ => "PACKAGE VERSION".to_string(),
+    };
+    let mut pot = String::new();
+    pot.push_str("msgid \"\"\nmsgstr \"\"\n");
+    writeln!(pot, "\"Project-Id-Version: {}\\n\"", project).unwrap();
+    pot.push_str("\"MIME-Version: 1.0\\n\"\n");
+    pot.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    pot.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    for (key, idx, text) in entries {
+        let context = match idx {
+            Some(idx) => format!("{}[{}]", key, idx),
+            None => key.clone(),
+        };
+        pot.push('\n');
+        if let Some(predicate) = document.cfgs.get(key) {
+            writeln!(pot, "#. cfg: {}", predicate).unwrap();
+        }
+        if fingerprints {
+            writeln!(pot, "#. fingerprint: {}", fingerprint(text)).unwrap();
+        }
+        if let Some((line, _)) = document.positions.get(key) {
+            writeln!(pot, "#: {}:{}", source.display(), line).unwrap();
+        }
+        writeln!(pot, "msgctxt {}", po_string(&context)).unwrap();
+        writeln!(pot, "msgid {}", po_string(text)).unwrap();
+        pot.push_str("msgstr \"\"\n");
+    }
+    pot
+}
+
+/// Entry of a PO file
+#[derive(Debug, Default, PartialEq)]
+struct PoEntry {
+    /// 1-based line of the first keyword of the entry
+    line: usize,
+    msgctxt: Option<String>,
+    msgid: String,
+    msgstr: String,
+    /// Marked with `#, fuzzy`, so the translation needs review
+    fuzzy: bool,
+}
+
+/// Field of [`PoEntry`] continuation lines are appended to
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    Translation,
+}
+
+/// Text of PO string `quoted`, with escape sequences written by [`po_string`] decoded
+fn unquote(quoted: &str) -> Result<String> {
+    let inner = quoted
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+        .ok_or_else(|| Error::InvalidPo(format!("`{}` isn't a quoted string", quoted)))?;
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(c @ ('"' | '\\')) => text.push(c),
+            Some(c) => return Err(Error::InvalidPo(format!("unknown escape sequence `\\{}`", c))),
+            None => return Err(Error::InvalidPo(format!("`{}` isn't a quoted string", quoted))),
+        }
+    }
+    Ok(text)
+}
+
+/// Entries of PO file `input` read from `path`, including the header. Comments other
+/// than flags are ignored, plural entries aren't supported.
+fn parse_po(input: &str, path: &Path) -> Result<Vec<PoEntry>> {
+    let mut entries = Vec::new();
+    let mut entry = PoEntry::default();
+    let mut field = None;
+    // Flags come before the entry they're about
+    let mut fuzzy = false;
+    for (idx, line) in input.lines().enumerate() {
+        let at_line = |error: Error| error.at_line(path, idx + 1);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            if let Some(flags) = line.strip_prefix("#,") {
+                fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            continue;
+        }
+        let (keyword, quoted) = match line.find(' ') {
+            Some(space) if !line.starts_with('"') => (&line[..space], line[space..].trim_start()),
+            _ => ("", line),
+        };
+        let text = unquote(quoted).map_err(at_line)?;
+        let next = match keyword {
+            "" => {
+                let field = field.ok_or_else(|| at_line(Error::InvalidPo("string without a keyword".to_string())))?;
+                let value = match field {
+                    Field::Context => entry.msgctxt.get_or_insert_with(String::new),
+                    Field::Id => &mut entry.msgid,
+                    Field::Translation => &mut entry.msgstr,
+                };
+                value.push_str(&text);
+                continue;
+            }
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgstr" => Field::Translation,
+            "msgid_plural" => return Err(at_line(Error::InvalidPo("plural entries aren't supported".to_string()))),
+            keyword => return Err(at_line(Error::InvalidPo(format!("unknown keyword `{}`", keyword)))),
+        };
+        // `msgctxt` or `msgid` after `msgstr` starts the next entry
+        if matches!((field, next), (Some(Field::Translation), Field::Context | Field::Id)) {
+            entries.push(std::mem::take(&mut entry));
+            field = None;
+        }
+        if field.is_none() {
+            entry.line = idx + 1;
+            entry.fuzzy = std::mem::take(&mut fuzzy);
+        }
+        match next {
+            Field::Context => entry.msgctxt = Some(text),
+            Field::Id => entry.msgid = text,
+            Field::Translation => entry.msgstr = text,
+        }
+        field = Some(next);
+    }
+    if field.is_some() {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Strings file with translations of PO file `input` read from `path`, which was
+/// translated from a template exported with [`pot`] from `template`. Entries which
+/// aren't translated or are fuzzy are skipped, lists are only written if every
+/// item is translated.
+pub(crate) fn import_po(input: &str, path: &Path, template: &Document) -> Result<String> {
+    let mut strings = toml::value::Table::new();
+    let mut lists: BTreeMap<&str, BTreeMap<usize, String>> = BTreeMap::new();
+    for mut entry in parse_po(input, path)? {
+        let context = match entry.msgctxt.take() {
+            Some(context) => context,
+            // The header
+            None if entry.msgid.is_empty() => continue,
+            None => {
+                let error = Error::InvalidPo(format!("entry `{}` has no msgctxt with its key", entry.msgid));
+                return Err(error.at_line(path, entry.line));
+            }
+        };
+        let unknown = || Error::InvalidPo(format!("key `{}` isn't in the template", context)).at_line(path, entry.line);
+        // List items are `key[idx]`
+        let item = context.strip_suffix(']').and_then(|context| {
+            let (key, idx) = context.rsplit_once('[')?;
+            Some((key, idx.parse::<usize>().ok()?))
+        });
+        if let Some((key, idx)) = item {
+            let (key, items) = template.lists.get_key_value(key).ok_or_else(unknown)?;
+            if idx >= items.len() {
+                return Err(unknown());
+            }
+            if !entry.fuzzy && !entry.msgstr.is_empty() {
+                lists.entry(key).or_default().insert(idx, entry.msgstr);
+            }
+            continue;
+        }
+        if !template.strings.contains_key(&context) {
+            return Err(unknown());
+        }
+        if !entry.fuzzy && !entry.msgstr.is_empty() {
+            strings.insert(context, toml::Value::String(entry.msgstr));
+        }
+    }
+    for (key, items) in lists {
+        if items.len() == template.lists[key].len() {
+            let items = items.into_values().map(toml::Value::String).collect();
+            strings.insert(key.to_string(), toml::Value::Array(items));
+        }
+    }
+    Ok(toml::to_string(&toml::Value::Table(strings)).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml_parser::{parse_toml, ParseOptions};
+
+    fn document(toml: &str) -> Document {
+        parse_toml(toml, &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn list_items_in_order() {
+        let items: Vec<String> = (0..11).map(|idx| format!("\"Tip {}\"", idx)).collect();
+        let pot = pot(&document(&format!("tips = [{}]", items.join(", "))), Path::new("en.toml"), false);
+        let contexts: Vec<&str> = pot.lines().filter_map(|line| line.strip_prefix("msgctxt ")).collect();
+        assert_eq!(contexts[2], "\"tips[2]\"");
+        assert_eq!(contexts[10], "\"tips[10]\"");
+    }
+
+    #[test]
+    fn import() {
+        let template = document(
+            r#"
+            greeting = "Hello, {name}!"
+            about = "Line\nnext"
+            bye = "Bye"
+            tips = ["First", "Second"]
+            "#,
+        );
+        let po = r#"
+msgid ""
+msgstr ""
+"Language: de\n"
+
+msgctxt "about"
+msgid ""
+"Line\n"
+"next"
+msgstr ""
+"Zeile\n"
+"nächste"
+
+#, fuzzy
+msgctxt "bye"
+msgid "Bye"
+msgstr "Tschüss"
+msgctxt "greeting"
+msgid "Hello, {name}!"
+msgstr "Hallo, \"{name}\"!"
+
+msgctxt "tips[0]"
+msgid "First"
+msgstr "Erster"
+msgctxt "tips[1]"
+msgid "Second"
+msgstr "Zweiter"
+"#;
+        let imported = document(&import_po(po, Path::new("de.po"), &template).unwrap());
+        assert_eq!(imported.strings["greeting"], "Hallo, \"{name}\"!");
+        assert_eq!(imported.strings["about"], "Zeile\nnächste");
+        assert!(!imported.strings.contains_key("bye"));
+        assert_eq!(imported.lists["tips"], ["Erster", "Zweiter"]);
+    }
+
+    #[test]
+    fn import_errors() {
+        let template = document(r#"greeting = "Hello""#);
+        let import = |po: &str| import_po(po, Path::new("de.po"), &template).unwrap_err().to_string();
+        assert_eq!(
+            import("msgctxt \"bye\"\nmsgid \"Bye\"\nmsgstr \"\""),
+            "de.po:1: Invalid PO file: key `bye` isn't in the template",
+        );
+        assert_eq!(import("\nmsgid \"Hello\"\nmsgstr \"Hallo\""), "de.po:2: Invalid PO file: entry `Hello` has no msgctxt with its key");
+        assert_eq!(import("msgid \"Hello"), "de.po:1: Invalid PO file: `\"Hello` isn't a quoted string");
+        assert_eq!(import("msgstr[0] \"\""), "de.po:1: Invalid PO file: unknown keyword `msgstr[0]`");
+    }
+}
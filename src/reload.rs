@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::escapes::Escapes;
+use crate::toml_parser::{decode, parse_toml, ParseOptions};
+use crate::whitespace::Whitespace;
+
+/// Transformations [`reload_string`] applies to reloaded strings, written by
+/// [`Builder::hot_reload`](crate::Builder::hot_reload) according to options of the builder,
+/// so debug builds show the same strings as release ones
+#[derive(Debug, Clone, Copy)]
+pub struct ReloadOptions {
+    lenient_values: bool,
+    join_arrays: Option<&'static str>,
+    whitespace: Whitespace,
+    escapes: Escapes,
+    #[cfg(feature = "normalize")]
+    normalization: Option<crate::NormalizationForm>,
+    show_keys: bool,
+    overridden: &'static [&'static str],
+}
+
+impl ReloadOptions {
+    /// Options of [`Builder::new`](crate::Builder::new)
+    pub const fn new() -> Self {
+        Self {
+            lenient_values: false,
+            join_arrays: None,
+            whitespace: Whitespace::Keep,
+            escapes: Escapes::Keep,
+            #[cfg(feature = "normalize")]
+            normalization: None,
+            show_keys: false,
+            overridden: &[],
+        }
+    }
+
+    /// See [`Builder::lenient_values`](crate::Builder::lenient_values)
+    pub const fn lenient_values(mut self, lenient: bool) -> Self {
+        self.lenient_values = lenient;
+        self
+    }
+
+    /// See [`Builder::join_arrays`](crate::Builder::join_arrays)
+    pub const fn join_arrays(mut self, separator: &'static str) -> Self {
+        self.join_arrays = Some(separator);
+        self
+    }
+
+    /// See [`Builder::whitespace`](crate::Builder::whitespace)
+    pub const fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// See [`Builder::escapes`](crate::Builder::escapes)
+    pub const fn escapes(mut self, escapes: Escapes) -> Self {
+        self.escapes = escapes;
+        self
+    }
+
+    /// See [`Builder::normalize_unicode`](crate::Builder::normalize_unicode)
+    #[cfg(feature = "normalize")]
+    pub const fn normalize_unicode(mut self, form: crate::NormalizationForm) -> Self {
+        self.normalization = Some(form);
+        self
+    }
+
+    /// See [`Builder::show_keys`](crate::Builder::show_keys)
+    pub const fn show_keys(mut self, show_keys: bool) -> Self {
+        self.show_keys = show_keys;
+        self
+    }
+
+    /// Keys overridden by environment variables during build, which keep compiled strings,
+    /// see [`Builder::env_overrides`](crate::Builder::env_overrides)
+    pub const fn overridden(mut self, keys: &'static [&'static str]) -> Self {
+        self.overridden = keys;
+        self
+    }
+}
+
+impl Default for ReloadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Strings of a file read by [`reload_string`]
+struct LoadedFile {
+    modified: SystemTime,
+    strings: BTreeMap<String, &'static str>,
+}
+
+static FILES: Mutex<BTreeMap<String, LoadedFile>> = Mutex::new(BTreeMap::new());
+
+/// String for `key` in strings file at `path`, read again whenever the file is modified,
+/// or `embedded` if the file or key can't be read. It's called by code generated with
+/// [`Builder::hot_reload`](crate::Builder::hot_reload) in debug builds.
+///
+/// Values are transformed according to `options` as during generation. Only the file
+/// itself is read, so keys from its includes and overrides file, keys filled from the
+/// template with [`Builder::fill_missing`](crate::Builder::fill_missing) and keys
+/// overridden by environment variables keep compiled strings. Changed values are leaked,
+/// which is fine while tweaking strings during development.
+pub fn reload_string(path: &str, key: &str, embedded: &'static str, options: &ReloadOptions) -> &'static str {
+    let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return embedded,
+    };
+    let mut files = FILES.lock().unwrap_or_else(|err| err.into_inner());
+    let outdated = files.get(path).is_none_or(|file| file.modified != modified);
+    if outdated {
+        let mut strings = files.remove(path).map(|file| file.strings).unwrap_or_default();
+        if let Some(document) = read(Path::new(path), options) {
+            strings = document
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match strings.get(&key) {
+                        Some(&previous) if previous == value => previous,
+                        _ => &*Box::leak(value.into_boxed_str()),
+                    };
+                    (key, value)
+                })
+                .collect();
+        }
+        files.insert(path.to_string(), LoadedFile { modified, strings });
+    }
+    files[path].strings.get(key).copied().unwrap_or(embedded)
+}
+
+/// Strings of file at `path` transformed according to `options`, if it can be read and parsed
+fn read(path: &Path, options: &ReloadOptions) -> Option<BTreeMap<String, String>> {
+    let input = fs::read(path).ok().and_then(|bytes| decode(bytes).ok())?;
+    let parse_options = ParseOptions {
+        lenient_values: options.lenient_values,
+        join_arrays: options.join_arrays.map(str::to_string),
+        whitespace: options.whitespace,
+        escapes: options.escapes,
+    };
+    let document = match path.extension() {
+        Some(extension) if extension == "json" => crate::json::parse_json(&input, &parse_options),
+        _ => parse_toml(&input, &parse_options),
+    };
+    let mut document = document.ok()?;
+    for key in options.overridden {
+        document.strings.remove(*key);
+    }
+    #[cfg(feature = "normalize")]
+    if let Some(form) = options.normalization {
+        crate::normalize::normalize(&mut document, form);
+    }
+    if options.show_keys {
+        crate::builder::show_keys(&mut document);
+    }
+    Some(document.strings)
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::error::{Error, Result};
+use crate::lint::{Lint, Severity};
+
+/// Problems and statistics collected while generating code from a strings file
+///
+/// ```no_run
+/// let report = ctl10n::Builder::new()
+///     .print_warnings(false)
+///     .convert_default_strings_file()
+///     .expect("ctl10n failed");
+/// for key in &report.filled_keys {
+///     eprintln!("untranslated: {}", key);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenerationReport {
+    /// All warnings, as printed with `cargo:warning`
+    pub warnings: Vec<String>,
+    /// Informational messages like deduplication statistics. Unlike warnings they aren't
+    /// printed, since `cargo:warning` would show them on every build.
+    pub notes: Vec<String>,
+    /// Lint findings reported as warnings
+    pub lint_findings: Vec<LintFinding>,
+    /// Keys failing [`Lint::KeyStyle`] with names following the style, as `(key, suggestion)`
+    pub key_renames: Vec<(String, String)>,
+    /// Keys filled from the source locale by [`Builder::fill_missing`](crate::Builder::fill_missing)
+    pub filled_keys: Vec<String>,
+    /// Filled keys of every locale converted by [`Builder::convert_all`](crate::Builder::convert_all),
+    /// by locale, since [`filled_keys`](Self::filled_keys) of all locales are combined
+    pub filled_keys_by_locale: BTreeMap<String, Vec<String>>,
+    /// Keys overridden by [`Builder::env_overrides`](crate::Builder::env_overrides)
+    pub overridden_keys: Vec<String>,
+    /// Number of distinct values shared between multiple keys, which are stored once
+    pub shared_values: usize,
+    /// Bytes of string data saved thanks to deduplication of shared values
+    pub saved_bytes: usize,
+    /// Arms of the largest generated `tr!()` macro, see [`Builder::macro_budget`](crate::Builder::macro_budget)
+    pub macro_arms: usize,
+    /// Bytes of string literals in the largest generated `tr!()` macro
+    pub literal_bytes: usize,
+}
+
+/// Value which failed a lint with [`Severity::Warn`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// Key of the value
+    pub key: String,
+    /// Lint which the value failed
+    pub lint: Lint,
+    /// What's wrong with the value, e.g. `double space at byte 5`
+    pub detail: String,
+}
+
+impl GenerationReport {
+    pub(crate) fn warn(&mut self, message: impl Display) {
+        self.warnings.push(message.to_string());
+    }
+
+    pub(crate) fn note(&mut self, message: impl Display) {
+        self.notes.push(message.to_string());
+    }
+
+    /// Record `error` as warning or return it according to `severity`
+    pub(crate) fn report(&mut self, error: Error, severity: Severity) -> Result<()> {
+        match severity {
+            Severity::Allow => Ok(()),
+            Severity::Warn => {
+                let inner = match &error {
+                    Error::InFile { error, .. } => error.as_ref(),
+                    error => error,
+                };
+                if let Error::Lint { key, lint, detail } = inner {
+                    self.lint_findings.push(LintFinding {
+                        key: key.clone(),
+                        lint: *lint,
+                        detail: detail.clone(),
+                    });
+                }
+                self.warn(error);
+                Ok(())
+            }
+            Severity::Deny => Err(error),
+        }
+    }
+
+    /// Add messages and statistics of `other`
+    pub(crate) fn extend(&mut self, other: GenerationReport) {
+        self.warnings.extend(other.warnings);
+        self.notes.extend(other.notes);
+        self.lint_findings.extend(other.lint_findings);
+        self.key_renames.extend(other.key_renames);
+        self.filled_keys.extend(other.filled_keys);
+        for (locale, keys) in other.filled_keys_by_locale {
+            self.filled_keys_by_locale.entry(locale).or_default().extend(keys);
+        }
+        self.overridden_keys.extend(other.overridden_keys);
+        self.shared_values += other.shared_values;
+        self.saved_bytes += other.saved_bytes;
+        self.macro_arms = self.macro_arms.max(other.macro_arms);
+        self.literal_bytes = self.literal_bytes.max(other.literal_bytes);
+    }
+
+    /// Print warnings for cargo
+    pub(crate) fn print(&self) {
+        for warning in &self.warnings {
+            println!("cargo:warning=ctl10n: {}", warning);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt::{Display, Write};
+
+/// Substitute named arguments into a message loaded at runtime.
+///
+/// This is used by the code generated with the binary backend, where messages
+/// aren't literals and can't be passed to `format!()`. Supports `{name}`
+/// placeholders and `{{`/`}}` escapes; format specs after `:` are ignored.
+/// Placeholders without a matching argument are left as is.
+///
+/// ```
+/// let name = "world";
+/// assert_eq!(
+///     ctl10n::format_named("Hello, {name}! {{}}", &[("name", &name)]),
+///     "Hello, world! {}",
+/// );
+/// ```
+pub fn format_named(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(idx) = rest.find(['{', '}']) {
+        result.push_str(&rest[..idx]);
+        let tail = &rest[idx..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            result.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        match (tail.starts_with('{'), tail.find('}')) {
+            (true, Some(end)) => {
+                let placeholder = &tail[1..end];
+                let name = placeholder.split(':').next().unwrap_or_default();
+                match args.iter().find(|(arg, _)| *arg == name) {
+                    Some((_, value)) => {
+                        let _infallible = write!(result, "{}", value);
+                    }
+                    None => result.push_str(&tail[..=end]),
+                }
+                rest = &tail[end + 1..];
+            }
+            _ => {
+                result.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,199 @@
+use serde_json::{json, Map, Value};
+
+use crate::toml_parser::Document;
+
+/// Allow value described by `property` to be written as `{ value = ..., cfg = "..." }`.
+/// Strings may also set `defaults` of placeholders and declare `args`.
+fn with_cfg(property: Value) -> Value {
+    let description = property["description"].clone();
+    json!({
+        "anyOf": [
+            property,
+            {
+                "type": "object",
+                "properties": { "value": property, "cfg": { "type": "string" } },
+                "required": ["value"],
+                "additionalProperties": false,
+            },
+        ],
+        "description": description,
+    })
+}
+
+/// JSON Schema allowing exactly the keys of `template`, for editor validation
+/// of locale files (e.g. with Taplo / Even Better TOML). With `joined_arrays`
+/// strings may be written as arrays of lines. Limits from `[limits]` of template
+/// become `maxLength` of string values.
+pub(crate) fn schema(template: &Document, title: &str, joined_arrays: bool) -> Value {
+    let mut properties = Map::new();
+    for (key, value) in &template.strings {
+        let mut property = if joined_arrays {
+            json!({
+                "anyOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } },
+                ],
+                "description": value,
+            })
+        } else {
+            json!({ "type": "string", "description": value })
+        };
+        if let (Some(limit), false) = (template.limits.get(key), joined_arrays) {
+            property["maxLength"] = json!(limit);
+        }
+        let mut property = with_cfg(property);
+        property["anyOf"][1]["properties"]["defaults"] = json!({
+            "type": "object",
+            "additionalProperties": { "type": "string" },
+        });
+        property["anyOf"][1]["properties"]["args"] = json!({ "type": "array", "items": { "type": "string" } });
+        properties.insert(key.clone(), property);
+    }
+    for (key, items) in &template.lists {
+        properties.insert(
+            key.clone(),
+            with_cfg(json!({
+                "type": "array",
+                "items": { "type": "string" },
+                "description": items.join("\n"),
+            })),
+        );
+    }
+    let required: Vec<String> = properties.keys().cloned().collect();
+    properties.insert(
+        "limits".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": { "type": "integer", "minimum": 0 },
+        }),
+    );
+    properties.insert(
+        "include".to_string(),
+        json!({ "type": "array", "items": { "type": "string" } }),
+    );
+    properties.insert(
+        "html".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": { "type": "boolean" },
+        }),
+    );
+    properties.insert(
+        "number".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "decimal": { "type": "string" }, "group": { "type": "string" } },
+            "additionalProperties": false,
+        }),
+    );
+    properties.insert(
+        "join".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "separator": { "type": "string" },
+                "last": { "type": "string" },
+                "pair": { "type": "string" },
+            },
+            "additionalProperties": false,
+        }),
+    );
+    properties.insert(
+        "truncate".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "ellipsis": { "type": "string" } },
+            "additionalProperties": false,
+        }),
+    );
+    let messages = json!({
+        "type": "object",
+        "additionalProperties": {
+            "type": "object",
+            "properties": {
+                "zero": { "type": "string" },
+                "one": { "type": "string" },
+                "two": { "type": "string" },
+                "few": { "type": "string" },
+                "many": { "type": "string" },
+                "other": { "type": "string" },
+            },
+            "required": ["other"],
+            "additionalProperties": false,
+        },
+    });
+    properties.insert("cardinal".to_string(), messages.clone());
+    properties.insert("ordinal".to_string(), messages);
+    let rules = json!({
+        "type": "object",
+        "properties": {
+            "zero": { "type": "string" },
+            "one": { "type": "string" },
+            "two": { "type": "string" },
+            "few": { "type": "string" },
+            "many": { "type": "string" },
+        },
+        "additionalProperties": false,
+    });
+    properties.insert(
+        "plural".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "cardinal": rules, "ordinal": rules },
+            "additionalProperties": false,
+        }),
+    );
+    properties.insert(
+        "fingerprints".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": { "type": "string" },
+        }),
+    );
+    properties.insert(
+        "target".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+            },
+        }),
+    );
+    properties.insert(
+        "formats".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": { "type": "string" },
+        }),
+    );
+    properties.insert(
+        "whitespace".to_string(),
+        json!({
+            "type": "object",
+            "additionalProperties": { "enum": ["keep", "dedent", "trim", "collapse"] },
+        }),
+    );
+    properties.insert(
+        "currency".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": { "type": "string" },
+                "position": { "enum": ["before", "after"] },
+                "space": { "type": "boolean" },
+                "digits": { "type": "integer", "minimum": 0 },
+            },
+            "required": ["symbol"],
+            "additionalProperties": false,
+        }),
+    );
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": title,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
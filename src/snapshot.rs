@@ -0,0 +1,24 @@
+use std::fmt::{Display, Write};
+
+use crate::runtime::format_named;
+use crate::toml_parser::Document;
+
+/// Append `key = text` line to `snapshot`, indenting continuation lines of `text`
+fn line(snapshot: &mut String, key: &str, text: &str) {
+    writeln!(snapshot, "{} = {}", key, text.replace('\n', "\n    ")).unwrap();
+}
+
+/// Text of every string of `document` rendered with sample `args`, one per line in
+/// order of definition, followed by items of array values as `key[idx]`
+pub(crate) fn snapshot(locale: &str, document: &Document, args: &[(&str, &dyn Display)]) -> String {
+    let mut snapshot = format!("# Strings of {}\n", locale);
+    for (key, value) in document.in_order(&document.strings) {
+        line(&mut snapshot, key, &format_named(value, args));
+    }
+    for (key, items) in document.in_order(&document.lists) {
+        for (idx, item) in items.iter().enumerate() {
+            line(&mut snapshot, &format!("{}[{}]", key, idx), item);
+        }
+    }
+    snapshot
+}
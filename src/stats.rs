@@ -0,0 +1,62 @@
+use crate::fingerprint::{fingerprint, list_fingerprint};
+use crate::toml_parser::Document;
+
+/// Translation completeness of a locale, returned by [`Builder::stats`](crate::Builder::stats)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleStats {
+    /// Name of the locale, e.g. `de`
+    pub locale: String,
+    /// Number of keys in the source locale
+    pub total: usize,
+    /// Keys of the source locale defined in this locale
+    pub translated: usize,
+    /// Keys of the source locale not defined in this locale
+    pub missing: usize,
+    /// Missing keys which are filled from the source locale with
+    /// [`Builder::fill_missing`](crate::Builder::fill_missing)
+    pub filled: usize,
+    /// Translated keys whose fingerprint in `[fingerprints]` table doesn't match the source
+    /// text anymore, i.e. the source changed since they were translated
+    pub stale: usize,
+    /// Percentage of keys of the source locale translated and not stale
+    pub completeness: f64,
+}
+
+/// Fingerprint of value of `key`, if it's defined
+fn value_fingerprint(document: &Document, key: &str) -> Option<String> {
+    match (document.strings.get(key), document.lists.get(key)) {
+        (Some(value), _) => Some(fingerprint(value)),
+        (None, Some(items)) => Some(list_fingerprint(items)),
+        (None, None) => None,
+    }
+}
+
+/// Compare `document` of `locale` to `source` document
+pub(crate) fn locale_stats(locale: &str, document: &Document, source: &Document, fill_missing: bool) -> LocaleStats {
+    let keys: Vec<&String> = source.strings.keys().chain(source.lists.keys()).collect();
+    let defined = |key: &str| document.strings.contains_key(key) || document.lists.contains_key(key);
+    let translated = keys.iter().filter(|key| defined(key)).count();
+    let stale = keys
+        .iter()
+        .filter(|key| defined(key))
+        .filter(|key| match document.fingerprints.get(key.as_str()) {
+            Some(recorded) => value_fingerprint(source, key).as_ref() != Some(recorded),
+            None => false,
+        })
+        .count();
+    let missing = keys.len() - translated;
+    let completeness = if keys.is_empty() {
+        100.0
+    } else {
+        (translated - stale) as f64 * 100.0 / keys.len() as f64
+    };
+    LocaleStats {
+        locale: locale.to_string(),
+        total: keys.len(),
+        translated,
+        missing,
+        filled: if fill_missing { missing } else { 0 },
+        stale,
+        completeness,
+    }
+}
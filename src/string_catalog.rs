@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+/// Strings of a catalog as owned key/value pairs, returned by the generated `string_catalog()`.
+///
+/// It's meant for shipping strings to clients or dumping them for debugging, `tr!()` is
+/// still the way to use them in Rust. It's serialized as a map of keys to values.
+///
+/// ```
+/// let catalog: ctl10n::StringCatalog = vec![("greeting", "Hello!")].into_iter().collect();
+/// assert_eq!(catalog.get("greeting"), Some("Hello!"));
+/// assert_eq!(catalog.len(), 1);
+/// assert_eq!(serde_json::to_string(&catalog).unwrap(), r#"{"greeting":"Hello!"}"#);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringCatalog {
+    strings: BTreeMap<String, String>,
+}
+
+impl StringCatalog {
+    /// Empty catalog
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value of `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    /// Set value of `key`, returning the previous one
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.strings.insert(key.into(), value.into())
+    }
+
+    /// Iterate over key/value pairs in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.strings.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Number of strings
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether the catalog has no strings
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for StringCatalog {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let strings = iter.into_iter().map(|(key, value)| (key.into(), value.into())).collect();
+        Self { strings }
+    }
+}
+
+impl serde::Serialize for StringCatalog {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.strings.serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for StringCatalog {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BTreeMap::deserialize(deserializer).map(|strings| Self { strings })
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::toml_parser::Document;
+
+/// Canonical locale, which strings are written in and other locales are translated from.
+/// Every check of translations is defined against it: keys missing from a locale are
+/// [filled](crate::Builder::fill_missing) from it, translations must use its placeholders
+/// and [arguments](crate::Builder::check_locale), and the [lockfile](crate::Builder::lockfile),
+/// [schema](crate::Builder::emit_schema) and [gettext template](crate::Builder::export_pot)
+/// list its keys.
+///
+/// [`Builder::convert_locales`](crate::Builder::convert_locales) uses the template set with
+/// [`Builder::template`](crate::Builder::template), or the locale named by
+/// [`Builder::source_locale`](crate::Builder::source_locale) or the default locale of
+/// [`Builder::runtime_locales`](crate::Builder::runtime_locales).
+///
+/// ```no_run
+/// use ctl10n::Builder;
+///
+/// let builder = Builder::new().fill_missing(true);
+/// let template = builder.load_template("locales/en.toml").unwrap();
+/// assert_eq!(template.locale(), "en");
+/// builder.template(template).convert_locales("locales").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Template {
+    locale: String,
+    path: PathBuf,
+    document: Document,
+}
+
+impl Template {
+    pub(crate) fn new(locale: impl Into<String>, path: impl Into<PathBuf>, document: Document) -> Self {
+        Self {
+            locale: locale.into(),
+            path: path.into(),
+            document,
+        }
+    }
+
+    /// Name of the locale, e.g. `en`
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Strings file the template was read from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Keys of strings and arrays in order of definition
+    pub fn keys(&self) -> Vec<&str> {
+        self.document.order.iter().map(String::as_str).collect()
+    }
+
+    /// Whether the template was read from `path`, which may be spelled differently,
+    /// like `./locales/en.toml` and `locales/en.toml`
+    pub(crate) fn is_read_from(&self, path: &Path) -> bool {
+        match (fs::canonicalize(&self.path), fs::canonicalize(path)) {
+            (Ok(template), Ok(path)) => template == path,
+            _ => self.path == path,
+        }
+    }
+
+    pub(crate) fn document(&self) -> &Document {
+        &self.document
+    }
+}
@@ -1,21 +1,1165 @@
-use std::collections::HashMap;
-use crate::error::{Result, Error::TOMLStructureError};
-
-pub fn parse_toml(toml: &str) -> Result<HashMap<String, String>> {
-    let toml_value = toml.parse::<toml::Value>()?;
-
-    if let toml::Value::Table(table) = toml_value {
-        table
-            .into_iter()
-            .map(|(key, value)| {
-                if let toml::Value::String(string) = value {
-                    Ok((key, string))
-                } else {
-                    Err(TOMLStructureError)
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::currency::SymbolPosition;
+use crate::error::{Error, Result};
+use crate::escapes::Escapes;
+use crate::placeholder::{is_identifier, parse_placeholders, with_specs, Placeholder};
+use crate::plural::{PluralCategory, PluralKind};
+use crate::whitespace::Whitespace;
+
+/// Parsed strings. Sorted by key so generated code doesn't depend on hash order,
+/// [`Document::in_order`] gives them in order of definition.
+pub type Strings = BTreeMap<String, String>;
+
+/// Parsed array values
+pub type Lists = BTreeMap<String, Vec<String>>;
+
+/// Maximum lengths of values in characters
+pub type Limits = BTreeMap<String, usize>;
+
+/// Messages of `[cardinal]` or `[ordinal]` table by key and plural category
+pub type PluralMessages = BTreeMap<String, BTreeMap<PluralCategory, String>>;
+
+/// Values of `[target.'cfg(...)']` tables by key and `cfg` predicate
+pub type Targets = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Contents of `[currency]` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Currency {
+    pub symbol: String,
+    pub position: SymbolPosition,
+    /// Whether the symbol is separated from the amount by a no-break space
+    pub space: bool,
+    /// Number of fractional digits
+    pub digits: usize,
+}
+
+/// Parsed strings file
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    pub strings: Strings,
+    pub lists: Lists,
+    /// Keys of strings and lists in order of definition
+    pub order: Vec<String>,
+    /// 1-based line and column of every key
+    pub positions: BTreeMap<String, (usize, usize)>,
+    /// Files defining keys which come from included or overrides files, which their
+    /// positions refer to
+    pub files: BTreeMap<String, PathBuf>,
+    /// Contents of `[limits]` table
+    pub limits: Limits,
+    /// Keys set to `true` in `[html]` table
+    pub html: BTreeSet<String>,
+    /// Files listed in `include` array, relative to this one
+    pub includes: Vec<String>,
+    /// `cfg` predicates of keys defined as `key = { value = "...", cfg = "..." }`
+    pub cfgs: BTreeMap<String, String>,
+    /// Values of placeholders used by `tr!()` without arguments, for keys defined as
+    /// `key = { value = "...", defaults = { name = "..." } }`
+    pub defaults: BTreeMap<String, BTreeMap<String, String>>,
+    /// Arguments of keys defined as `key = { value = "...", args = [...] }`, which must be
+    /// exactly their placeholders
+    pub args: BTreeMap<String, Vec<String>>,
+    /// Separators set in `[number]` table, by name (`decimal` or `group`)
+    pub number: BTreeMap<String, String>,
+    /// Separators set in `[join]` table, by name (`separator`, `last` or `pair`)
+    pub join: BTreeMap<String, String>,
+    /// Ellipsis set in `[truncate]` table, by name (`ellipsis`)
+    pub truncate: BTreeMap<String, String>,
+    /// Contents of `[cardinal]` table, available via `trn!()`
+    pub cardinals: PluralMessages,
+    /// Contents of `[ordinal]` table, available via `trn_ord!()`
+    pub ordinals: PluralMessages,
+    /// Conditions of cardinal plural categories from `[plural]` table, replacing
+    /// built-in rules of the locale
+    pub cardinal_rules: BTreeMap<PluralCategory, String>,
+    /// Conditions of ordinal plural categories from `[plural]` table, replacing
+    /// built-in rules of the locale
+    pub ordinal_rules: BTreeMap<PluralCategory, String>,
+    /// Fingerprints of source texts keys were translated from, from `[fingerprints]` table
+    pub fingerprints: BTreeMap<String, String>,
+    /// Contents of `[formats]` table, emitted as constants instead of keys
+    pub formats: BTreeMap<String, String>,
+    /// Values replacing base values of keys on targets matching `cfg` predicate
+    pub targets: Targets,
+    /// Contents of `[currency]` table, available via `tr_money!()`
+    pub currency: Option<Currency>,
+    /// Contents of `[whitespace]` table, already applied to values of this file
+    pub whitespace: BTreeMap<String, Whitespace>,
+}
+
+impl Document {
+    /// Add keys and metadata of `other`, replacing existing ones. Returns keys
+    /// which were defined in both.
+    pub fn merge(&mut self, other: Document) -> Vec<String> {
+        let mut duplicates = Vec::new();
+        for key in other.strings.keys().chain(other.lists.keys()) {
+            if self.strings.remove(key).is_some() || self.lists.remove(key).is_some() {
+                duplicates.push(key.clone());
+            }
+            self.files.remove(key);
+        }
+        self.strings.extend(other.strings);
+        self.lists.extend(other.lists);
+        // Replaced keys keep their place
+        let known: BTreeSet<&String> = self.order.iter().collect();
+        let new: Vec<String> = other.order.into_iter().filter(|key| !known.contains(key)).collect();
+        self.order.extend(new);
+        self.positions.extend(other.positions);
+        self.files.extend(other.files);
+        self.limits.extend(other.limits);
+        self.html.extend(other.html);
+        for key in &duplicates {
+            self.cfgs.remove(key);
+            self.defaults.remove(key);
+            self.args.remove(key);
+            self.targets.remove(key);
+        }
+        self.cfgs.extend(other.cfgs);
+        self.defaults.extend(other.defaults);
+        self.args.extend(other.args);
+        for (key, overrides) in other.targets {
+            self.targets.entry(key).or_default().extend(overrides);
+        }
+        self.number.extend(other.number);
+        self.join.extend(other.join);
+        self.truncate.extend(other.truncate);
+        self.cardinals.extend(other.cardinals);
+        self.ordinals.extend(other.ordinals);
+        if !other.cardinal_rules.is_empty() {
+            self.cardinal_rules = other.cardinal_rules;
+        }
+        if !other.ordinal_rules.is_empty() {
+            self.ordinal_rules = other.ordinal_rules;
+        }
+        self.fingerprints.extend(other.fingerprints);
+        self.formats.extend(other.formats);
+        if other.currency.is_some() {
+            self.currency = other.currency;
+        }
+        duplicates
+    }
+
+    /// File defining `key` and its 1-based line, if it's known. `path` is the file this
+    /// document was read from.
+    pub fn location<'a>(&'a self, key: &str, path: &'a Path) -> (&'a Path, Option<usize>) {
+        let file = self.files.get(key).map_or(path, PathBuf::as_path);
+        (file, self.positions.get(key).map(|&(line, _)| line))
+    }
+
+    /// Record that keys without known file were defined in `path`
+    pub fn set_file(&mut self, path: &Path) {
+        for key in self.strings.keys().chain(self.lists.keys()) {
+            self.files.entry(key.clone()).or_insert_with(|| path.to_path_buf());
+        }
+    }
+
+    /// Document with only keys in namespace `prefix`, e.g. `errors.not-found` for `errors`.
+    /// Metadata not tied to keys, like `[number]`, is kept.
+    pub fn with_prefix(&self, prefix: &str) -> Document {
+        let matches = |key: &String| key.strip_prefix(prefix).is_some_and(|name| name.starts_with('.'));
+        let mut document = self.clone();
+        document.strings.retain(|key, _| matches(key));
+        document.lists.retain(|key, _| matches(key));
+        document.order.retain(matches);
+        document.positions.retain(|key, _| matches(key));
+        document.files.retain(|key, _| matches(key));
+        document.limits.retain(|key, _| matches(key));
+        document.html.retain(matches);
+        document.cfgs.retain(|key, _| matches(key));
+        document.defaults.retain(|key, _| matches(key));
+        document.args.retain(|key, _| matches(key));
+        document.cardinals.retain(|key, _| matches(key));
+        document.ordinals.retain(|key, _| matches(key));
+        document.fingerprints.retain(|key, _| matches(key));
+        document.targets.retain(|key, _| matches(key));
+        document
+    }
+
+    /// Messages of `[cardinal]` or `[ordinal]` table, depending on `kind`
+    pub(crate) fn plurals(&self, kind: PluralKind) -> &PluralMessages {
+        match kind {
+            PluralKind::Cardinal => &self.cardinals,
+            PluralKind::Ordinal => &self.ordinals,
+        }
+    }
+
+    /// Entries of `map`, which is `strings` or `lists` of this document, in order of
+    /// definition. Keys not defined in strings files, e.g. filled from the source
+    /// locale, come last, sorted.
+    pub fn in_order<'a, V>(&'a self, map: &'a BTreeMap<String, V>) -> Vec<(&'a String, &'a V)> {
+        let mut entries: Vec<_> = self.order.iter().filter_map(|key| map.get_key_value(key)).collect();
+        if entries.len() < map.len() {
+            let ordered: BTreeSet<&String> = self.order.iter().collect();
+            entries.extend(map.iter().filter(|(key, _)| !ordered.contains(key)));
+        }
+        entries
+    }
+}
+
+/// Keys with special meaning
+const RESERVED: &[&str] = &[
+    "limits", "html", "include", "number", "join", "cardinal", "ordinal", "plural", "fingerprints", "formats", "target",
+    "whitespace", "currency", "truncate",
+];
+
+/// Value of top-level entry. Strings and arrays of strings, which most entries are,
+/// are deserialized directly instead of building a `toml::Value` for them.
+enum EntryValue {
+    String(String),
+    List(Vec<String>),
+    Other(toml::Value),
+}
+
+impl EntryValue {
+    fn into_value(self) -> toml::Value {
+        match self {
+            Self::String(string) => toml::Value::String(string),
+            Self::List(list) => toml::Value::Array(list.into_iter().map(toml::Value::String).collect()),
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntryValueVisitor;
+
+        impl<'de> Visitor<'de> for EntryValueVisitor {
+            type Value = EntryValue;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a TOML value")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<EntryValue, E> {
+                Ok(EntryValue::String(value.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<EntryValue, E> {
+                Ok(EntryValue::String(value))
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<EntryValue, E> {
+                Ok(EntryValue::Other(toml::Value::Boolean(value)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<EntryValue, E> {
+                Ok(EntryValue::Other(toml::Value::Integer(value)))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<EntryValue, E> {
+                let value = i64::try_from(value).map_err(|_| E::custom("integer is too large"))?;
+                Ok(EntryValue::Other(toml::Value::Integer(value)))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<EntryValue, E> {
+                Ok(EntryValue::Other(toml::Value::Float(value)))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<EntryValue, A::Error> {
+                let mut list = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    match item {
+                        EntryValue::String(string) => list.push(string),
+                        // Mixed arrays are rare, so they are collected as values
+                        item => {
+                            let mut array: Vec<toml::Value> = list.into_iter().map(toml::Value::String).collect();
+                            array.push(item.into_value());
+                            array.extend(Vec::<toml::Value>::deserialize(SeqAccessDeserializer::new(seq))?);
+                            return Ok(EntryValue::Other(toml::Value::Array(array)));
+                        }
+                    }
                 }
-            })
-            .collect()
+                Ok(EntryValue::List(list))
+            }
+
+            // Also handles datetimes, which are maps with a special key
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<EntryValue, A::Error> {
+                toml::Value::deserialize(MapAccessDeserializer::new(map)).map(EntryValue::Other)
+            }
+        }
+
+        deserializer.deserialize_any(EntryValueVisitor)
+    }
+}
+
+/// Builds document from top-level entries as they are deserialized, so large files are
+/// never kept in memory as a `toml::Value` tree: only the input and the document are,
+/// plus values of tables like `[limits]`. The input is still read whole, since the TOML
+/// parser works on `&str`. Duplicate keys, which deserializing into a map would silently
+/// drop, are seen too.
+struct DocumentVisitor<'a> {
+    lines: LineIndex<'a>,
+    options: &'a ParseOptions,
+    /// Error of invalid entry, which can't be returned as deserializer error
+    error: &'a mut Option<Error>,
+}
+
+impl<'de> Visitor<'de> for DocumentVisitor<'_> {
+    type Value = Document;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a table")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Document, A::Error> {
+        let mut document = Document::default();
+        while let Some(key) = map.next_key::<toml::Spanned<String>>()? {
+            let value = map.next_value()?;
+            if let Err(error) = insert_entry(&mut document, &self.lines, self.options, key, value) {
+                *self.error = Some(error);
+                return Err(de::Error::custom("invalid entry"));
+            }
+        }
+        Ok(document)
+    }
+}
+
+/// Options affecting interpretation of strings file
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Accept integers, floats and booleans, converting them to strings
+    pub lenient_values: bool,
+    /// Join array values into a single string with this separator instead
+    /// of treating them as lists
+    pub join_arrays: Option<String>,
+    /// Mode of cleaning up whitespace for keys not listed in `[whitespace]` table
+    pub whitespace: Whitespace,
+    /// What to do with backslash sequences and control characters in values
+    pub escapes: Escapes,
+}
+
+/// Decode contents of strings file: UTF-8, optionally with BOM, or UTF-16 as saved
+/// by some Windows tools
+pub(crate) fn decode(mut bytes: Vec<u8>) -> Result<String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let pairs = bytes.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(Error::InvalidEncoding("UTF-16 file has odd length".to_string()));
+        }
+        let units: Vec<u16> = pairs.map(|pair| from_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16(&units)
+            .map_err(|_| Error::InvalidEncoding("invalid UTF-16, save the file as UTF-8".to_string()))
+    };
+    if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+        // Large files aren't copied to skip BOM
+        bytes.drain(..3);
+        return String::from_utf8(bytes).map_err(invalid_utf8);
+    }
+    match bytes.as_slice() {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        // UTF-16 without BOM: ASCII characters have a zero byte, which can't occur in TOML
+        [_, 0, _, 0, ..] => utf16(&bytes, u16::from_le_bytes),
+        [0, _, 0, _, ..] => utf16(&bytes, u16::from_be_bytes),
+        _ => String::from_utf8(bytes).map_err(invalid_utf8),
+    }
+}
+
+fn invalid_utf8(err: std::string::FromUtf8Error) -> Error {
+    let bytes = err.as_bytes();
+    let pos = err.utf8_error().valid_up_to();
+    let line = bytes[..pos].iter().filter(|&&b| b == b'\n').count() + 1;
+    Error::InvalidEncoding(format!(
+        "invalid UTF-8 at line {}, byte {}; save the file as UTF-8",
+        line, pos,
+    ))
+}
+
+/// Display float so that `3.0` stays `3.0` rather than becoming `3`
+fn float_to_string(float: f64) -> String {
+    if float.is_finite() && float.fract() == 0.0 {
+        format!("{:.1}", float)
     } else {
-        Err(TOMLStructureError)
+        float.to_string()
+    }
+}
+
+/// Convert scalar value to string, if it's allowed by `options`
+fn scalar_to_string(value: toml::Value, options: &ParseOptions) -> Result<String, toml::Value> {
+    match value {
+        toml::Value::String(string) => Ok(string),
+        toml::Value::Integer(int) if options.lenient_values => Ok(int.to_string()),
+        toml::Value::Float(float) if options.lenient_values => Ok(float_to_string(float)),
+        toml::Value::Boolean(boolean) if options.lenient_values => Ok(boolean.to_string()),
+        other => Err(other),
+    }
+}
+
+/// Offsets of line starts, so positions of keys in large files are found without
+/// scanning the input from the beginning for every key
+struct LineIndex<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(input: &'a str) -> Self {
+        let newlines = input.match_indices('\n').map(|(pos, _)| pos + 1);
+        Self { input, starts: std::iter::once(0).chain(newlines).collect() }
+    }
+
+    /// 1-based line and column of byte offset `pos`
+    fn line_col(&self, pos: usize) -> (usize, usize) {
+        let pos = pos.min(self.input.len());
+        let line = self.starts.partition_point(|&start| start <= pos);
+        let column = self.input[self.starts[line - 1]..pos].chars().count() + 1;
+        (line, column)
+    }
+}
+
+fn insert_string(document: &mut Document, key: String, string: String) -> Result<()> {
+    if let Err(detail) = parse_placeholders(&string) {
+        return Err(Error::InvalidPlaceholder { key, detail });
+    }
+    document.order.push(key.clone());
+    document.strings.insert(key, string);
+    Ok(())
+}
+
+/// Parse `[limits]` table of non-negative integers
+fn parse_limits(table: toml::value::Table) -> Result<Limits> {
+    table
+        .into_iter()
+        .map(|(key, value)| match value {
+            toml::Value::Integer(limit) if limit >= 0 => Ok((key, limit as usize)),
+            other => Err(Error::InvalidMetadata {
+                detail: format!("limit must be a non-negative integer, found {}", other.type_str()),
+                key,
+            }),
+        })
+        .collect()
+}
+
+/// Parse `[html]` table of booleans
+fn parse_html(table: toml::value::Table) -> Result<BTreeSet<String>> {
+    let mut html = BTreeSet::new();
+    for (key, value) in table {
+        match value {
+            toml::Value::Boolean(true) => {
+                html.insert(key);
+            }
+            toml::Value::Boolean(false) => {}
+            other => {
+                return Err(Error::InvalidMetadata {
+                    detail: format!("html must be a boolean, found {}", other.type_str()),
+                    key,
+                })
+            }
+        }
+    }
+    Ok(html)
+}
+
+/// Parse `[whitespace]` table of mode names
+fn parse_whitespace(table: toml::value::Table) -> Result<BTreeMap<String, Whitespace>> {
+    table
+        .into_iter()
+        .map(|(key, value)| match value.as_str().map(Whitespace::from_name) {
+            Some(Some(mode)) => Ok((key, mode)),
+            Some(None) => Err(Error::InvalidMetadata {
+                detail: format!("unknown whitespace mode `{}`", value.as_str().unwrap()),
+                key,
+            }),
+            None => Err(Error::InvalidMetadata {
+                detail: format!("whitespace must be a string, found {}", value.type_str()),
+                key,
+            }),
+        })
+        .collect()
+}
+
+/// Clean up whitespace of values according to `[whitespace]` table of `document`,
+/// using `default` mode for other keys
+pub(crate) fn apply_whitespace(document: &mut Document, default: Whitespace) -> Result<()> {
+    for key in document.whitespace.keys() {
+        if !document.strings.contains_key(key) && !document.lists.contains_key(key) {
+            return Err(Error::InvalidMetadata {
+                key: key.clone(),
+                detail: "whitespace is set for a key which isn't defined in the same file".to_string(),
+            });
+        }
+    }
+    if default == Whitespace::Keep && document.whitespace.is_empty() {
+        return Ok(());
+    }
+    let modes = document.whitespace.clone();
+    for_each_value(document, |key, value| {
+        *value = modes.get(key).copied().unwrap_or(default).apply(value);
+        Ok(())
+    })
+}
+
+/// Convert or forbid backslash sequences and control characters in values according to `escapes`
+pub(crate) fn apply_escapes(document: &mut Document, escapes: Escapes) -> Result<()> {
+    if escapes == Escapes::Keep {
+        return Ok(());
+    }
+    for_each_value(document, |key, value| {
+        *value = escapes.apply(value).map_err(|detail| Error::InvalidEscape {
+            key: key.to_string(),
+            detail,
+        })?;
+        Ok(())
+    })
+}
+
+/// Call `f` with key and value of every string, array item, target override and plural message
+fn for_each_value(document: &mut Document, mut f: impl FnMut(&str, &mut String) -> Result<()>) -> Result<()> {
+    for (key, value) in document.strings.iter_mut() {
+        f(key, value)?;
+    }
+    for (key, items) in document.lists.iter_mut() {
+        for item in items {
+            f(key, item)?;
+        }
+    }
+    for (key, overrides) in document.targets.iter_mut() {
+        for value in overrides.values_mut() {
+            f(key, value)?;
+        }
+    }
+    for (key, messages) in document.cardinals.iter_mut().chain(document.ordinals.iter_mut()) {
+        for message in messages.values_mut() {
+            f(key, message)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse table of separators like `[number]`, which may only set separators from `names`
+fn parse_separators(table: toml::value::Table, names: &[&str]) -> Result<BTreeMap<String, String>> {
+    table
+        .into_iter()
+        .map(|(key, value)| match value {
+            toml::Value::String(separator) if names.contains(&key.as_str()) => Ok((key, separator)),
+            toml::Value::String(_) => {
+                let names: Vec<String> = names.iter().map(|name| format!("`{}`", name)).collect();
+                Err(Error::InvalidMetadata {
+                    detail: format!("only {} separators can be set", names.join(", ")),
+                    key,
+                })
+            }
+            other => Err(Error::InvalidMetadata {
+                detail: format!("separator must be a string, found {}", other.type_str()),
+                key,
+            }),
+        })
+        .collect()
+}
+
+/// Parse `[cardinal]` or `[ordinal]` table of messages by plural category
+fn parse_plurals(table: toml::value::Table, kind: PluralKind) -> Result<PluralMessages> {
+    let mut plurals = PluralMessages::new();
+    for (key, value) in table {
+        let invalid = |detail: String| Error::InvalidMetadata { key: key.clone(), detail };
+        let categories = match value {
+            toml::Value::Table(categories) => categories,
+            other => return Err(invalid(format!("{} must be a table, found {}", kind.name(), other.type_str()))),
+        };
+        let mut messages = BTreeMap::new();
+        for (name, message) in categories {
+            let category = PluralCategory::from_name(&name)
+                .ok_or_else(|| invalid(format!("unknown plural category `{}`", name)))?;
+            let message = match message {
+                toml::Value::String(message) => message,
+                other => return Err(invalid(format!("message must be a string, found {}", other.type_str()))),
+            };
+            messages.insert(category, message);
+        }
+        check_plural(&key, kind, &messages)?;
+        plurals.insert(key, messages);
+    }
+    Ok(plurals)
+}
+
+/// Check that plural `messages` of `key` have `other` category and only use `{n}` placeholder
+pub(crate) fn check_plural(key: &str, kind: PluralKind, messages: &BTreeMap<PluralCategory, String>) -> Result<()> {
+    for message in messages.values() {
+        let placeholders = parse_placeholders(message)
+            .map_err(|detail| Error::InvalidPlaceholder { key: key.to_string(), detail })?;
+        if placeholders.iter().any(|placeholder| *placeholder != Placeholder::Named("n".to_string())) {
+            return Err(Error::InvalidPlaceholder {
+                key: key.to_string(),
+                detail: format!("{} messages may only use `{{n}}` placeholder", kind.name()),
+            });
+        }
+    }
+    if !messages.contains_key(&PluralCategory::Other) {
+        return Err(Error::InvalidMetadata {
+            key: key.to_string(),
+            detail: "missing `other` category".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Parse `[plural]` table with `cardinal` and `ordinal` tables of CLDR conditions by plural
+/// category into rules of `document`
+fn parse_plural_rules(table: toml::value::Table, document: &mut Document) -> Result<()> {
+    for (key, value) in table {
+        let invalid = |detail: String| Error::InvalidMetadata { key: key.clone(), detail };
+        let (conditions, rules) = match value {
+            toml::Value::Table(conditions) if key == "cardinal" => (conditions, &mut document.cardinal_rules),
+            toml::Value::Table(conditions) if key == "ordinal" => (conditions, &mut document.ordinal_rules),
+            toml::Value::Table(_) => return Err(invalid("only `cardinal` and `ordinal` rules can be set".to_string())),
+            other => return Err(invalid(format!("plural rules must be a table, found {}", other.type_str()))),
+        };
+        for (name, condition) in conditions {
+            let category = match PluralCategory::from_name(&name) {
+                Some(PluralCategory::Other) => {
+                    return Err(invalid("`other` is used when no rule matches and can't be set".to_string()))
+                }
+                Some(category) => category,
+                None => return Err(invalid(format!("unknown plural category `{}`", name))),
+            };
+            let condition = match condition {
+                toml::Value::String(condition) => condition,
+                other => return Err(invalid(format!("plural rule must be a string, found {}", other.type_str()))),
+            };
+            crate::plural::parse_condition(&condition).map_err(invalid)?;
+            rules.insert(category, condition);
+        }
+    }
+    Ok(())
+}
+
+/// Parse `[currency]` table with required `symbol` and optional `position`, `space` and `digits`
+fn parse_currency(table: toml::value::Table) -> Result<Currency> {
+    let mut currency = Currency {
+        symbol: String::new(),
+        position: SymbolPosition::Before,
+        space: false,
+        digits: 2,
+    };
+    let mut symbol = None;
+    for (key, value) in table {
+        let invalid = |detail: String| Error::InvalidMetadata { key: key.clone(), detail };
+        match (key.as_str(), value) {
+            ("symbol", toml::Value::String(value)) => symbol = Some(value),
+            ("position", toml::Value::String(value)) => {
+                currency.position = SymbolPosition::from_name(&value)
+                    .ok_or_else(|| invalid(format!("position must be `before` or `after`, found `{}`", value)))?;
+            }
+            ("space", toml::Value::Boolean(value)) => currency.space = value,
+            ("digits", toml::Value::Integer(value)) if value >= 0 => currency.digits = value as usize,
+            ("symbol" | "position", other) => return Err(invalid(format!("must be a string, found {}", other.type_str()))),
+            ("space", other) => return Err(invalid(format!("must be a boolean, found {}", other.type_str()))),
+            ("digits", other) => return Err(invalid(format!("must be a non-negative integer, found {}", other.type_str()))),
+            _ => return Err(invalid("only `symbol`, `position`, `space` and `digits` can be set".to_string())),
+        }
+    }
+    currency.symbol = symbol.ok_or_else(|| Error::InvalidMetadata {
+        key: "currency".to_string(),
+        detail: "missing `symbol`".to_string(),
+    })?;
+    Ok(currency)
+}
+
+/// Parse `[fingerprints]` table of strings
+fn parse_fingerprints(table: toml::value::Table) -> Result<BTreeMap<String, String>> {
+    table
+        .into_iter()
+        .map(|(key, value)| match value {
+            toml::Value::String(fingerprint) => Ok((key, fingerprint)),
+            other => Err(Error::InvalidMetadata {
+                detail: format!("fingerprint must be a string, found {}", other.type_str()),
+                key,
+            }),
+        })
+        .collect()
+}
+
+/// Parse `[formats]` table of strings, checking that names map to distinct constants
+fn parse_formats(table: toml::value::Table) -> Result<BTreeMap<String, String>> {
+    let mut formats = BTreeMap::new();
+    let mut constants = BTreeMap::new();
+    for (key, value) in table {
+        let pattern = match value {
+            toml::Value::String(pattern) => pattern,
+            other => {
+                return Err(Error::InvalidMetadata {
+                    detail: format!("format must be a string, found {}", other.type_str()),
+                    key,
+                })
+            }
+        };
+        if let Some(other) = constants.insert(format_constant(&key), key.clone()) {
+            return Err(Error::InvalidMetadata {
+                detail: format!("format has the same constant name as `{}`", other),
+                key,
+            });
+        }
+        formats.insert(key, pattern);
+    }
+    Ok(formats)
+}
+
+/// Parse `[target.'cfg(...)']` tables of strings replacing base values on matching targets
+fn parse_targets(table: toml::value::Table) -> Result<Targets> {
+    let mut targets = Targets::new();
+    for (name, overrides) in table {
+        let invalid = |detail: String| Error::InvalidMetadata {
+            key: format!("target.{}", name),
+            detail,
+        };
+        let predicate = name
+            .trim()
+            .strip_prefix("cfg(")
+            .and_then(|predicate| predicate.strip_suffix(')'))
+            .ok_or_else(|| invalid("target must be written as `cfg(predicate)`".to_string()))?;
+        crate::cfg::parse(predicate).map_err(invalid)?;
+        let overrides = match overrides {
+            toml::Value::Table(overrides) => overrides,
+            other => return Err(invalid(format!("target must be a table, found {}", other.type_str()))),
+        };
+        for (key, value) in overrides {
+            let value = match value {
+                toml::Value::String(value) => value,
+                other => return Err(invalid(format!("value of `{}` must be a string, found {}", key, other.type_str()))),
+            };
+            if let Err(detail) = parse_placeholders(&value) {
+                return Err(Error::InvalidPlaceholder { key, detail });
+            }
+            targets.entry(key).or_default().insert(predicate.trim().to_string(), value);
+        }
+    }
+    Ok(targets)
+}
+
+/// Name of constant generated for format `name`: `FORMAT_SHORT_DATE` for `short-date`
+pub(crate) fn format_constant(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("FORMAT_{}", name)
+}
+
+/// Check that static text of HTML message is safe
+pub(crate) fn validate_html(key: &str, value: &str) -> Result<()> {
+    crate::html::validate(value).map_err(|detail| Error::UnsafeHtml {
+        key: key.to_string(),
+        detail,
+    })
+}
+
+/// Parts of `{ value = ..., cfg = "...", defaults = { ... }, args = [...] }` table
+struct ValueTable {
+    value: toml::Value,
+    cfg: Option<String>,
+    defaults: Option<BTreeMap<String, String>>,
+    args: Option<Vec<String>>,
+}
+
+/// Split `{ value = ..., cfg = "...", defaults = { ... }, args = [...] }` table into its parts
+fn parse_value_table(key: &str, mut table: toml::value::Table) -> Result<ValueTable> {
+    let invalid = |detail: String| Error::InvalidMetadata {
+        key: key.to_string(),
+        detail,
+    };
+    if let Some(unknown) = table.keys().find(|name| !["value", "cfg", "defaults", "args"].contains(&name.as_str())) {
+        return Err(invalid(format!("unknown field `{}`", unknown)));
+    }
+    let cfg = match table.remove("cfg") {
+        Some(toml::Value::String(predicate)) => {
+            crate::cfg::parse(&predicate).map_err(invalid)?;
+            Some(predicate)
+        }
+        Some(other) => return Err(invalid(format!("cfg must be a string, found {}", other.type_str()))),
+        None => None,
+    };
+    let defaults = match table.remove("defaults") {
+        Some(toml::Value::Table(defaults)) => Some(
+            defaults
+                .into_iter()
+                .map(|(name, value)| match value {
+                    toml::Value::String(value) => Ok((name, value)),
+                    other => Err(invalid(format!("default of `{{{}}}` must be a string, found {}", name, other.type_str()))),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Some(other) => return Err(invalid(format!("defaults must be a table, found {}", other.type_str()))),
+        None => None,
+    };
+    let args = match table.remove("args") {
+        Some(toml::Value::Array(args)) => Some(
+            args.into_iter()
+                .map(|arg| match arg {
+                    toml::Value::String(arg) if is_identifier(&arg) => Ok(arg),
+                    toml::Value::String(arg) => Err(invalid(format!("argument `{}` isn't an identifier", arg))),
+                    other => Err(invalid(format!("args must be strings, found {}", other.type_str()))),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Some(other) => return Err(invalid(format!("args must be an array, found {}", other.type_str()))),
+        None => None,
+    };
+    let value = table.remove("value").ok_or_else(|| invalid("missing `value`".to_string()))?;
+    Ok(ValueTable { value, cfg, defaults, args })
+}
+
+/// Check that `defaults` give values to exactly the placeholders of `value`, which
+/// must all be named
+fn validate_defaults(key: &str, value: &str, defaults: &BTreeMap<String, String>) -> Result<()> {
+    let invalid = |detail: String| Error::InvalidMetadata {
+        key: key.to_string(),
+        detail,
+    };
+    let placeholders = parse_placeholders(value).map_err(|detail| Error::InvalidPlaceholder {
+        key: key.to_string(),
+        detail,
+    })?;
+    for placeholder in &placeholders {
+        match placeholder {
+            Placeholder::Named(name) if defaults.contains_key(name) => {}
+            Placeholder::Named(_) => return Err(invalid(format!("placeholder `{}` has no default", placeholder))),
+            _ => return Err(invalid(format!("keys with defaults can't have positional placeholder `{}`", placeholder))),
+        }
+    }
+    if let Some(name) = defaults.keys().find(|name| !placeholders.contains(&Placeholder::Named(name.to_string()))) {
+        return Err(invalid(format!("default is set for `{{{}}}`, which isn't used in value", name)));
+    }
+    // Defaults are substituted as is when generating code
+    if let Some(placeholder) = with_specs(value).first() {
+        return Err(invalid(format!("keys with defaults can't have format spec in `{}`", placeholder)));
+    }
+    Ok(())
+}
+
+pub fn parse_toml(toml: &str, options: &ParseOptions) -> Result<Document> {
+    let toml = toml.strip_prefix('\u{feff}').unwrap_or(toml);
+    let mut error = None;
+    let mut deserializer = toml::Deserializer::new(toml);
+    let visitor = DocumentVisitor { lines: LineIndex::new(toml), options, error: &mut error };
+    let mut document = match deserializer.deserialize_map(visitor) {
+        Ok(document) => document,
+        Err(err) => return Err(error.unwrap_or_else(|| err.into())),
+    };
+    apply_whitespace(&mut document, options.whitespace)?;
+    apply_escapes(&mut document, options.escapes)?;
+    // Metadata of documents with includes may refer to included keys
+    if document.includes.is_empty() {
+        validate_metadata(&document)?;
+    }
+    Ok(document)
+}
+
+/// Add array value `list` to `document`, joining it into a string with [`ParseOptions::join_arrays`]
+fn insert_list(document: &mut Document, key: String, list: Vec<String>, options: &ParseOptions) -> Result<()> {
+    match &options.join_arrays {
+        Some(separator) => insert_string(document, key, list.join(separator)),
+        None => {
+            document.order.push(key.clone());
+            document.lists.insert(key, list);
+            Ok(())
+        }
+    }
+}
+
+/// Add top-level entry of document to `document`
+fn insert_entry(
+    document: &mut Document,
+    lines: &LineIndex<'_>,
+    options: &ParseOptions,
+    key: toml::Spanned<String>,
+    value: EntryValue,
+) -> Result<()> {
+    let position = lines.line_col(key.start());
+    let key = key.into_inner();
+    if key.is_empty() {
+        return Err(Error::EmptyKey { position });
+    }
+    if let Some(&first) = document.positions.get(&key) {
+        return Err(Error::DuplicateKey {
+            key,
+            first,
+            second: position,
+        });
+    }
+    document.positions.insert(key.clone(), position);
+    let non_string = |value: toml::Value, key: String| Error::NonStringValue {
+        key,
+        found_type: value.type_str(),
+        position,
+    };
+    let value = match value {
+        EntryValue::String(string) => return insert_string(document, key, string),
+        EntryValue::List(list) if key == "include" => {
+            document.includes = list;
+            return Ok(());
+        }
+        EntryValue::List(list) => return insert_list(document, key, list, options),
+        EntryValue::Other(toml::Value::Table(table)) if table.contains_key("value") && !RESERVED.contains(&key.as_str()) => {
+            let table = parse_value_table(&key, table)?;
+            if let Some(cfg) = table.cfg {
+                document.cfgs.insert(key.clone(), cfg);
+            }
+            if let Some(defaults) = table.defaults {
+                match &table.value {
+                    toml::Value::String(value) => validate_defaults(&key, value, &defaults)?,
+                    _ => {
+                        return Err(Error::InvalidMetadata {
+                            key,
+                            detail: "defaults can only be set for strings".to_string(),
+                        })
+                    }
+                }
+                document.defaults.insert(key.clone(), defaults);
+            }
+            if let Some(args) = table.args {
+                if !table.value.is_str() {
+                    return Err(Error::InvalidMetadata {
+                        key,
+                        detail: "args can only be declared for strings".to_string(),
+                    });
+                }
+                document.args.insert(key.clone(), args);
+            }
+            table.value
+        }
+        EntryValue::Other(value) => value,
+    };
+    match value {
+        toml::Value::Table(table) if key == "limits" => {
+            document.limits = parse_limits(table)?;
+        }
+        toml::Value::Table(table) if key == "html" => {
+            document.html = parse_html(table)?;
+        }
+        toml::Value::Table(table) if key == "number" => {
+            document.number = parse_separators(table, &["decimal", "group"])?;
+        }
+        toml::Value::Table(table) if key == "join" => {
+            document.join = parse_separators(table, &["separator", "last", "pair"])?;
+        }
+        toml::Value::Table(table) if key == "truncate" => {
+            document.truncate = parse_separators(table, &["ellipsis"])?;
+        }
+        toml::Value::Table(table) if key == "cardinal" => {
+            document.cardinals = parse_plurals(table, PluralKind::Cardinal)?;
+        }
+        toml::Value::Table(table) if key == "ordinal" => {
+            document.ordinals = parse_plurals(table, PluralKind::Ordinal)?;
+        }
+        toml::Value::Table(table) if key == "plural" => {
+            parse_plural_rules(table, document)?;
+        }
+        toml::Value::Table(table) if key == "fingerprints" => {
+            document.fingerprints = parse_fingerprints(table)?;
+        }
+        toml::Value::Table(table) if key == "formats" => {
+            document.formats = parse_formats(table)?;
+        }
+        toml::Value::Table(table) if key == "target" => {
+            document.targets = parse_targets(table)?;
+        }
+        toml::Value::Table(table) if key == "currency" => {
+            document.currency = Some(parse_currency(table)?);
+        }
+        toml::Value::Table(table) if key == "whitespace" => {
+            document.whitespace = parse_whitespace(table)?;
+        }
+        toml::Value::Array(array) if key == "include" => {
+            document.includes = array
+                .into_iter()
+                .map(|item| match item {
+                    toml::Value::String(path) => Ok(path),
+                    other => Err(non_string(other, key.clone())),
+                })
+                .collect::<Result<_>>()?;
+        }
+        toml::Value::Array(array) => {
+            let list = array
+                .into_iter()
+                .map(|item| scalar_to_string(item, options))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|item| non_string(item, key.clone()))?;
+            insert_list(document, key, list, options)?;
+        }
+        value => {
+            let string = scalar_to_string(value, options).map_err(|v| non_string(v, key.clone()))?;
+            insert_string(document, key, string)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that `[limits]` and `[html]` refer to existing keys and HTML values are safe
+pub(crate) fn validate_metadata(document: &Document) -> Result<()> {
+    for key in document.limits.keys() {
+        if !document.strings.contains_key(key) && !document.lists.contains_key(key) {
+            return Err(Error::InvalidMetadata {
+                key: key.clone(),
+                detail: "limit is set for a key which isn't defined".to_string(),
+            });
+        }
+    }
+    for key in &document.html {
+        match document.strings.get(key) {
+            Some(value) => validate_html(key, value)?,
+            None => {
+                return Err(Error::InvalidMetadata {
+                    key: key.clone(),
+                    detail: "html is set for a key which isn't a string".to_string(),
+                })
+            }
+        }
+    }
+    for (key, overrides) in &document.targets {
+        let invalid = |detail: &str| Error::InvalidMetadata {
+            key: key.clone(),
+            detail: detail.to_string(),
+        };
+        let base = document
+            .strings
+            .get(key)
+            .ok_or_else(|| invalid("target override is set for a key which isn't a string"))?;
+        if document.cfgs.contains_key(key) {
+            return Err(invalid("keys with `cfg` can't have target overrides"));
+        }
+        let placeholders = |value: &str| -> BTreeSet<Placeholder> {
+            parse_placeholders(value).unwrap_or_default().into_iter().collect()
+        };
+        for value in overrides.values() {
+            if placeholders(value) != placeholders(base) {
+                return Err(invalid("target override must have the same placeholders as the base value"));
+            }
+            if document.html.contains(key) {
+                validate_html(key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        text.encode_utf16().flat_map(to_bytes).collect()
+    }
+
+    #[test]
+    fn decode_utf8() {
+        assert_eq!(decode(b"ok = \"OK\"".to_vec()).unwrap(), "ok = \"OK\"");
+        assert_eq!(decode("ok = \"Grüß\"".as_bytes().to_vec()).unwrap(), "ok = \"Grüß\"");
+        assert_eq!(decode(Vec::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn decode_utf8_bom() {
+        let mut bytes = vec![0xef, 0xbb, 0xbf];
+        bytes.extend_from_slice(b"ok = \"OK\"");
+        assert_eq!(decode(bytes).unwrap(), "ok = \"OK\"");
+    }
+
+    #[test]
+    fn decode_utf16_bom() {
+        let text = "ok = \"Grüß 🎉\"";
+        let mut le = vec![0xff, 0xfe];
+        le.extend(utf16(text, u16::to_le_bytes));
+        assert_eq!(decode(le).unwrap(), text);
+        let mut be = vec![0xfe, 0xff];
+        be.extend(utf16(text, u16::to_be_bytes));
+        assert_eq!(decode(be).unwrap(), text);
+    }
+
+    #[test]
+    fn decode_utf16_without_bom() {
+        let text = "ok = \"OK\"";
+        assert_eq!(decode(utf16(text, u16::to_le_bytes)).unwrap(), text);
+        assert_eq!(decode(utf16(text, u16::to_be_bytes)).unwrap(), text);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(matches!(decode(vec![b'o', b'k', 0xff, 0xfe]), Err(Error::InvalidEncoding(_))));
+        // Odd length
+        assert!(matches!(decode(vec![0xff, 0xfe, b'o']), Err(Error::InvalidEncoding(_))));
+        // Unpaired surrogate
+        assert!(matches!(decode(vec![0xff, 0xfe, 0x00, 0xd8, b'o', 0]), Err(Error::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn decoded_utf16_parses() {
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(utf16("ok = \"OK\"\n", u16::to_le_bytes));
+        let document = parse_toml(&decode(bytes).unwrap(), &ParseOptions::default()).unwrap();
+        assert_eq!(document.strings["ok"], "OK");
+    }
+
+    #[test]
+    fn defaults_without_specs() {
+        let parse = |toml: &str| parse_toml(toml, &ParseOptions::default());
+        let document = parse(r#"greeting = { value = "Hello, {name}!", defaults = { name = "friend" } }"#).unwrap();
+        assert_eq!(document.defaults["greeting"]["name"], "friend");
+        let error = parse(r#"greeting = { value = "Hello, {name:>10}!", defaults = { name = "friend" } }"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid metadata for key `greeting`: keys with defaults can't have format spec in `{name:>10}`",
+        );
+    }
+
+    #[test]
+    fn plural_tables() {
+        let toml = r#"
+            [plural.cardinal]
+            one = "n = 1"
+
+            [cardinal]
+            files = { one = "{n} file", other = "{n} files" }
+
+            [ordinal]
+            place = { other = "{n}." }
+        "#;
+        let document = parse_toml(toml, &ParseOptions::default()).unwrap();
+        assert_eq!(document.cardinals["files"][&PluralCategory::One], "{n} file");
+        assert_eq!(document.cardinal_rules[&PluralCategory::One], "n = 1");
+        assert_eq!(document.plurals(PluralKind::Ordinal)["place"][&PluralCategory::Other], "{n}.");
+        assert!(document.ordinal_rules.is_empty());
+        let invalid = [
+            "[cardinal]\nfiles = { one = \"{n} file\" }",
+            "[cardinal]\nfiles = { other = \"{count} files\" }",
+            "[plural.decimal]\none = \"n = 1\"",
+            "[plural.cardinal]\nother = \"n = 1\"",
+        ];
+        for toml in invalid {
+            assert!(parse_toml(toml, &ParseOptions::default()).is_err(), "{}", toml);
+        }
+    }
+
+    #[test]
+    fn large_document() {
+        let mut toml = String::new();
+        for idx in 0..20_000 {
+            toml.push_str(&format!("key-{} = \"Value {{name}} {}\"\n", idx, idx));
+            toml.push_str(&format!("list-{} = [\"First {}\", \"Second\"]\n", idx, idx));
+        }
+        let document = parse_toml(&toml, &ParseOptions::default()).unwrap();
+        assert_eq!(document.strings.len(), 20_000);
+        assert_eq!(document.lists.len(), 20_000);
+        assert_eq!(document.strings["key-19999"], "Value {name} 19999");
+        assert_eq!(document.lists["list-19999"], ["First 19999", "Second"]);
+        assert_eq!(document.positions["list-19999"], (40_000, 1));
+    }
+
+    #[test]
+    fn mixed_arrays() {
+        let options = ParseOptions { lenient_values: true, ..ParseOptions::default() };
+        let document = parse_toml(r#"mixed = ["a", 1, "b", true]"#, &options).unwrap();
+        assert_eq!(document.lists["mixed"], ["a", "1", "b", "true"]);
+        assert!(parse_toml(r#"mixed = ["a", 1]"#, &ParseOptions::default()).is_err());
+        assert!(parse_toml(r#"nested = ["a", ["b"]]"#, &options).is_err());
+        let options = ParseOptions { join_arrays: Some(", ".to_string()), ..ParseOptions::default() };
+        assert_eq!(parse_toml(r#"joined = ["a", "b"]"#, &options).unwrap().strings["joined"], "a, b");
     }
 }
@@ -0,0 +1,128 @@
+use std::borrow::Cow;
+
+use crate::negotiate::{language, normalize};
+
+/// Ellipsis used by languages not listed below
+const DEFAULT_ELLIPSIS: &str = "…";
+
+/// Ellipsis commonly used in locale with given BCP-47 tag, e.g. `……` in Chinese.
+/// Other locales use `…`.
+///
+/// ```
+/// assert_eq!(ctl10n::ellipsis_for_locale("zh-Hans"), "……");
+/// assert_eq!(ctl10n::ellipsis_for_locale("en-US"), "…");
+/// ```
+pub fn ellipsis_for_locale(tag: &str) -> &'static str {
+    match language(&normalize(tag)) {
+        "zh" => "……",
+        _ => DEFAULT_ELLIPSIS,
+    }
+}
+
+/// Characters which extend the preceding grapheme cluster: common combining marks, joiners,
+/// variation selectors, emoji modifiers and tags, and Hangul vowel and final jamo
+const EXTEND: &[(char, char)] = &[
+    ('\u{300}', '\u{36f}'),
+    ('\u{483}', '\u{489}'),
+    ('\u{591}', '\u{5bd}'),
+    ('\u{5bf}', '\u{5c7}'),
+    ('\u{610}', '\u{61a}'),
+    ('\u{64b}', '\u{65f}'),
+    ('\u{670}', '\u{670}'),
+    ('\u{6d6}', '\u{6ed}'),
+    ('\u{900}', '\u{903}'),
+    ('\u{93a}', '\u{93c}'),
+    ('\u{93e}', '\u{94f}'),
+    ('\u{951}', '\u{957}'),
+    ('\u{962}', '\u{963}'),
+    ('\u{981}', '\u{983}'),
+    ('\u{9bc}', '\u{9bc}'),
+    ('\u{9be}', '\u{9cd}'),
+    ('\u{e31}', '\u{e31}'),
+    ('\u{e34}', '\u{e3a}'),
+    ('\u{e47}', '\u{e4e}'),
+    ('\u{1160}', '\u{11ff}'),
+    ('\u{1ab0}', '\u{1aff}'),
+    ('\u{1dc0}', '\u{1dff}'),
+    ('\u{200c}', '\u{200d}'),
+    ('\u{20d0}', '\u{20ff}'),
+    ('\u{3099}', '\u{309a}'),
+    ('\u{fe00}', '\u{fe0f}'),
+    ('\u{fe20}', '\u{fe2f}'),
+    ('\u{1f3fb}', '\u{1f3ff}'),
+    ('\u{e0020}', '\u{e007f}'),
+    ('\u{e0100}', '\u{e01ef}'),
+];
+
+const ZWJ: char = '\u{200d}';
+
+fn is_extend(c: char) -> bool {
+    EXTEND.iter().any(|&(start, end)| (start..=end).contains(&c))
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// Byte positions where grapheme clusters of `text` start, followed by `text.len()`.
+///
+/// This approximates extended grapheme clusters of Unicode without its tables: combining
+/// marks, emoji joined with ZWJ, emoji with modifiers or variation selectors, flags and
+/// `\r\n` are kept together, which covers text of translations.
+fn boundaries(text: &str) -> Vec<usize> {
+    let mut boundaries = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut indicators = 0;
+    for (pos, c) in text.char_indices() {
+        let joined = match previous {
+            None => false,
+            Some(previous) => {
+                (previous == '\r' && c == '\n')
+                    || is_extend(c)
+                    || previous == ZWJ
+                    || (is_regional_indicator(c) && indicators % 2 == 1)
+            }
+        };
+        indicators = if is_regional_indicator(c) { indicators + 1 } else { 0 };
+        if !joined {
+            boundaries.push(pos);
+        }
+        previous = Some(c);
+    }
+    boundaries.push(text.len());
+    boundaries
+}
+
+/// Number of grapheme clusters of `text`, as counted by [`truncate`]
+pub fn grapheme_count(text: &str) -> usize {
+    boundaries(text).len() - 1
+}
+
+/// Shorten `text` to at most `max` grapheme clusters, ending it with `ellipsis` if it
+/// was cut. Clusters like emoji and letters with combining marks are never split and
+/// whitespace before the ellipsis is dropped. Used by `tr_trunc!()`.
+///
+/// Clusters are found by an approximation of Unicode segmentation rules, which doesn't
+/// need Unicode tables: combining marks of Latin, Greek, Cyrillic, Hebrew, Arabic,
+/// Devanagari, Bengali and Thai, Hangul jamo, kana voicing marks, joiners, variation
+/// selectors, emoji modifiers and tags, flags and `\r\n` are kept together. Marks of other
+/// scripts, e.g. Tamil or Tibetan vowel signs, count as separate clusters, so text in
+/// those scripts may be cut between a letter and its mark.
+///
+/// ```
+/// assert_eq!(ctl10n::truncate("Hello, world!", 6, "…"), "Hello…");
+/// assert_eq!(ctl10n::truncate("👩‍👩‍👧 family", 3, "…"), "👩‍👩‍👧…");
+/// assert_eq!(ctl10n::truncate("Short", 10, "…"), "Short");
+/// ```
+pub fn truncate<'a>(text: &'a str, max: usize, ellipsis: &str) -> Cow<'a, str> {
+    let boundaries = boundaries(text);
+    if boundaries.len() - 1 <= max {
+        return Cow::Borrowed(text);
+    }
+    let ellipsis_len = grapheme_count(ellipsis);
+    if max < ellipsis_len {
+        return Cow::Borrowed(&text[..boundaries[max]]);
+    }
+    let kept = text[..boundaries[max - ellipsis_len]].trim_end();
+    Cow::Owned(format!("{}{}", kept, ellipsis))
+}
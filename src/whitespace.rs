@@ -0,0 +1,78 @@
+/// How whitespace of values is cleaned up during generation, so long messages can be
+/// written as indented multi-line TOML strings. Every mode also does what the previous
+/// ones do.
+///
+/// ```toml
+/// about = """
+///     ctl10n embeds strings
+///     into the binary.
+///
+///     Without runtime files.
+///     """
+/// ```
+///
+/// With [`Whitespace::Collapse`] `about` is `ctl10n embeds strings into the binary.\n\nWithout runtime files.`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    /// Values are used as written
+    #[default]
+    Keep,
+    /// Indentation common to all non-blank lines is removed
+    Dedent,
+    /// Whitespace at the start and end of value and at the ends of lines is removed
+    Trim,
+    /// Lines of a paragraph are joined with spaces and paragraphs are separated
+    /// by a single blank line
+    Collapse,
+}
+
+impl Whitespace {
+    /// Mode with given lowercase name, e.g. `dedent`
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Self::Keep),
+            "dedent" => Some(Self::Dedent),
+            "trim" => Some(Self::Trim),
+            "collapse" => Some(Self::Collapse),
+            _ => None,
+        }
+    }
+
+    /// `value` with whitespace cleaned up according to this mode
+    pub(crate) fn apply(self, value: &str) -> String {
+        match self {
+            Self::Keep => value.to_string(),
+            Self::Dedent => dedent(value),
+            Self::Trim => {
+                let lines: Vec<&str> = value.split('\n').map(str::trim_end).collect();
+                dedent(&lines.join("\n")).trim().to_string()
+            }
+            Self::Collapse => {
+                let trimmed = Self::Trim.apply(value);
+                let paragraphs: Vec<String> = trimmed
+                    .split("\n\n")
+                    .map(|paragraph| paragraph.split('\n').map(str::trim).filter(|line| !line.is_empty()))
+                    .map(|lines| lines.collect::<Vec<_>>().join(" "))
+                    .filter(|paragraph| !paragraph.is_empty())
+                    .collect();
+                paragraphs.join("\n\n")
+            }
+        }
+    }
+}
+
+/// Remove indentation common to all non-blank lines of `value`. Blank lines are emptied.
+fn dedent(value: &str) -> String {
+    let indentation = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let common = value
+        .split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(indentation)
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = value
+        .split('\n')
+        .map(|line| if line.trim().is_empty() { "" } else { &line[common..] })
+        .collect();
+    lines.join("\n")
+}